@@ -0,0 +1,125 @@
+use crate::error::Error;
+use alloc::vec::Vec;
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    high_level::{load_cell_data, load_script, QueryIter},
+};
+use common::freshness::{fresh_value, FreshnessError, FreshnessPolicy};
+use common::reader::{load_oracle_cell_deps, load_since_reference, ReaderError};
+use core::result::Result;
+
+// Expiry type args: index_state_type_hash(32 bytes) | info_type_code_hash(32 bytes) | grace_period(u64)
+// | max_age(u64)
+const HASH_LEN: usize = 32;
+const EXPIRY_ARGS_LEN: usize = HASH_LEN * 2 + 8 * 2;
+// Expiry cell data: expired_at(u64) | account data
+const EXPIRED_AT_LEN: usize = 8;
+
+pub fn main() -> Result<(), Error> {
+    let args: Bytes = load_script()?.args().unpack();
+    if args.len() != EXPIRY_ARGS_LEN {
+        return Err(Error::InvalidArgument);
+    }
+
+    let inputs_data: Vec<Vec<u8>> = QueryIter::new(load_cell_data, Source::GroupInput).collect();
+    let outputs_data: Vec<Vec<u8>> = QueryIter::new(load_cell_data, Source::GroupOutput).collect();
+    match (inputs_data.len(), outputs_data.len()) {
+        // Create the accounts
+        (0, _) => outputs_data.iter().try_for_each(check_expiry_data_len),
+        // Accounts can be recycled
+        (_, 0) => Ok(()),
+        // Update the account, the cells of the group can't be told apart, so only one account can be
+        // updated by a transaction, or an expired one could take the place of another one
+        (1, 1) => {
+            check_expiry_data_len(&inputs_data[0])?;
+            check_expiry_data_len(&outputs_data[0])?;
+            check_transition(&args, &inputs_data[0], &outputs_data[0])
+        }
+        _ => Err(Error::MultipleExpiryCells),
+    }
+}
+
+fn check_expiry_data_len(data: &Vec<u8>) -> Result<(), Error> {
+    if data.len() < EXPIRED_AT_LEN {
+        return Err(Error::ExpiryDataLenError);
+    }
+    Ok(())
+}
+
+fn expired_at_from_data(data: &Vec<u8>) -> u64 {
+    let mut expired_at_buf = [0u8; EXPIRED_AT_LEN];
+    expired_at_buf.copy_from_slice(&data[..EXPIRED_AT_LEN]);
+    u64::from_be_bytes(expired_at_buf)
+}
+
+// Before expiry the account can be updated freely, in the grace period it can only be renewed,
+// and after the grace period it can only be recycled.
+fn check_transition(
+    args: &Bytes,
+    input_data: &Vec<u8>,
+    output_data: &Vec<u8>,
+) -> Result<(), Error> {
+    let input_expired_at = expired_at_from_data(input_data);
+    let output_expired_at = expired_at_from_data(output_data);
+    if output_expired_at < input_expired_at {
+        return Err(Error::ExpiredAtDecreased);
+    }
+
+    let u64_at = |pos: usize| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&args[pos..pos + 8]);
+        u64::from_be_bytes(buf)
+    };
+    let grace_period = u64_at(HASH_LEN * 2);
+    let max_age = u64_at(HASH_LEN * 2 + 8);
+
+    let oracle = load_oracle_cell_deps(&args[..HASH_LEN], &args[HASH_LEN..HASH_LEN * 2])
+        .map_err(reader_error)?;
+    let now = oracle.latest_timestamp().map_err(reader_error)?;
+
+    // A stopped oracle would keep the account before its expiry forever, so the latest timestamp
+    // must not be older than the max age at the since of the expiry input
+    let policy = FreshnessPolicy {
+        max_age,
+        halted_age: max_age,
+    };
+    let reference = load_since_reference().map_err(reader_error)?;
+    fresh_value(&oracle.latest().map_err(reader_error)?, reference, &policy)
+        .map_err(freshness_error)?;
+
+    if now < input_expired_at {
+        Ok(())
+    } else if now < input_expired_at.saturating_add(grace_period) {
+        let is_renewal = output_expired_at > input_expired_at
+            && output_data[EXPIRED_AT_LEN..] == input_data[EXPIRED_AT_LEN..];
+        if !is_renewal {
+            return Err(Error::OnlyRenewalInGracePeriod);
+        }
+        Ok(())
+    } else {
+        Err(Error::AccountExpired)
+    }
+}
+
+fn reader_error(err: ReaderError) -> Error {
+    match err {
+        ReaderError::Syscall => Error::ItemMissing,
+        ReaderError::IndexStateNotFound => Error::IndexStateNotExist,
+        ReaderError::InfoNotFound => Error::InfoNotExist,
+        ReaderError::IndexStateNotUnique
+        | ReaderError::InfoNotUnique
+        | ReaderError::History(_)
+        | ReaderError::InvalidInfoProof => Error::InvalidOracleData,
+        ReaderError::InvalidDataType => Error::InvalidDataType,
+    }
+}
+
+fn freshness_error(err: FreshnessError) -> Error {
+    match err {
+        FreshnessError::InvalidContent => Error::InvalidDataType,
+        // The oracle is a timestamp one already, so only the since can mismatch it
+        FreshnessError::InvalidSince | FreshnessError::MetricMismatch => Error::InvalidSince,
+        FreshnessError::NotFresh(_) => Error::OracleNotFresh,
+    }
+}
@@ -1,7 +1,16 @@
 use crate::error::Error;
 use alloc::vec::Vec;
-use ckb_std::{ckb_constants::Source, ckb_types::packed::*, high_level};
-use common::constants::*;
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, packed::*, prelude::*},
+    high_level,
+};
+use common::{
+    constants::*,
+    history::extend_history_root,
+    index_state::{IndexState, IndexStateError},
+    merkle::{info_root, root_from_proof},
+};
 use core::result::Result;
 
 pub fn main() -> Result<(), Error> {
@@ -20,45 +29,261 @@ pub fn main() -> Result<(), Error> {
     }
     // Create the index state cell and the input type script doesn't exist
     else {
-        load_output_type_script(|_| {
-            let _ = check_index_state_cell_data(Source::GroupOutput)?;
-            Ok(())
+        load_output_type_script(|output_type_script| {
+            let index_state = check_index_state_cell_data(Source::GroupOutput)?;
+            if index_state.version >= INDEX_STATE_VERSION_UNIQUE {
+                check_type_id_args(&output_type_script)?;
+                check_unique_output()?;
+            }
+            // The sequence counts the updates since creation
+            if matches!(index_state.sequence, Some(sequence) if sequence != 0) {
+                return Err(Error::IndexStateSequenceError);
+            }
+            if index_state.version >= INDEX_STATE_VERSION_HISTORY_ROOT
+                && output_type_script.args().raw_data().len() != ORACLE_ARGS_LEN
+            {
+                return Err(Error::InvalidArgument);
+            }
+            check_created_info_root(&output_type_script, &index_state)
         })
     }
 }
 
-// Index state cell data: index(u8) | sum_of_time_info_cells(u8)
-fn check_index_state_cell_data(source: Source) -> Result<Vec<u8>, Error> {
-    let data = high_level::load_cell_data(0, source)?;
-    if data.len() != INDEX_STATE_CELL_DATA_LEN {
-        return Err(Error::IndexStateDataLenError);
+// Since version 5 the args start with the out point of the first input like a type id, so the type
+// script of the index state cell can't be created twice
+fn check_type_id_args(type_script: &Script) -> Result<(), Error> {
+    let args: Bytes = type_script.args().unpack();
+    let first_input = high_level::load_input(0, Source::Input)?.previous_output();
+    if args.len() < OUT_POINT_LEN || args[..OUT_POINT_LEN] != first_input.as_slice()[..] {
+        return Err(Error::InvalidArgument);
     }
-    if data[0] >= SUM_OF_INFO_CELLS {
-        return Err(Error::IndexStateOutOfBound);
+    Ok(())
+}
+
+// Since version 5 only one index state cell can have the type script, or the readers can be given
+// a forged one
+fn check_unique_output() -> Result<(), Error> {
+    if high_level::QueryIter::new(high_level::load_cell_capacity, Source::GroupOutput).count() > 1 {
+        return Err(Error::IndexStateNotUnique);
     }
-    if data[1] != SUM_OF_INFO_CELLS {
-        return Err(Error::InfoAmountError);
+    Ok(())
+}
+
+// Index state cell data: index(u8) | sum_of_time_info_cells(u8)
+// or version(u8) | index(u16) | sum_of_time_info_cells(u16)
+// or version(u8) | index(u16) | sum_of_time_info_cells(u16) | history_root(32 bytes)
+// or version(u8) | index(u16) | sum_of_time_info_cells(u16) | history_root(32 bytes) | sequence(u64)
+// or the version 3 data | info_root(32 bytes), and version 5 has the same data as version 4
+fn check_index_state_cell_data(source: Source) -> Result<IndexState, Error> {
+    let index_state = parse_index_state(source)?;
+    if index_state.version == INDEX_STATE_VERSION_LEGACY {
+        if index_state.index >= SUM_OF_INFO_CELLS as u16 {
+            return Err(Error::IndexStateOutOfBound);
+        }
+        if index_state.length != SUM_OF_INFO_CELLS as u16 {
+            return Err(Error::InfoAmountError);
+        }
+    } else {
+        if index_state.length == 0 {
+            return Err(Error::InfoAmountError);
+        }
+        if index_state.index >= index_state.length {
+            return Err(Error::IndexStateOutOfBound);
+        }
+    }
+    Ok(index_state)
+}
+
+fn parse_index_state(source: Source) -> Result<IndexState, Error> {
+    let data = high_level::load_cell_data(0, source)?;
+    match IndexState::from_slice(&data) {
+        Ok(index_state) => Ok(index_state),
+        Err(IndexStateError::DataLenError) => Err(Error::IndexStateDataLenError),
+        Err(IndexStateError::VersionError) => Err(Error::IndexStateVersionError),
     }
-    Ok(data)
 }
 
 fn check_index_state_cells_data() -> Result<(), Error> {
-    // Due to the need for dynamic resizing SUM_OF_INFO_CELLS, do not check the data of input IndexStateCell anymore.
+    // Before version 2 SUM_OF_INFO_CELLS could be resized dynamically, so the data of the input IndexStateCell isn't checked.
     // let input_data = check_index_state_cell_data(Source::GroupInput)?;
 
-    let input_data = high_level::load_cell_data(0, Source::GroupInput)?;
-    let output_data = check_index_state_cell_data(Source::GroupOutput)?;
+    let input_index_state = parse_index_state(Source::GroupInput)?;
+    let output_index_state = check_index_state_cell_data(Source::GroupOutput)?;
+    if output_index_state.version >= INDEX_STATE_VERSION_UNIQUE {
+        check_unique_output()?;
+    }
 
-    if input_data[0] >= SUM_OF_INFO_CELLS - 1 {
-        if output_data[0] != 0 {
-            return Err(Error::IndexIncreaseError);
-        }
-    } else if input_data[0] + 1 != output_data[0] {
+    // The info cells keep the layout of their version, so the version can't be changed by updating
+    if input_index_state.version != output_index_state.version {
+        return Err(Error::IndexStateVersionError);
+    }
+
+    // Since version 2 the roots commit to a ring of a fixed length, and the proof of the info root
+    // is checked against the length of the output
+    if output_index_state.version >= INDEX_STATE_VERSION_HISTORY_ROOT
+        && input_index_state.length != output_index_state.length
+    {
+        return Err(Error::InfoAmountError);
+    }
+
+    if input_index_state.next_index(output_index_state.length) != output_index_state.index {
         return Err(Error::IndexIncreaseError);
     }
+
+    // The sequence never wraps, so the skipped or replayed updates can be detected
+    if output_index_state.sequence.is_some()
+        && input_index_state
+            .sequence
+            .and_then(|sequence| sequence.checked_add(1))
+            != output_index_state.sequence
+    {
+        return Err(Error::IndexStateSequenceError);
+    }
+
+    // The roots commit to the info cells, so they can only change with the info cell of the index
+    if output_index_state.version >= INDEX_STATE_VERSION_HISTORY_ROOT {
+        let script = high_level::load_script()?;
+        let (_, output_info_data) = load_updated_info(&script, Source::Output)?;
+        let (input_position, input_info_data) = load_updated_info(&script, Source::Input)?;
+        check_history_root(&input_index_state, &output_index_state, &output_info_data)?;
+        check_info_root(
+            &input_index_state,
+            &output_index_state,
+            input_position,
+            &input_info_data,
+            &output_info_data,
+        )?;
+    }
+    Ok(())
+}
+
+// The info root of a new ring commits to all of its info cells, which are created with it
+fn check_created_info_root(script: &Script, index_state: &IndexState) -> Result<(), Error> {
+    let created_info_root = match index_state.info_root {
+        Some(info_root) => info_root,
+        None => return Ok(()),
+    };
+    let layout = index_state.info_layout();
+    let mut infos_data = Vec::new();
+    for (pos, type_script) in
+        high_level::QueryIter::new(high_level::load_cell_type, Source::Output).enumerate()
+    {
+        if matches!(type_script, Some(type_script) if is_info_type_script(&type_script, script)) {
+            let info_data = high_level::load_cell_data(pos, Source::Output)?;
+            if !layout.is_data_len_valid(&info_data) {
+                return Err(Error::InvalidInfoRoot);
+            }
+            infos_data.push(info_data);
+        }
+    }
+    if infos_data.len() != index_state.length as usize {
+        return Err(Error::InfoAmountError);
+    }
+
+    // The leaf i is the info cell of the index i
+    infos_data.sort_by_key(|info_data| layout.index(info_data));
+    let leaves: Vec<&[u8]> = infos_data.iter().map(|info_data| &info_data[..]).collect();
+    if leaves
+        .iter()
+        .enumerate()
+        .any(|(index, info_data)| layout.index(info_data) as usize != index)
+        || info_root(&leaves) != created_info_root
+    {
+        return Err(Error::InvalidInfoRoot);
+    }
     Ok(())
 }
 
+// The same merkle proof of the updated index, in the input_type of the witness of the info cell
+// input, must lead to the input root with the input info cell and to the output root with the
+// output info cell, see info-type
+fn check_info_root(
+    input_index_state: &IndexState,
+    output_index_state: &IndexState,
+    input_info_position: usize,
+    input_info_data: &[u8],
+    output_info_data: &[u8],
+) -> Result<(), Error> {
+    let (input_info_root, output_info_root) =
+        match (input_index_state.info_root, output_index_state.info_root) {
+            (Some(input_info_root), Some(output_info_root)) => (input_info_root, output_info_root),
+            (None, None) => return Ok(()),
+            _ => return Err(Error::InvalidInfoRoot),
+        };
+
+    let proof: Bytes = match high_level::load_witness_args(input_info_position, Source::Input)
+        .ok()
+        .and_then(|witness_args| witness_args.input_type().to_opt())
+    {
+        Some(proof) => proof.unpack(),
+        None => return Err(Error::InvalidInfoProof),
+    };
+    let index = output_index_state.index;
+    let length = output_index_state.length;
+    match (
+        root_from_proof(input_info_data, index, length, &proof),
+        root_from_proof(output_info_data, index, length, &proof),
+    ) {
+        (Ok(input_root), Ok(output_root)) => {
+            if input_root != input_info_root || output_root != output_info_root {
+                return Err(Error::InvalidInfoRoot);
+            }
+            Ok(())
+        }
+        _ => Err(Error::InvalidInfoProof),
+    }
+}
+
+// Since version 2 the args pin the code hash of info-type: out point(36 bytes) | info type code hash,
+// and the info type script has the same hash type and args as the index state type script
+fn is_info_type_script(type_script: &Script, script: &Script) -> bool {
+    let args = script.args().raw_data();
+    args.len() == ORACLE_ARGS_LEN
+        && type_script.code_hash().raw_data()[..] == args[OUT_POINT_LEN..]
+        && type_script.hash_type() == script.hash_type()
+        && type_script.args().raw_data()[..] == args[..]
+}
+
+// An update writes exactly one info cell of the ring
+fn load_updated_info(script: &Script, source: Source) -> Result<(usize, Vec<u8>), Error> {
+    if script.args().raw_data().len() != ORACLE_ARGS_LEN {
+        return Err(Error::InvalidArgument);
+    }
+    let mut position = None;
+    for (pos, type_script) in
+        high_level::QueryIter::new(high_level::load_cell_type, source).enumerate()
+    {
+        if matches!(type_script, Some(type_script) if is_info_type_script(&type_script, script)) {
+            if position.is_some() {
+                return Err(Error::InfoNotUpdated);
+            }
+            position = Some(pos);
+        }
+    }
+    match position {
+        Some(pos) => Ok((pos, high_level::load_cell_data(pos, source)?)),
+        None => Err(Error::InfoNotUpdated),
+    }
+}
+
+fn check_history_root(
+    input_index_state: &IndexState,
+    output_index_state: &IndexState,
+    output_info_data: &[u8],
+) -> Result<(), Error> {
+    match (
+        input_index_state.history_root,
+        output_index_state.history_root,
+    ) {
+        (Some(input_root), Some(output_root))
+            if extend_history_root(&input_root, output_info_data) == output_root =>
+        {
+            Ok(())
+        }
+        _ => Err(Error::InvalidHistoryRoot),
+    }
+}
+
 fn load_output_type_script<F>(closure: F) -> Result<(), Error>
 where
     F: Fn(Script) -> Result<(), Error>,
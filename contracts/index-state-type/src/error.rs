@@ -6,11 +6,19 @@ pub enum Error {
     ItemMissing,
     LengthNotEnough,
     Encoding,
-    IndexStateTypeNotExist = 6,
+    InvalidArgument = 5,
+    IndexStateTypeNotExist,
     IndexStateDataLenError,
     InfoAmountError,
     IndexStateOutOfBound,
     IndexIncreaseError,
+    IndexStateVersionError,
+    IndexStateSequenceError,
+    IndexStateNotUnique,
+    InfoNotUpdated,
+    InvalidHistoryRoot,
+    InvalidInfoProof,
+    InvalidInfoRoot,
 }
 
 impl From<SysError> for Error {
@@ -3,9 +3,15 @@ use alloc::vec::Vec;
 use ckb_std::{
     ckb_constants::Source,
     ckb_types::{bytes::Bytes, packed::*, prelude::*},
-    high_level::{load_cell_data, load_cell_type, load_input_since, load_script, QueryIter},
+    high_level::{
+        load_cell_capacity, load_cell_data, load_cell_type, load_cell_type_hash, load_header,
+        load_input, load_input_since, load_script, load_transaction, load_witness_args, QueryIter,
+    },
+};
+use common::{
+    constants::*, history::extend_history_root, index_state::IndexState, info::InfoLayout,
+    merkle::root_from_proof,
 };
-use common::constants::*;
 use core::result::Result;
 
 pub fn main() -> Result<(), Error> {
@@ -30,7 +36,24 @@ pub fn main() -> Result<(), Error> {
             if info_type_args[..] != index_state_type_args[..] {
                 return Err(Error::InvalidArgument);
             }
-            check_info_cell_data()
+            // Since version 5 the info cells are only created with the index state cell, whose
+            // args are a type id, so no one can create more info cells of the ring later
+            let output_index_state = load_output_index_state()?;
+            if output_index_state.version >= INDEX_STATE_VERSION_UNIQUE {
+                let first_input = load_input(0, Source::Input)?.previous_output();
+                if info_type_args.len() < OUT_POINT_LEN
+                    || info_type_args[..OUT_POINT_LEN] != first_input.as_slice()[..]
+                {
+                    return Err(Error::InvalidArgument);
+                }
+            }
+            // Since version 2 the args end with the code hash of info-type, see index-state-type
+            if info_type_args.len() == ORACLE_ARGS_LEN
+                && info_type_args[OUT_POINT_LEN..] != output_type_script.code_hash().raw_data()[..]
+            {
+                return Err(Error::InvalidArgument);
+            }
+            check_info_cell_data(&output_index_state)
         })
     }
 }
@@ -46,60 +69,175 @@ fn load_output_index_state_type_args() -> Result<Bytes, Error> {
     }
 }
 
-// Info cell data: index(u8) | type(u8) | DataType(u64)
-fn check_info_cell_data() -> Result<(), Error> {
-    match load_cell_data(0, Source::GroupOutput) {
-        Ok(info_data) => match is_info_data_len_valid(&info_data, &info_data) {
-            true => Ok(()),
-            false => Err(Error::InfoDataLenError),
-        },
-        Err(_) => Err(Error::InfoTypeNotExist),
+// Note: Assuming that the first output must have index state type
+fn load_output_index_state() -> Result<IndexState, Error> {
+    let output_index_state_data = load_cell_data(0, Source::Output)?;
+    match IndexState::from_slice(&output_index_state_data) {
+        Ok(index_state) => Ok(index_state),
+        Err(_) => Err(Error::IndexStateDataLenError),
+    }
+}
+
+// The input index state is found by the type hash of the output one, wherever it is
+fn load_input_index_state() -> Result<IndexState, Error> {
+    let index_state_type_hash = match load_cell_type_hash(0, Source::Output)? {
+        Some(type_hash) => type_hash,
+        None => return Err(Error::IndexStateTypeNotExist),
+    };
+    let position = QueryIter::new(load_cell_type_hash, Source::Input)
+        .position(|type_hash| type_hash == Some(index_state_type_hash));
+    let input_index_state_data = match position {
+        Some(position) => load_cell_data(position, Source::Input)?,
+        None => return Err(Error::IndexStateTypeNotExist),
+    };
+    match IndexState::from_slice(&input_index_state_data) {
+        Ok(index_state) => Ok(index_state),
+        Err(_) => Err(Error::IndexStateDataLenError),
+    }
+}
+
+// Info cell data: index(u8) | type(u8) | content whose length is decided by the type
+// The index is u16 when the index state cell is version 1 or 2,
+// and the version 3 info cell has a sequence(u64) after the type
+fn check_info_cell_data(output_index_state: &IndexState) -> Result<(), Error> {
+    let layout = output_index_state.info_layout();
+    // Since version 5 the whole ring is created at once, so every info cell of it is checked
+    let infos_data: Vec<Vec<u8>> = if output_index_state.version >= INDEX_STATE_VERSION_UNIQUE {
+        QueryIter::new(load_cell_data, Source::GroupOutput).collect()
+    } else {
+        match load_cell_data(0, Source::GroupOutput) {
+            Ok(info_data) => vec![info_data],
+            Err(_) => return Err(Error::InfoTypeNotExist),
+        }
+    };
+    for info_data in infos_data {
+        if !layout.is_data_len_valid(&info_data) {
+            return Err(Error::InfoDataLenError);
+        }
+        if layout.sequence(&info_data) != output_index_state.sequence {
+            return Err(Error::InvalidInfoSequence);
+        }
     }
+    Ok(())
 }
 
 fn check_info_cells_data() -> Result<(), Error> {
-    // Note: Assuming that the first output must have index state type
-    let output_index_state_data = load_cell_data(0, Source::Output)?;
-    if output_index_state_data.len() != INDEX_STATE_CELL_DATA_LEN {
-        return Err(Error::IndexStateDataLenError);
+    let output_index_state = load_output_index_state()?;
+    let layout = output_index_state.info_layout();
+
+    // Since version 5 an update replaces one info cell, another output of the ring wouldn't be
+    // checked
+    if output_index_state.version >= INDEX_STATE_VERSION_UNIQUE
+        && (QueryIter::new(load_cell_capacity, Source::GroupInput).count() != 1
+            || QueryIter::new(load_cell_capacity, Source::GroupOutput).count() != 1)
+    {
+        return Err(Error::InfoNotUnique);
     }
 
     let input_info_data = load_cell_data(0, Source::GroupInput)?;
     let output_info_data = load_cell_data(0, Source::GroupOutput)?;
 
-    if !is_info_data_len_valid(&input_info_data, &output_info_data) {
+    if !layout.is_data_len_valid(&input_info_data) || !layout.is_data_len_valid(&output_info_data) {
         return Err(Error::InfoDataLenError);
     }
 
-    // the first u8 is index
-    if output_info_data[0] != output_index_state_data[0] {
+    if layout.index(&output_info_data) != output_index_state.index {
         return Err(Error::InfoIndexNotSame);
     }
 
+    // The info cell records the sequence of the index state it is written at
+    if layout.sequence(&output_info_data) != output_index_state.sequence {
+        return Err(Error::InvalidInfoSequence);
+    }
+
+    if output_index_state.version >= INDEX_STATE_VERSION_HISTORY_ROOT {
+        let input_index_state = load_input_index_state()?;
+        check_history_root(&input_index_state, &output_index_state, &output_info_data)?;
+        check_info_root(
+            &input_index_state,
+            &output_index_state,
+            &input_info_data,
+            &output_info_data,
+        )?;
+    }
+
     let since = load_input_since(0, Source::GroupInput)?;
-    let output_info_data_type = output_info_data[INFO_CELL_META_TYPE_POS];
+    let output_info_data_type = layout.data_type(&output_info_data);
 
-    // the second u8 is DataType
     if output_info_data_type == DataType::Timestamp as u8 {
-        let input_timestamp = content_from_info_data(&input_info_data);
-        let output_timestamp = content_from_info_data(&output_info_data);
+        let input_timestamp = u64_from_info_data(&layout, &input_info_data)?;
+        let output_timestamp = u64_from_info_data(&layout, &output_info_data)?;
 
         if input_timestamp >= output_timestamp {
             return Err(Error::OutputTimestampNotBigger);
         }
 
-        let since_timestamp_base: u64 = 1 << 62;
-        if since_timestamp_base + output_timestamp as u64 != since {
+        if SINCE_TIMESTAMP_BASE + output_timestamp as u64 != since {
             return Err(Error::InvalidTimeInfoSince);
         }
     } else if output_info_data_type == DataType::BlockNumber as u8 {
-        let input_block_number = content_from_info_data(&input_info_data);
-        let output_block_number = content_from_info_data(&output_info_data);
+        let input_block_number = u64_from_info_data(&layout, &input_info_data)?;
+        let output_block_number = u64_from_info_data(&layout, &output_info_data)?;
 
         if input_block_number >= output_block_number {
             return Err(Error::OutputBlockNumberNotBigger);
         }
 
+        if output_block_number != since {
+            return Err(Error::InvalidTimeInfoSince);
+        }
+    } else if output_info_data_type == DataType::TimestampMillis as u8 {
+        let input_timestamp = u64_from_info_data(&layout, &input_info_data)?;
+        let output_timestamp = u64_from_info_data(&layout, &output_info_data)?;
+
+        if input_timestamp >= output_timestamp {
+            return Err(Error::OutputTimestampNotBigger);
+        }
+
+        // the millisecond timestamp must be the one carried by the first header dep
+        if load_header_dep_timestamp()? != output_timestamp {
+            return Err(Error::InvalidHeaderDepTimestamp);
+        }
+
+        // since only has second precision, so it is the lower bound of the millisecond timestamp
+        if SINCE_TIMESTAMP_BASE + output_timestamp / MILLIS_PER_SECOND != since {
+            return Err(Error::InvalidTimeInfoSince);
+        }
+    } else if output_info_data_type == DataType::MedianTimestamp as u8 {
+        let input_timestamp = u64_from_info_data(&layout, &input_info_data)?;
+        let output_timestamp = u64_from_info_data(&layout, &output_info_data)?;
+
+        if input_timestamp >= output_timestamp {
+            return Err(Error::OutputTimestampNotBigger);
+        }
+
+        if load_header_deps_median_timestamp()? != output_timestamp {
+            return Err(Error::InvalidMedianTimestamp);
+        }
+
+        if SINCE_TIMESTAMP_BASE + output_timestamp / MILLIS_PER_SECOND != since {
+            return Err(Error::InvalidTimeInfoSince);
+        }
+    } else if output_info_data_type == DataType::BlockHash as u8 {
+        let input_block_number = u64_from_info_data(&layout, &input_info_data)?;
+        let output_block_number = u64_from_info_data(&layout, &output_info_data)?;
+
+        if input_block_number >= output_block_number {
+            return Err(Error::OutputBlockNumberNotBigger);
+        }
+
+        // block_number(u64) | block_hash must be the number and the hash of the first header dep
+        let header_dep_hash = match load_transaction()?.raw().header_deps().get(0) {
+            Some(hash) => hash,
+            None => return Err(Error::HeaderDepNotExist),
+        };
+        let header_dep_number: u64 = load_header(0, Source::HeaderDep)?.raw().number().unpack();
+        if header_dep_number != output_block_number
+            || header_dep_hash.raw_data()[..] != layout.content(&output_info_data)[8..]
+        {
+            return Err(Error::InvalidHeaderDepBlockHash);
+        }
+
         if output_block_number != since {
             return Err(Error::InvalidTimeInfoSince);
         }
@@ -110,14 +248,120 @@ fn check_info_cells_data() -> Result<(), Error> {
     Ok(())
 }
 
-fn content_from_info_data(info_data: &Vec<u8>) -> u64 {
-    let mut content_buf = [0u8; INFO_CELL_CONTENT_LEN];
-    content_buf.copy_from_slice(&info_data[2..]);
-    u64::from_be_bytes(content_buf)
+// Every update extends the hash chain of the version 2 index state by the output info data
+fn check_history_root(
+    input_index_state: &IndexState,
+    output_index_state: &IndexState,
+    output_info_data: &[u8],
+) -> Result<(), Error> {
+    let input_history_root = match input_index_state.history_root {
+        Some(history_root) => history_root,
+        None => return Err(Error::InvalidHistoryRoot),
+    };
+    let output_history_root = match output_index_state.history_root {
+        Some(history_root) => history_root,
+        None => return Err(Error::InvalidHistoryRoot),
+    };
+    if extend_history_root(&input_history_root, output_info_data) != output_history_root {
+        return Err(Error::InvalidHistoryRoot);
+    }
+    Ok(())
 }
 
-fn is_info_data_len_valid(input_info_data: &Vec<u8>, output_info_data: &Vec<u8>) -> bool {
-    input_info_data.len() == INFO_CELL_DATA_LEN && output_info_data.len() == INFO_CELL_DATA_LEN
+// The input info cell is the leaf of the index which is replaced by the output info cell, so the
+// same merkle proof in the input_type of the witness must lead to the input and the output roots.
+fn check_info_root(
+    input_index_state: &IndexState,
+    output_index_state: &IndexState,
+    input_info_data: &[u8],
+    output_info_data: &[u8],
+) -> Result<(), Error> {
+    let output_info_root = match output_index_state.info_root {
+        Some(info_root) => info_root,
+        None => return Ok(()),
+    };
+    let input_info_root = match input_index_state.info_root {
+        Some(info_root) => info_root,
+        None => return Err(Error::InvalidInfoRoot),
+    };
+    // The proof is checked against the length of the output, which must be the length of the input
+    if input_index_state.length != output_index_state.length {
+        return Err(Error::InvalidInfoRoot);
+    }
+
+    let proof = load_info_proof()?;
+    let index = output_index_state.index;
+    let length = output_index_state.length;
+    match (
+        root_from_proof(input_info_data, index, length, &proof),
+        root_from_proof(output_info_data, index, length, &proof),
+    ) {
+        (Ok(input_root), Ok(output_root)) => {
+            if input_root != input_info_root || output_root != output_info_root {
+                return Err(Error::InvalidInfoRoot);
+            }
+            Ok(())
+        }
+        _ => Err(Error::InvalidInfoProof),
+    }
+}
+
+fn load_info_proof() -> Result<Bytes, Error> {
+    let witness_args = match load_witness_args(0, Source::GroupInput) {
+        Ok(witness_args) => witness_args,
+        Err(_) => return Err(Error::InvalidInfoProof),
+    };
+    match witness_args.input_type().to_opt() {
+        Some(proof) => Ok(proof.unpack()),
+        None => Err(Error::InvalidInfoProof),
+    }
+}
+
+fn load_header_dep_timestamp() -> Result<u64, Error> {
+    match load_header(0, Source::HeaderDep) {
+        Ok(header) => Ok(header.raw().timestamp().unpack()),
+        Err(_) => Err(Error::HeaderDepNotExist),
+    }
+}
+
+// The header deps must be MEDIAN_TIMESTAMP_HEADERS consecutive blocks in ascending order,
+// and the median of their timestamps can't be skewed by a single miner.
+// A script can't read the tip, so the window is not checked to be the latest one: the median only
+// has to be bigger than the previous value, and the since keeps it from being ahead of the chain.
+// An updater may publish an older window than the latest, which is as stale as an update delayed
+// by the same blocks, and consumers must bound the staleness with the freshness check instead.
+fn load_header_deps_median_timestamp() -> Result<u64, Error> {
+    let headers: Vec<Header> = QueryIter::new(load_header, Source::HeaderDep).collect();
+    if headers.len() != MEDIAN_TIMESTAMP_HEADERS {
+        return Err(Error::HeaderDepNotExist);
+    }
+
+    let mut timestamps: Vec<u64> = Vec::with_capacity(MEDIAN_TIMESTAMP_HEADERS);
+    let mut prev_number: Option<u64> = None;
+    for header in headers.iter() {
+        let number: u64 = header.raw().number().unpack();
+        if let Some(prev) = prev_number {
+            if prev + 1 != number {
+                return Err(Error::InvalidHeaderDeps);
+            }
+        }
+        prev_number = Some(number);
+        timestamps.push(header.raw().timestamp().unpack());
+    }
+
+    timestamps.sort_unstable();
+    Ok(timestamps[MEDIAN_TIMESTAMP_HEADERS / 2])
+}
+
+// The u64 content, the block number is also the first u64 of the BlockHash content
+fn u64_from_info_data(layout: &InfoLayout, info_data: &Vec<u8>) -> Result<u64, Error> {
+    let content = layout.content(info_data);
+    if content.len() < INFO_CELL_CONTENT_LEN {
+        return Err(Error::InfoDataLenError);
+    }
+    let mut content_buf = [0u8; INFO_CELL_CONTENT_LEN];
+    content_buf.copy_from_slice(&content[..INFO_CELL_CONTENT_LEN]);
+    Ok(u64::from_be_bytes(content_buf))
 }
 
 fn load_output_type_script<F>(closure: F) -> Result<(), Error>
@@ -15,6 +15,16 @@ pub enum Error {
     OutputBlockNumberNotBigger,
     InvalidTimeInfoSince,
     IndexStateTypeNotExist,
+    HeaderDepNotExist,
+    InvalidHeaderDepTimestamp = 15,
+    InvalidHeaderDeps,
+    InvalidMedianTimestamp,
+    InvalidHeaderDepBlockHash,
+    InvalidHistoryRoot,
+    InvalidInfoSequence,
+    InvalidInfoProof,
+    InvalidInfoRoot,
+    InfoNotUnique,
 }
 
 impl From<SysError> for Error {
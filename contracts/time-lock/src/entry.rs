@@ -0,0 +1,88 @@
+use crate::error::Error;
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    high_level::{load_cell_lock_hash, load_script, QueryIter},
+};
+use common::freshness::{fresh_value, FreshnessError, FreshnessPolicy};
+use common::reader::{load_oracle_cell_deps, load_since_reference, ReaderError};
+use core::result::Result;
+
+// Time lock args: owner_lock_hash(32 bytes) | index_state_type_hash(32 bytes) | info_type_code_hash(32 bytes) | unlock_timestamp(u64)
+// | max_age(u64)
+const HASH_LEN: usize = 32;
+const TIME_LOCK_ARGS_LEN: usize = HASH_LEN * 3 + 8 * 2;
+
+pub fn main() -> Result<(), Error> {
+    let args: Bytes = load_script()?.args().unpack();
+    if args.len() != TIME_LOCK_ARGS_LEN {
+        return Err(Error::InvalidArgument);
+    }
+    let owner_lock_hash = &args[..HASH_LEN];
+    let index_state_type_hash = &args[HASH_LEN..HASH_LEN * 2];
+    let info_type_code_hash = &args[HASH_LEN * 2..HASH_LEN * 3];
+    let u64_at = |pos: usize| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&args[pos..pos + 8]);
+        u64::from_be_bytes(buf)
+    };
+    let unlock_timestamp = u64_at(HASH_LEN * 3);
+    let max_age = u64_at(HASH_LEN * 3 + 8);
+
+    // The owner must unlock the time lock cell with another input of the owner lock
+    let owner_exists = QueryIter::new(load_cell_lock_hash, Source::Input)
+        .any(|lock_hash| lock_hash[..] == owner_lock_hash[..]);
+    if !owner_exists {
+        return Err(Error::OwnerNotExist);
+    }
+
+    if load_latest_timestamp(index_state_type_hash, info_type_code_hash, max_age)?
+        < unlock_timestamp
+    {
+        return Err(Error::TimestampNotReached);
+    }
+    Ok(())
+}
+
+// The latest timestamp(seconds) of the oracle in the cell deps, which must not be older than the
+// max age at the since of the time lock input
+fn load_latest_timestamp(
+    index_state_type_hash: &[u8],
+    info_type_code_hash: &[u8],
+    max_age: u64,
+) -> Result<u64, Error> {
+    let oracle =
+        load_oracle_cell_deps(index_state_type_hash, info_type_code_hash).map_err(reader_error)?;
+    let latest_timestamp = oracle.latest_timestamp().map_err(reader_error)?;
+
+    let policy = FreshnessPolicy {
+        max_age,
+        halted_age: max_age,
+    };
+    let reference = load_since_reference().map_err(reader_error)?;
+    fresh_value(&oracle.latest().map_err(reader_error)?, reference, &policy)
+        .map_err(freshness_error)?;
+    Ok(latest_timestamp)
+}
+
+fn reader_error(err: ReaderError) -> Error {
+    match err {
+        ReaderError::Syscall => Error::ItemMissing,
+        ReaderError::IndexStateNotFound => Error::IndexStateNotExist,
+        ReaderError::InfoNotFound => Error::InfoNotExist,
+        ReaderError::IndexStateNotUnique
+        | ReaderError::InfoNotUnique
+        | ReaderError::History(_)
+        | ReaderError::InvalidInfoProof => Error::InvalidOracleData,
+        ReaderError::InvalidDataType => Error::InvalidDataType,
+    }
+}
+
+fn freshness_error(err: FreshnessError) -> Error {
+    match err {
+        FreshnessError::InvalidContent => Error::InvalidDataType,
+        // The oracle is a timestamp one already, so only the since can mismatch it
+        FreshnessError::InvalidSince | FreshnessError::MetricMismatch => Error::InvalidSince,
+        FreshnessError::NotFresh(_) => Error::OracleNotFresh,
+    }
+}
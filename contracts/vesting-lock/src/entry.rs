@@ -0,0 +1,133 @@
+use crate::error::Error;
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    high_level::{
+        load_cell_capacity, load_cell_lock_hash, load_script, load_script_hash, QueryIter,
+    },
+};
+use common::freshness::{fresh_value, FreshnessError, FreshnessPolicy};
+use common::reader::{load_oracle_cell_deps, load_since_reference, ReaderError};
+use core::result::Result;
+
+// Vesting lock args: owner_lock_hash(32 bytes) | index_state_type_hash(32 bytes) | info_type_code_hash(32 bytes)
+// | total_capacity(u64) | start_timestamp(u64) | end_timestamp(u64) | max_age(u64)
+const HASH_LEN: usize = 32;
+const VESTING_LOCK_ARGS_LEN: usize = HASH_LEN * 3 + 8 * 4;
+
+struct VestingArgs<'a> {
+    owner_lock_hash: &'a [u8],
+    index_state_type_hash: &'a [u8],
+    info_type_code_hash: &'a [u8],
+    total_capacity: u64,
+    start_timestamp: u64,
+    end_timestamp: u64,
+    max_age: u64,
+}
+
+fn parse_args(args: &Bytes) -> Result<VestingArgs<'_>, Error> {
+    if args.len() != VESTING_LOCK_ARGS_LEN {
+        return Err(Error::InvalidArgument);
+    }
+    let u64_at = |pos: usize| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&args[pos..pos + 8]);
+        u64::from_be_bytes(buf)
+    };
+    let vesting_args = VestingArgs {
+        owner_lock_hash: &args[..HASH_LEN],
+        index_state_type_hash: &args[HASH_LEN..HASH_LEN * 2],
+        info_type_code_hash: &args[HASH_LEN * 2..HASH_LEN * 3],
+        total_capacity: u64_at(HASH_LEN * 3),
+        start_timestamp: u64_at(HASH_LEN * 3 + 8),
+        end_timestamp: u64_at(HASH_LEN * 3 + 16),
+        max_age: u64_at(HASH_LEN * 3 + 24),
+    };
+    if vesting_args.start_timestamp >= vesting_args.end_timestamp {
+        return Err(Error::InvalidArgument);
+    }
+    Ok(vesting_args)
+}
+
+pub fn main() -> Result<(), Error> {
+    let args: Bytes = load_script()?.args().unpack();
+    let vesting_args = parse_args(&args)?;
+
+    // The owner must unlock the vesting cell with another input of the owner lock
+    let owner_exists = QueryIter::new(load_cell_lock_hash, Source::Input)
+        .any(|lock_hash| lock_hash[..] == vesting_args.owner_lock_hash[..]);
+    if !owner_exists {
+        return Err(Error::OwnerNotExist);
+    }
+
+    // The change cells of the same args share one schedule, so only one vesting cell can be spent at a time
+    if QueryIter::new(load_cell_capacity, Source::GroupInput).count() != 1 {
+        return Err(Error::MultipleVestingInputs);
+    }
+
+    let oracle = load_oracle_cell_deps(
+        vesting_args.index_state_type_hash,
+        vesting_args.info_type_code_hash,
+    )
+    .map_err(reader_error)?;
+    let now = oracle.latest_timestamp().map_err(reader_error)?;
+
+    // The latest timestamp must not be older than the max age at the since of the vesting input
+    let policy = FreshnessPolicy {
+        max_age: vesting_args.max_age,
+        halted_age: vesting_args.max_age,
+    };
+    let reference = load_since_reference().map_err(reader_error)?;
+    fresh_value(&oracle.latest().map_err(reader_error)?, reference, &policy)
+        .map_err(freshness_error)?;
+
+    // The capacity which is not vested yet must stay in the change cells with this lock
+    let script_hash = load_script_hash()?;
+    let mut change_capacity: u64 = 0;
+    for (index, lock_hash) in QueryIter::new(load_cell_lock_hash, Source::Output).enumerate() {
+        if lock_hash[..] == script_hash[..] {
+            change_capacity =
+                change_capacity.saturating_add(load_cell_capacity(index, Source::Output)?);
+        }
+    }
+    if change_capacity < locked_capacity(&vesting_args, now) {
+        return Err(Error::LockedCapacityNotEnough);
+    }
+    Ok(())
+}
+
+// The capacity is released linearly from the start timestamp to the end timestamp
+fn locked_capacity(vesting_args: &VestingArgs, now: u64) -> u64 {
+    if now <= vesting_args.start_timestamp {
+        vesting_args.total_capacity
+    } else if now >= vesting_args.end_timestamp {
+        0
+    } else {
+        let vested = vesting_args.total_capacity as u128
+            * (now - vesting_args.start_timestamp) as u128
+            / (vesting_args.end_timestamp - vesting_args.start_timestamp) as u128;
+        vesting_args.total_capacity - vested as u64
+    }
+}
+
+fn reader_error(err: ReaderError) -> Error {
+    match err {
+        ReaderError::Syscall => Error::ItemMissing,
+        ReaderError::IndexStateNotFound => Error::IndexStateNotExist,
+        ReaderError::InfoNotFound => Error::InfoNotExist,
+        ReaderError::IndexStateNotUnique
+        | ReaderError::InfoNotUnique
+        | ReaderError::History(_)
+        | ReaderError::InvalidInfoProof => Error::InvalidOracleData,
+        ReaderError::InvalidDataType => Error::InvalidDataType,
+    }
+}
+
+fn freshness_error(err: FreshnessError) -> Error {
+    match err {
+        FreshnessError::InvalidContent => Error::InvalidDataType,
+        // The oracle is a timestamp one already, so only the since can mismatch it
+        FreshnessError::InvalidSince | FreshnessError::MetricMismatch => Error::InvalidSince,
+        FreshnessError::NotFresh(_) => Error::OracleNotFresh,
+    }
+}
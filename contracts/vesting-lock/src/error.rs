@@ -0,0 +1,32 @@
+use ckb_std::error::SysError;
+
+#[repr(i8)]
+pub enum Error {
+    IndexOutOfBound = 1,
+    ItemMissing,
+    LengthNotEnough,
+    Encoding,
+    InvalidArgument = 5,
+    OwnerNotExist,
+    IndexStateNotExist,
+    InfoNotExist,
+    InvalidOracleData,
+    InvalidDataType = 10,
+    MultipleVestingInputs,
+    LockedCapacityNotEnough,
+    InvalidSince,
+    OracleNotFresh,
+}
+
+impl From<SysError> for Error {
+    fn from(err: SysError) -> Self {
+        use SysError::*;
+        match err {
+            IndexOutOfBound => Self::IndexOutOfBound,
+            ItemMissing => Self::ItemMissing,
+            LengthNotEnough(_) => Self::LengthNotEnough,
+            Encoding => Self::Encoding,
+            Unknown(err_code) => panic!("unexpected sys error {}", err_code),
+        }
+    }
+}
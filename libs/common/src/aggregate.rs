@@ -0,0 +1,64 @@
+use crate::freshness::{freshness, Freshness, FreshnessError, FreshnessPolicy, Reference};
+use crate::history::InfoValue;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Aggregation {
+    // The lower median when the amount of the values is even
+    Median,
+    Minimum,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AggregateError {
+    InconsistentDataType,
+    Freshness(FreshnessError),
+    // The amount of the fresh oracles
+    QuorumNotReached(usize),
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct AggregatePolicy {
+    pub aggregation: Aggregation,
+    // The least amount of the fresh oracles
+    pub quorum: usize,
+    pub freshness: FreshnessPolicy,
+}
+
+// Aggregates the latest values of several oracles of the same data type, the stale and halted ones
+// are ignored, so one faulty or stuck oracle can't decide the result.
+pub fn aggregate(
+    latest_values: &[InfoValue],
+    reference: Reference,
+    policy: &AggregatePolicy,
+) -> Result<u64, AggregateError> {
+    if let Some(first) = latest_values.first() {
+        if latest_values
+            .iter()
+            .any(|value| value.data_type != first.data_type)
+        {
+            return Err(AggregateError::InconsistentDataType);
+        }
+    }
+
+    let mut fresh_values = Vec::with_capacity(latest_values.len());
+    for latest in latest_values.iter() {
+        let fresh =
+            freshness(latest, reference, &policy.freshness).map_err(AggregateError::Freshness)?;
+        if fresh == Freshness::Fresh {
+            let value = latest
+                .as_u64()
+                .ok_or(AggregateError::Freshness(FreshnessError::InvalidContent))?;
+            fresh_values.push(value);
+        }
+    }
+    if fresh_values.is_empty() || fresh_values.len() < policy.quorum {
+        return Err(AggregateError::QuorumNotReached(fresh_values.len()));
+    }
+
+    fresh_values.sort_unstable();
+    match policy.aggregation {
+        Aggregation::Median => Ok(fresh_values[(fresh_values.len() - 1) / 2]),
+        Aggregation::Minimum => Ok(fresh_values[0]),
+    }
+}
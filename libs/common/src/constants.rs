@@ -1,10 +1,37 @@
 pub const SUM_OF_INFO_CELLS: u8 = 12;
 pub const INDEX_STATE_CELL_DATA_LEN: usize = 1 + 1; // index(u8) + length(u8)
+pub const INDEX_STATE_V1_CELL_DATA_LEN: usize = 1 + 2 + 2; // version(u8) + index(u16) + length(u16)
+pub const INDEX_STATE_V2_CELL_DATA_LEN: usize = INDEX_STATE_V1_CELL_DATA_LEN + HASH_LEN; // v1 data + history_root
+pub const INDEX_STATE_V3_CELL_DATA_LEN: usize = INDEX_STATE_V2_CELL_DATA_LEN + 8; // v2 data + sequence(u64)
+pub const INDEX_STATE_V4_CELL_DATA_LEN: usize = INDEX_STATE_V3_CELL_DATA_LEN + HASH_LEN; // v3 data + info_root
+
+pub const INDEX_STATE_VERSION_LEGACY: u8 = 0; // the two bytes data without version
+pub const INDEX_STATE_VERSION_U16: u8 = 1;
+pub const INDEX_STATE_VERSION_HISTORY_ROOT: u8 = 2; // the u16 index state with a history hash chain
+pub const INDEX_STATE_VERSION_SEQUENCE: u8 = 3; // the version 2 index state with an update sequence
+pub const INDEX_STATE_VERSION_INFO_ROOT: u8 = 4; // the version 3 index state with a merkle root of the info cells
+pub const INDEX_STATE_VERSION_UNIQUE: u8 = 5; // the version 4 index state whose oracle cells can only be created once
 
 pub const INFO_CELL_DATA_LEN: usize = 1 + 1 + 8; // index(u8)| data_type(u8) |content(u64)
 pub const INFO_CELL_META_LEN: usize = 1 + 1; // index(u8) | type(u8)
 pub const INFO_CELL_CONTENT_LEN: usize = INFO_CELL_DATA_LEN - INFO_CELL_META_LEN; // u64
 pub const INFO_CELL_META_TYPE_POS: usize = 1; // second byte is content type
+pub const INFO_CELL_V1_META_LEN: usize = 2 + 1; // index(u16) | type(u8)
+pub const INFO_CELL_V3_META_LEN: usize = 2 + 1 + 8; // index(u16) | type(u8) | sequence(u64)
+pub const BLOCK_HASH_INFO_CELL_DATA_LEN: usize = INFO_CELL_META_LEN + 8 + 32; // block_number(u64) | block_hash(32 bytes)
+pub const MAX_INFO_CELL_CONTENT_LEN: usize = 512; // the longest Payload content
+
+pub const SINCE_TIMESTAMP_BASE: u64 = 1 << 62; // absolute since with timestamp metric
+pub const SINCE_RELATIVE_FLAG: u64 = 1 << 63;
+pub const SINCE_METRIC_MASK: u64 = 0b11 << 61; // block number(00) | epoch(01) | timestamp(10)
+pub const SINCE_VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+pub const MILLIS_PER_SECOND: u64 = 1000;
+pub const MEDIAN_TIMESTAMP_HEADERS: usize = 11; // consecutive header deps of MedianTimestamp
+
+pub const HASH_LEN: usize = 32;
+pub const OUT_POINT_LEN: usize = 32 + 4; // tx_hash(32 bytes) | index(u32), the type id args
+pub const ORACLE_ARGS_LEN: usize = OUT_POINT_LEN + HASH_LEN; // type id args | info type code hash, since version 2
+pub const CKB_HASH_PERSONALIZATION: &[u8] = b"ckb-default-hash";
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 #[repr(u8)]
@@ -12,4 +39,10 @@ pub enum DataType {
     Arbitrage = 0,
     Timestamp = 1,
     BlockNumber = 2,
+    TimestampMillis = 3,
+    MedianTimestamp = 4,
+    BlockHash = 5,
+    Uint128 = 6,
+    Hash = 7,
+    Payload = 8,
 }
@@ -0,0 +1,164 @@
+use crate::constants::DataType;
+use crate::history::InfoValue;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum EstimateError {
+    NotEnoughPoints,
+    // The block numbers and the timestamps of the points must both be increasing
+    NotMonotonic,
+    UnpairedHistory,
+    InvalidContent,
+    // The timestamps are not of a timestamp data type, or the block numbers not of BlockNumber
+    DataTypeMismatch,
+}
+
+// A block number and the timestamp recorded for it, e.g. by a timestamp ring and a block number ring
+// which are updated in the same transactions
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct TimePoint {
+    pub block_number: u64,
+    pub timestamp: u64,
+}
+
+// The estimated value and the range which the recorded points allow for it
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Estimate {
+    pub value: u64,
+    pub min: u64,
+    pub max: u64,
+}
+
+// Pairs the history windows of a timestamp ring and a block number ring by their indexes. The
+// timestamps may be Timestamp, TimestampMillis or MedianTimestamp, and the points have their unit.
+pub fn pair_history(
+    timestamps: &[InfoValue],
+    block_numbers: &[InfoValue],
+) -> Result<Vec<TimePoint>, EstimateError> {
+    if timestamps.len() != block_numbers.len() {
+        return Err(EstimateError::UnpairedHistory);
+    }
+    if timestamps
+        .iter()
+        .any(|value| !is_timestamp(value.data_type))
+        || block_numbers
+            .iter()
+            .any(|value| value.data_type != DataType::BlockNumber as u8)
+    {
+        return Err(EstimateError::DataTypeMismatch);
+    }
+    let mut points = Vec::with_capacity(timestamps.len());
+    for (timestamp, block_number) in timestamps.iter().zip(block_numbers.iter()) {
+        if timestamp.index != block_number.index {
+            return Err(EstimateError::UnpairedHistory);
+        }
+        match (timestamp.as_u64(), block_number.as_u64()) {
+            (Some(timestamp), Some(block_number)) => points.push(TimePoint {
+                block_number,
+                timestamp,
+            }),
+            _ => return Err(EstimateError::InvalidContent),
+        }
+    }
+    Ok(points)
+}
+
+fn is_timestamp(data_type: u8) -> bool {
+    data_type == DataType::Timestamp as u8
+        || data_type == DataType::TimestampMillis as u8
+        || data_type == DataType::MedianTimestamp as u8
+}
+
+pub fn estimate_timestamp(
+    points: &[TimePoint],
+    block_number: u64,
+) -> Result<Estimate, EstimateError> {
+    let pairs: Vec<(u64, u64)> = points
+        .iter()
+        .map(|point| (point.block_number, point.timestamp))
+        .collect();
+    estimate(pairs, block_number)
+}
+
+pub fn estimate_block_number(
+    points: &[TimePoint],
+    timestamp: u64,
+) -> Result<Estimate, EstimateError> {
+    let pairs: Vec<(u64, u64)> = points
+        .iter()
+        .map(|point| (point.timestamp, point.block_number))
+        .collect();
+    estimate(pairs, timestamp)
+}
+
+// Interpolates y between the two points around x, and the range is the y of the two points.
+// Otherwise extrapolates y from the nearest point with the average rate of the whole history,
+// and the range comes from the slowest and the fastest rate between two adjacent points.
+fn estimate(mut pairs: Vec<(u64, u64)>, x: u64) -> Result<Estimate, EstimateError> {
+    if pairs.len() < 2 {
+        return Err(EstimateError::NotEnoughPoints);
+    }
+    pairs.sort_unstable_by_key(|pair| pair.0);
+    if pairs
+        .windows(2)
+        .any(|window| window[0].0 >= window[1].0 || window[0].1 >= window[1].1)
+    {
+        return Err(EstimateError::NotMonotonic);
+    }
+
+    let first = pairs[0];
+    let last = pairs[pairs.len() - 1];
+    if x >= first.0 && x <= last.0 {
+        let window = pairs
+            .windows(2)
+            .find(|window| x <= window[1].0)
+            .ok_or(EstimateError::NotEnoughPoints)?;
+        let (start, end) = (window[0], window[1]);
+        let value = start.1 + scale(x - start.0, end.1 - start.1, end.0 - start.0);
+        return Ok(Estimate {
+            value,
+            min: start.1,
+            max: end.1,
+        });
+    }
+
+    let average = (last.1 - first.1, last.0 - first.0);
+    let rates = pairs
+        .windows(2)
+        .map(|window| (window[1].1 - window[0].1, window[1].0 - window[0].0));
+    if x > last.0 {
+        let distance = x - last.0;
+        let extrapolate = |(dy, dx): (u64, u64)| last.1.saturating_add(scale(distance, dy, dx));
+        let (min, max) = rates.fold((u64::MAX, 0), |(min, max), rate| {
+            let y = extrapolate(rate);
+            (min.min(y), max.max(y))
+        });
+        Ok(Estimate {
+            value: extrapolate(average),
+            min,
+            max,
+        })
+    } else {
+        let distance = first.0 - x;
+        let extrapolate = |(dy, dx): (u64, u64)| first.1.saturating_sub(scale(distance, dy, dx));
+        let (min, max) = rates.fold((u64::MAX, 0), |(min, max), rate| {
+            let y = extrapolate(rate);
+            (min.min(y), max.max(y))
+        });
+        Ok(Estimate {
+            value: extrapolate(average),
+            min,
+            max,
+        })
+    }
+}
+
+// distance * dy / dx without overflow, and it is saturated at u64::MAX
+fn scale(distance: u64, dy: u64, dx: u64) -> u64 {
+    let value = distance as u128 * dy as u128 / dx as u128;
+    if value > u64::MAX as u128 {
+        u64::MAX
+    } else {
+        value as u64
+    }
+}
@@ -0,0 +1,129 @@
+use crate::constants::*;
+use crate::history::InfoValue;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FreshnessError {
+    InvalidContent,
+    // The since must be absolute and its metric must be block number or timestamp
+    InvalidSince,
+    // The reference can't be compared with the data type of the oracle
+    MetricMismatch,
+    NotFresh(Freshness),
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Freshness {
+    Fresh,
+    // The age in the unit of the data type
+    Stale(u64),
+    Halted,
+}
+
+// What the consuming transaction knows about the current time, which is always a lower bound of it.
+// A consumer script must require the Since of its own input, see common::reader::load_since_reference,
+// rather than take the reference the transaction offers: a header dep can be any old block and the
+// since of another input may be unset, either of which hides the staleness. The since of the own
+// input is not taken from the transaction freely, the consumer decides which one it is, and the
+// transaction can't be committed before it. The header references are for the off-chain readers.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Reference {
+    // The absolute since of one of the consuming transaction inputs
+    Since(u64),
+    // The timestamp(milliseconds) of a header dep
+    HeaderTimestamp(u64),
+    // The block number of a header dep
+    HeaderNumber(u64),
+}
+
+// The ages are in the unit of the data type of the oracle: seconds for Timestamp,
+// milliseconds for TimestampMillis and MedianTimestamp, and blocks for BlockNumber and BlockHash.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct FreshnessPolicy {
+    pub max_age: u64,
+    // The oracle is regarded as stopped when the age is bigger than it
+    pub halted_age: u64,
+}
+
+enum Metric {
+    Seconds,
+    Millis,
+    Blocks,
+}
+
+fn metric_of(data_type: u8) -> Option<Metric> {
+    if data_type == DataType::Timestamp as u8 {
+        Some(Metric::Seconds)
+    } else if data_type == DataType::TimestampMillis as u8
+        || data_type == DataType::MedianTimestamp as u8
+    {
+        Some(Metric::Millis)
+    } else if data_type == DataType::BlockNumber as u8 || data_type == DataType::BlockHash as u8 {
+        Some(Metric::Blocks)
+    } else {
+        None
+    }
+}
+
+// The current time in the unit of the data type
+fn now_of(metric: &Metric, reference: Reference) -> Result<u64, FreshnessError> {
+    match reference {
+        Reference::Since(since) => {
+            if since & SINCE_RELATIVE_FLAG != 0 {
+                return Err(FreshnessError::InvalidSince);
+            }
+            let value = since & SINCE_VALUE_MASK;
+            match (since & SINCE_METRIC_MASK, metric) {
+                (0, Metric::Blocks) => Ok(value),
+                (SINCE_TIMESTAMP_BASE, Metric::Seconds) => Ok(value),
+                (SINCE_TIMESTAMP_BASE, Metric::Millis) => {
+                    Ok(value.saturating_mul(MILLIS_PER_SECOND))
+                }
+                (0, _) | (SINCE_TIMESTAMP_BASE, _) => Err(FreshnessError::MetricMismatch),
+                _ => Err(FreshnessError::InvalidSince),
+            }
+        }
+        Reference::HeaderTimestamp(timestamp) => match metric {
+            Metric::Seconds => Ok(timestamp / MILLIS_PER_SECOND),
+            Metric::Millis => Ok(timestamp),
+            Metric::Blocks => Err(FreshnessError::MetricMismatch),
+        },
+        Reference::HeaderNumber(number) => match metric {
+            Metric::Blocks => Ok(number),
+            _ => Err(FreshnessError::MetricMismatch),
+        },
+    }
+}
+
+// Compares the latest value of the oracle, e.g. the first one of common::history::history,
+// with the since or the header deps of the consuming transaction.
+pub fn freshness(
+    latest: &InfoValue,
+    reference: Reference,
+    policy: &FreshnessPolicy,
+) -> Result<Freshness, FreshnessError> {
+    let metric = metric_of(latest.data_type).ok_or(FreshnessError::MetricMismatch)?;
+    let value = latest.as_u64().ok_or(FreshnessError::InvalidContent)?;
+    let now = now_of(&metric, reference)?;
+
+    // The oracle can be ahead of the reference, because the reference is only a lower bound
+    let age = now.saturating_sub(value);
+    if age <= policy.max_age {
+        Ok(Freshness::Fresh)
+    } else if age <= policy.halted_age {
+        Ok(Freshness::Stale(age))
+    } else {
+        Ok(Freshness::Halted)
+    }
+}
+
+// Returns the latest value of the oracle only if it is not older than the max age of the policy
+pub fn fresh_value(
+    latest: &InfoValue,
+    reference: Reference,
+    policy: &FreshnessPolicy,
+) -> Result<u64, FreshnessError> {
+    match freshness(latest, reference, policy)? {
+        Freshness::Fresh => latest.as_u64().ok_or(FreshnessError::InvalidContent),
+        not_fresh => Err(FreshnessError::NotFresh(not_fresh)),
+    }
+}
@@ -0,0 +1,17 @@
+use crate::constants::*;
+use blake2b_ref::{Blake2b, Blake2bBuilder};
+
+// The same blake2b hasher as ckb_hash::new_blake2b, so the hashes can be computed by any ckb sdk
+pub fn new_blake2b() -> Blake2b {
+    Blake2bBuilder::new(HASH_LEN)
+        .personal(CKB_HASH_PERSONALIZATION)
+        .build()
+}
+
+pub fn blake2b_256(data: &[u8]) -> [u8; HASH_LEN] {
+    let mut hasher = new_blake2b();
+    hasher.update(data);
+    let mut hash = [0u8; HASH_LEN];
+    hasher.finalize(&mut hash);
+    hash
+}
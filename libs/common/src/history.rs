@@ -0,0 +1,122 @@
+use crate::constants::*;
+use crate::hash::new_blake2b;
+use crate::index_state::{IndexState, IndexStateError};
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum HistoryError {
+    IndexState(IndexStateError),
+    InfoAmountError,
+    InfoDataLenError,
+    // The index which should be in the window but no info cell has it
+    InfoIndexMissing(u16),
+    InconsistentDataType,
+    // The info cell of the index which wasn't written at its sequence in the window
+    InfoSequenceMismatch(u16),
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct InfoValue<'a> {
+    pub index: u16,
+    pub data_type: u8,
+    // The sequence it was written at, only since version 3
+    pub sequence: Option<u64>,
+    pub content: &'a [u8],
+}
+
+impl<'a> InfoValue<'a> {
+    // The u64 content of Timestamp, BlockNumber and the other u64 data types
+    pub fn as_u64(&self) -> Option<u64> {
+        if self.content.len() < INFO_CELL_CONTENT_LEN {
+            return None;
+        }
+        let mut content_buf = [0u8; INFO_CELL_CONTENT_LEN];
+        content_buf.copy_from_slice(&self.content[..INFO_CELL_CONTENT_LEN]);
+        Some(u64::from_be_bytes(content_buf))
+    }
+}
+
+// Orders the info cells of a ring from the newest to the oldest one by the index of the index state.
+// The info cells may be in any order, and they must be the latest infos_data.len() ones of the ring,
+// so that both the whole ring and a shorter window of it can be read.
+pub fn history<'a>(
+    index_state_data: &[u8],
+    infos_data: &[&'a [u8]],
+) -> Result<Vec<InfoValue<'a>>, HistoryError> {
+    let index_state = IndexState::from_slice(index_state_data).map_err(HistoryError::IndexState)?;
+    if infos_data.is_empty()
+        || infos_data.len() > index_state.length as usize
+        || index_state.index >= index_state.length
+    {
+        return Err(HistoryError::InfoAmountError);
+    }
+
+    let layout = index_state.info_layout();
+    let mut values: Vec<InfoValue<'a>> = Vec::with_capacity(infos_data.len());
+    for info_data in infos_data.iter() {
+        if !layout.is_data_len_valid(info_data) {
+            return Err(HistoryError::InfoDataLenError);
+        }
+        values.push(InfoValue {
+            index: layout.index(info_data),
+            data_type: layout.data_type(info_data),
+            sequence: layout.sequence(info_data),
+            content: layout.content(info_data),
+        });
+    }
+
+    let data_type = values[0].data_type;
+    if values.iter().any(|value| value.data_type != data_type) {
+        return Err(HistoryError::InconsistentDataType);
+    }
+
+    let mut window: Vec<InfoValue<'a>> = Vec::with_capacity(values.len());
+    let mut index = index_state.index;
+    for offset in 0..values.len() {
+        let value = match values.iter().find(|value| value.index == index) {
+            Some(value) => *value,
+            None => return Err(HistoryError::InfoIndexMissing(index)),
+        };
+        // An info cell which is left behind by a skipped or replayed update has another sequence
+        if let Some(sequence) = index_state.sequence {
+            if value.sequence != sequence.checked_sub(offset as u64) {
+                return Err(HistoryError::InfoSequenceMismatch(index));
+            }
+        }
+        window.push(value);
+        index = previous_index(index, index_state.length);
+    }
+    Ok(window)
+}
+
+fn previous_index(index: u16, length: u16) -> u16 {
+    if index == 0 {
+        length - 1
+    } else {
+        index - 1
+    }
+}
+
+// The history root of version 2 index state is a hash chain: every update of an info cell
+// replaces the root with blake2b(root | output info data), starting from the root of creation.
+pub fn extend_history_root(history_root: &[u8; HASH_LEN], info_data: &[u8]) -> [u8; HASH_LEN] {
+    let mut hasher = new_blake2b();
+    hasher.update(history_root);
+    hasher.update(info_data);
+    let mut next_root = [0u8; HASH_LEN];
+    hasher.finalize(&mut next_root);
+    next_root
+}
+
+// Proves that the info data were published in order after the from_root, e.g. the root of an
+// index state cell which is known to be older, and that they lead to the to_root.
+pub fn verify_history_chain(
+    from_root: &[u8; HASH_LEN],
+    infos_data: &[&[u8]],
+    to_root: &[u8; HASH_LEN],
+) -> bool {
+    let root = infos_data.iter().fold(*from_root, |root, info_data| {
+        extend_history_root(&root, info_data)
+    });
+    &root == to_root
+}
@@ -0,0 +1,133 @@
+use crate::constants::*;
+use crate::info::InfoLayout;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum IndexStateError {
+    DataLenError,
+    VersionError,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct IndexState {
+    pub version: u8,
+    pub index: u16,
+    pub length: u16,
+    // The head of the hash chain of all the info data ever published, only since version 2
+    pub history_root: Option<[u8; HASH_LEN]>,
+    // The amount of the updates since creation, only since version 3
+    pub sequence: Option<u64>,
+    // The merkle root of the current info cells of the ring, only since version 4
+    pub info_root: Option<[u8; HASH_LEN]>,
+}
+
+impl IndexState {
+    // Legacy data: index(u8) | length(u8)
+    // Version 1 data: version(u8) | index(u16) | length(u16)
+    // Version 2 data: version(u8) | index(u16) | length(u16) | history_root(32 bytes)
+    // Version 3 data: version(u8) | index(u16) | length(u16) | history_root(32 bytes) | sequence(u64)
+    // Version 4 data: version 3 data | info_root(32 bytes)
+    // Version 5 data: the same as version 4, only the rules of the scripts are stricter
+    pub fn from_slice(data: &[u8]) -> Result<IndexState, IndexStateError> {
+        if data.len() == INDEX_STATE_CELL_DATA_LEN {
+            return Ok(IndexState {
+                version: INDEX_STATE_VERSION_LEGACY,
+                index: data[0] as u16,
+                length: data[1] as u16,
+                history_root: None,
+                sequence: None,
+                info_root: None,
+            });
+        }
+        let version = match data.first() {
+            Some(version) => *version,
+            None => return Err(IndexStateError::DataLenError),
+        };
+        let data_len = match version {
+            INDEX_STATE_VERSION_U16 => INDEX_STATE_V1_CELL_DATA_LEN,
+            INDEX_STATE_VERSION_HISTORY_ROOT => INDEX_STATE_V2_CELL_DATA_LEN,
+            INDEX_STATE_VERSION_SEQUENCE => INDEX_STATE_V3_CELL_DATA_LEN,
+            INDEX_STATE_VERSION_INFO_ROOT | INDEX_STATE_VERSION_UNIQUE => {
+                INDEX_STATE_V4_CELL_DATA_LEN
+            }
+            _ => return Err(IndexStateError::VersionError),
+        };
+        if data.len() != data_len {
+            return Err(IndexStateError::DataLenError);
+        }
+
+        // Every version keeps the data of the previous one and appends its own field
+        let history_root = match version >= INDEX_STATE_VERSION_HISTORY_ROOT {
+            true => {
+                let mut history_root = [0u8; HASH_LEN];
+                history_root.copy_from_slice(
+                    &data[INDEX_STATE_V1_CELL_DATA_LEN..INDEX_STATE_V2_CELL_DATA_LEN],
+                );
+                Some(history_root)
+            }
+            false => None,
+        };
+        let sequence = match version >= INDEX_STATE_VERSION_SEQUENCE {
+            true => {
+                let mut sequence_buf = [0u8; 8];
+                sequence_buf.copy_from_slice(
+                    &data[INDEX_STATE_V2_CELL_DATA_LEN..INDEX_STATE_V3_CELL_DATA_LEN],
+                );
+                Some(u64::from_be_bytes(sequence_buf))
+            }
+            false => None,
+        };
+        let info_root = match version >= INDEX_STATE_VERSION_INFO_ROOT {
+            true => {
+                let mut info_root = [0u8; HASH_LEN];
+                info_root.copy_from_slice(
+                    &data[INDEX_STATE_V3_CELL_DATA_LEN..INDEX_STATE_V4_CELL_DATA_LEN],
+                );
+                Some(info_root)
+            }
+            false => None,
+        };
+        Ok(IndexState {
+            version,
+            index: u16::from_be_bytes([data[1], data[2]]),
+            length: u16::from_be_bytes([data[3], data[4]]),
+            history_root,
+            sequence,
+            info_root,
+        })
+    }
+
+    // The index which must follow the current one, and it wraps around at the length
+    pub fn next_index(&self, length: u16) -> u16 {
+        if self.index as u32 + 1 >= length as u32 {
+            0
+        } else {
+            self.index + 1
+        }
+    }
+
+    pub fn info_layout(&self) -> InfoLayout {
+        InfoLayout::of_version(self.version)
+    }
+
+    // The cell data of the version, which is parsed by from_slice, it is used off-chain
+    pub fn to_vec(&self) -> Vec<u8> {
+        if self.version == INDEX_STATE_VERSION_LEGACY {
+            return [self.index as u8, self.length as u8].to_vec();
+        }
+        let mut data = Vec::with_capacity(INDEX_STATE_V4_CELL_DATA_LEN);
+        data.push(self.version);
+        data.extend_from_slice(&self.index.to_be_bytes());
+        data.extend_from_slice(&self.length.to_be_bytes());
+        if let Some(history_root) = self.history_root {
+            data.extend_from_slice(&history_root);
+        }
+        if let Some(sequence) = self.sequence {
+            data.extend_from_slice(&sequence.to_be_bytes());
+        }
+        if let Some(info_root) = self.info_root {
+            data.extend_from_slice(&info_root);
+        }
+        data
+    }
+}
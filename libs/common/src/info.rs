@@ -0,0 +1,126 @@
+use crate::constants::*;
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ContentLen {
+    Fixed(usize),
+    Variable { min: usize, max: usize },
+}
+
+// Every data type decides the length of its content, and unknown data types keep the u64 content
+pub fn content_len_rule(data_type: u8) -> ContentLen {
+    if data_type == DataType::BlockHash as u8 {
+        ContentLen::Fixed(BLOCK_HASH_INFO_CELL_DATA_LEN - INFO_CELL_META_LEN)
+    } else if data_type == DataType::Uint128 as u8 {
+        ContentLen::Fixed(16)
+    } else if data_type == DataType::Hash as u8 {
+        ContentLen::Fixed(32)
+    } else if data_type == DataType::Payload as u8 {
+        ContentLen::Variable {
+            min: 1,
+            max: MAX_INFO_CELL_CONTENT_LEN,
+        }
+    } else {
+        ContentLen::Fixed(INFO_CELL_CONTENT_LEN)
+    }
+}
+
+// The meta of the info cell data follows the version of its index state cell
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct InfoLayout {
+    pub index_len: usize,
+    pub sequence_len: usize,
+}
+
+impl InfoLayout {
+    // index(u8) | type(u8) | content
+    pub const LEGACY: InfoLayout = InfoLayout {
+        index_len: 1,
+        sequence_len: 0,
+    };
+    // index(u16) | type(u8) | content
+    pub const V1: InfoLayout = InfoLayout {
+        index_len: 2,
+        sequence_len: 0,
+    };
+    // index(u16) | type(u8) | sequence(u64) | content
+    pub const V3: InfoLayout = InfoLayout {
+        index_len: 2,
+        sequence_len: 8,
+    };
+
+    // The layout of the info cells of an index state version
+    pub fn of_version(version: u8) -> InfoLayout {
+        match version {
+            INDEX_STATE_VERSION_LEGACY => InfoLayout::LEGACY,
+            INDEX_STATE_VERSION_U16 | INDEX_STATE_VERSION_HISTORY_ROOT => InfoLayout::V1,
+            _ => InfoLayout::V3,
+        }
+    }
+
+    pub fn meta_len(&self) -> usize {
+        self.index_len + 1 + self.sequence_len
+    }
+
+    pub fn type_pos(&self) -> usize {
+        self.index_len
+    }
+
+    pub fn is_data_len_valid(&self, info_data: &[u8]) -> bool {
+        if info_data.len() < self.meta_len() {
+            return false;
+        }
+        let content_len = info_data.len() - self.meta_len();
+        match content_len_rule(info_data[self.type_pos()]) {
+            ContentLen::Fixed(len) => content_len == len,
+            ContentLen::Variable { min, max } => content_len >= min && content_len <= max,
+        }
+    }
+
+    // The info data must be checked by is_data_len_valid before reading it
+    pub fn index(&self, info_data: &[u8]) -> u16 {
+        match self.index_len {
+            1 => info_data[0] as u16,
+            _ => u16::from_be_bytes([info_data[0], info_data[1]]),
+        }
+    }
+
+    pub fn data_type(&self, info_data: &[u8]) -> u8 {
+        info_data[self.type_pos()]
+    }
+
+    // The sequence of the index state when the info cell was written, only since version 3
+    pub fn sequence(&self, info_data: &[u8]) -> Option<u64> {
+        if self.sequence_len == 0 {
+            return None;
+        }
+        let mut sequence_buf = [0u8; 8];
+        sequence_buf.copy_from_slice(&info_data[self.type_pos() + 1..self.meta_len()]);
+        Some(u64::from_be_bytes(sequence_buf))
+    }
+
+    pub fn content<'a>(&self, info_data: &'a [u8]) -> &'a [u8] {
+        &info_data[self.meta_len()..]
+    }
+
+    // The info cell data of the layout, the sequence is ignored by the layouts without it
+    pub fn build(
+        &self,
+        index: u16,
+        data_type: u8,
+        sequence: Option<u64>,
+        content: &[u8],
+    ) -> Vec<u8> {
+        let mut info_data = Vec::with_capacity(self.meta_len() + content.len());
+        match self.index_len {
+            1 => info_data.push(index as u8),
+            _ => info_data.extend_from_slice(&index.to_be_bytes()),
+        }
+        info_data.push(data_type);
+        if self.sequence_len > 0 {
+            info_data.extend_from_slice(&sequence.unwrap_or(0).to_be_bytes());
+        }
+        info_data.extend_from_slice(content);
+        info_data
+    }
+}
@@ -1,3 +1,16 @@
 #![no_std]
 
+extern crate alloc;
+
+pub mod aggregate;
 pub mod constants;
+pub mod estimate;
+pub mod freshness;
+pub mod hash;
+pub mod history;
+pub mod index_state;
+pub mod info;
+pub mod merkle;
+pub mod script_error;
+#[cfg(feature = "script")]
+pub mod reader;
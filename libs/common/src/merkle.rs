@@ -0,0 +1,120 @@
+use crate::constants::*;
+use crate::hash::{blake2b_256, new_blake2b};
+use alloc::vec::Vec;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum MerkleError {
+    ProofLenError,
+    InfoAmountError,
+    InfoDataLenError,
+}
+
+// The info root of version 4 index state is the root of a binary merkle tree whose leaf i is the
+// hash of the info cell data of index i. The leaves are padded with zero hashes to a power of two.
+pub fn merkle_depth(length: u16) -> usize {
+    let mut depth = 0;
+    while (1usize << depth) < length as usize {
+        depth += 1;
+    }
+    depth
+}
+
+pub fn info_leaf(info_data: &[u8]) -> [u8; HASH_LEN] {
+    blake2b_256(info_data)
+}
+
+fn merge(left: &[u8], right: &[u8]) -> [u8; HASH_LEN] {
+    let mut hasher = new_blake2b();
+    hasher.update(left);
+    hasher.update(right);
+    let mut node = [0u8; HASH_LEN];
+    hasher.finalize(&mut node);
+    node
+}
+
+fn leaves_of(infos_data: &[&[u8]]) -> Vec<[u8; HASH_LEN]> {
+    let mut leaves: Vec<[u8; HASH_LEN]> = infos_data.iter().map(|data| info_leaf(data)).collect();
+    leaves.resize(1 << merkle_depth(infos_data.len() as u16), [0u8; HASH_LEN]);
+    leaves
+}
+
+// The info cells must be the whole ring ordered by their index, it is used off-chain
+pub fn info_root(infos_data: &[&[u8]]) -> [u8; HASH_LEN] {
+    let mut nodes = leaves_of(infos_data);
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| merge(&pair[0], &pair[1]))
+            .collect();
+    }
+    nodes[0]
+}
+
+// The siblings from the leaf to the root of the info cell of the index, it is used off-chain
+pub fn info_proof(infos_data: &[&[u8]], index: u16) -> Vec<u8> {
+    let mut nodes = leaves_of(infos_data);
+    let mut position = index as usize;
+    let mut proof = Vec::with_capacity(HASH_LEN * merkle_depth(infos_data.len() as u16));
+    while nodes.len() > 1 {
+        proof.extend_from_slice(&nodes[position ^ 1]);
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| merge(&pair[0], &pair[1]))
+            .collect();
+        position >>= 1;
+    }
+    proof
+}
+
+// Proof: the sibling hashes(32 bytes each) from the leaf to the root
+pub fn root_from_proof(
+    info_data: &[u8],
+    index: u16,
+    length: u16,
+    proof: &[u8],
+) -> Result<[u8; HASH_LEN], MerkleError> {
+    if index >= length || proof.len() != HASH_LEN * merkle_depth(length) {
+        return Err(MerkleError::ProofLenError);
+    }
+    let mut node = info_leaf(info_data);
+    let mut position = index;
+    for sibling in proof.chunks(HASH_LEN) {
+        node = match position & 1 {
+            0 => merge(&node, sibling),
+            _ => merge(sibling, &node),
+        };
+        position >>= 1;
+    }
+    Ok(node)
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct InfoProof<'a> {
+    pub info_data: &'a [u8],
+    pub proof: &'a [u8],
+}
+
+// Proofs data: [info_data_len(u16) | info_data | proof] repeated, every proof has the depth of the ring
+pub fn parse_info_proofs(data: &[u8], length: u16) -> Result<Vec<InfoProof<'_>>, MerkleError> {
+    let proof_len = HASH_LEN * merkle_depth(length);
+    let mut proofs = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        if rest.len() < 2 {
+            return Err(MerkleError::InfoDataLenError);
+        }
+        let info_data_len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+        if rest.len() < 2 + info_data_len + proof_len {
+            return Err(MerkleError::ProofLenError);
+        }
+        proofs.push(InfoProof {
+            info_data: &rest[2..2 + info_data_len],
+            proof: &rest[2 + info_data_len..2 + info_data_len + proof_len],
+        });
+        rest = &rest[2 + info_data_len + proof_len..];
+    }
+    if proofs.is_empty() || proofs.len() > length as usize {
+        return Err(MerkleError::InfoAmountError);
+    }
+    Ok(proofs)
+}
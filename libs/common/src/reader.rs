@@ -0,0 +1,270 @@
+use crate::aggregate::{aggregate, AggregateError, AggregatePolicy};
+use crate::constants::*;
+use crate::freshness::Reference;
+use crate::history::{history, HistoryError, InfoValue};
+use crate::index_state::{IndexState, IndexStateError};
+use crate::merkle::{parse_info_proofs, root_from_proof};
+use alloc::vec::Vec;
+use ckb_std::{
+    ckb_constants::Source,
+    error::SysError,
+    high_level::{
+        load_cell_data, load_cell_type, load_cell_type_hash, load_input_since, QueryIter,
+    },
+};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ReaderError {
+    Syscall,
+    IndexStateNotFound,
+    // More than one cell dep has the type script of the index state cell
+    IndexStateNotUnique,
+    InfoNotFound,
+    // More than one info cell dep has the index the index state points to
+    InfoNotUnique,
+    History(HistoryError),
+    InvalidDataType,
+    InvalidInfoProof,
+}
+
+impl From<SysError> for ReaderError {
+    fn from(_: SysError) -> Self {
+        ReaderError::Syscall
+    }
+}
+
+impl From<HistoryError> for ReaderError {
+    fn from(err: HistoryError) -> Self {
+        ReaderError::History(err)
+    }
+}
+
+// The index state cell and the info cells of an oracle instance found in the cell deps
+pub struct OracleCellDeps {
+    pub index_state_data: Vec<u8>,
+    pub infos_data: Vec<Vec<u8>>,
+}
+
+impl OracleCellDeps {
+    // The latest info of the oracle, which needs only the info cell the index state points to
+    pub fn latest(&self) -> Result<InfoValue<'_>, ReaderError> {
+        let index_state = IndexState::from_slice(&self.index_state_data)
+            .map_err(|err| ReaderError::History(HistoryError::IndexState(err)))?;
+        let layout = index_state.info_layout();
+        let latest_pos = unique_position(
+            self.infos_data.iter().map(|info_data| {
+                layout.is_data_len_valid(info_data) && layout.index(info_data) == index_state.index
+            }),
+            ReaderError::InfoNotFound,
+            ReaderError::InfoNotUnique,
+        )?;
+        let window = history(
+            &self.index_state_data,
+            &[self.infos_data[latest_pos].as_slice()],
+        )?;
+        Ok(window[0])
+    }
+
+    // The latest timestamp(seconds) of a Timestamp, TimestampMillis or MedianTimestamp oracle
+    pub fn latest_timestamp(&self) -> Result<u64, ReaderError> {
+        let latest = self.latest()?;
+        let value = latest.as_u64().ok_or(ReaderError::InvalidDataType)?;
+        if latest.data_type == DataType::Timestamp as u8 {
+            Ok(value)
+        } else if latest.data_type == DataType::TimestampMillis as u8
+            || latest.data_type == DataType::MedianTimestamp as u8
+        {
+            Ok(value / MILLIS_PER_SECOND)
+        } else {
+            Err(ReaderError::InvalidDataType)
+        }
+    }
+
+    // All the info cells in the cell deps from the newest to the oldest one
+    pub fn history(&self) -> Result<Vec<InfoValue<'_>>, ReaderError> {
+        let infos_data: Vec<&[u8]> = self.infos_data.iter().map(|data| data.as_slice()).collect();
+        Ok(history(&self.index_state_data, &infos_data)?)
+    }
+}
+
+// The since of the first input of the script group, i.e. the cell of the consumer itself, which is
+// the reference the consumers must require, see common::freshness::Reference
+pub fn load_since_reference() -> Result<Reference, ReaderError> {
+    Ok(Reference::Since(load_input_since(0, Source::GroupInput)?))
+}
+
+// The index state cell is found by its type script hash, and the info cells are the cell deps
+// whose type script has the info type code hash and the same args as the index state cell.
+pub fn load_oracle_cell_deps(
+    index_state_type_hash: &[u8],
+    info_type_code_hash: &[u8],
+) -> Result<OracleCellDeps, ReaderError> {
+    let index_state_pos = find_index_state_cell_dep(index_state_type_hash)?;
+    load_oracle_cell_deps_at(index_state_pos, info_type_code_hash)
+}
+
+// Only the version 4 index state cell needs to be in the cell deps, and the info cells are taken from
+// the proofs data, e.g. a field of the witness, after they are proven by the info root of it. The
+// root is only recomputed by index-state-type when its args pin the info type code hash.
+pub fn load_proven_oracle(
+    index_state_type_hash: &[u8],
+    info_type_code_hash: &[u8],
+    proofs_data: &[u8],
+) -> Result<OracleCellDeps, ReaderError> {
+    let index_state_pos = find_index_state_cell_dep(index_state_type_hash)?;
+    let index_state_args = match load_cell_type(index_state_pos, Source::CellDep)? {
+        Some(type_script) => type_script.args().raw_data(),
+        None => return Err(ReaderError::IndexStateNotFound),
+    };
+    if index_state_args.len() != ORACLE_ARGS_LEN
+        || index_state_args[OUT_POINT_LEN..] != info_type_code_hash[..]
+    {
+        return Err(ReaderError::InvalidInfoProof);
+    }
+    let index_state_data = load_cell_data(index_state_pos, Source::CellDep)?;
+    let index_state = IndexState::from_slice(&index_state_data)
+        .map_err(|err| ReaderError::History(HistoryError::IndexState(err)))?;
+    let info_root = index_state
+        .info_root
+        .ok_or(ReaderError::History(HistoryError::IndexState(
+            IndexStateError::VersionError,
+        )))?;
+
+    let layout = index_state.info_layout();
+    let proofs = parse_info_proofs(proofs_data, index_state.length)
+        .map_err(|_| ReaderError::InvalidInfoProof)?;
+    let mut infos_data = Vec::with_capacity(proofs.len());
+    for proof in proofs.iter() {
+        if !layout.is_data_len_valid(proof.info_data) {
+            return Err(ReaderError::InvalidInfoProof);
+        }
+        let index = layout.index(proof.info_data);
+        match root_from_proof(proof.info_data, index, index_state.length, proof.proof) {
+            Ok(root) if root == info_root => infos_data.push(proof.info_data.to_vec()),
+            _ => return Err(ReaderError::InvalidInfoProof),
+        }
+    }
+
+    Ok(OracleCellDeps {
+        index_state_data,
+        infos_data,
+    })
+}
+
+// The index state cell is unique on chain, see index-state-type, so another cell dep with its type
+// script is a forged one and the oracle can't be trusted
+fn find_index_state_cell_dep(index_state_type_hash: &[u8]) -> Result<usize, ReaderError> {
+    unique_position(
+        QueryIter::new(load_cell_type_hash, Source::CellDep).map(|type_hash| match type_hash {
+            Some(type_hash) => type_hash[..] == index_state_type_hash[..],
+            None => false,
+        }),
+        ReaderError::IndexStateNotFound,
+        ReaderError::IndexStateNotUnique,
+    )
+}
+
+// The position of the only matched item
+fn unique_position<I: Iterator<Item = bool>>(
+    matches: I,
+    not_found: ReaderError,
+    not_unique: ReaderError,
+) -> Result<usize, ReaderError> {
+    let mut position = None;
+    for (pos, matched) in matches.enumerate() {
+        if !matched {
+            continue;
+        }
+        if position.is_some() {
+            return Err(not_unique);
+        }
+        position = Some(pos);
+    }
+    position.ok_or(not_found)
+}
+
+// The same as load_oracle_cell_deps, but the index state cell is found by its code hash and args
+pub fn load_oracle_cell_deps_by_args(
+    index_state_code_hash: &[u8],
+    info_type_code_hash: &[u8],
+    index_state_args: &[u8],
+) -> Result<OracleCellDeps, ReaderError> {
+    let index_state_pos = unique_position(
+        QueryIter::new(load_cell_type, Source::CellDep).map(|type_script| match type_script {
+            Some(type_script) => {
+                type_script.code_hash().raw_data()[..] == index_state_code_hash[..]
+                    && type_script.args().raw_data()[..] == index_state_args[..]
+            }
+            None => false,
+        }),
+        ReaderError::IndexStateNotFound,
+        ReaderError::IndexStateNotUnique,
+    )?;
+    load_oracle_cell_deps_at(index_state_pos, info_type_code_hash)
+}
+
+fn load_oracle_cell_deps_at(
+    index_state_pos: usize,
+    info_type_code_hash: &[u8],
+) -> Result<OracleCellDeps, ReaderError> {
+    let index_state_data = load_cell_data(index_state_pos, Source::CellDep)?;
+    let index_state_args = match load_cell_type(index_state_pos, Source::CellDep)? {
+        Some(type_script) => type_script.args().raw_data(),
+        None => return Err(ReaderError::IndexStateNotFound),
+    };
+
+    let mut infos_data = Vec::new();
+    for (pos, type_script) in QueryIter::new(load_cell_type, Source::CellDep).enumerate() {
+        if let Some(type_script) = type_script {
+            if type_script.code_hash().raw_data()[..] == info_type_code_hash[..]
+                && type_script.args().raw_data()[..] == index_state_args[..]
+            {
+                infos_data.push(load_cell_data(pos, Source::CellDep)?);
+            }
+        }
+    }
+    if infos_data.is_empty() {
+        return Err(ReaderError::InfoNotFound);
+    }
+
+    Ok(OracleCellDeps {
+        index_state_data,
+        infos_data,
+    })
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AggregateReaderError {
+    Reader(ReaderError),
+    Aggregate(AggregateError),
+}
+
+// Aggregates the latest values of the oracle instances, which are identified by their index state args,
+// in the cell deps. The instances which are not in the cell deps are not counted for the quorum.
+// The reference should be the one of load_since_reference.
+pub fn aggregate_oracle_cell_deps(
+    index_state_code_hash: &[u8],
+    info_type_code_hash: &[u8],
+    instances_args: &[&[u8]],
+    reference: Reference,
+    policy: &AggregatePolicy,
+) -> Result<u64, AggregateReaderError> {
+    let mut oracles = Vec::with_capacity(instances_args.len());
+    for args in instances_args.iter() {
+        match load_oracle_cell_deps_by_args(index_state_code_hash, info_type_code_hash, args) {
+            Ok(oracle) => oracles.push(oracle),
+            Err(ReaderError::IndexStateNotFound) | Err(ReaderError::InfoNotFound) => {}
+            Err(err) => return Err(AggregateReaderError::Reader(err)),
+        }
+    }
+
+    let mut latest_values = Vec::with_capacity(oracles.len());
+    for oracle in oracles.iter() {
+        match oracle.latest() {
+            Ok(latest) => latest_values.push(latest),
+            Err(ReaderError::InfoNotFound) => {}
+            Err(err) => return Err(AggregateReaderError::Reader(err)),
+        }
+    }
+    aggregate(&latest_values, reference, policy).map_err(AggregateReaderError::Aggregate)
+}
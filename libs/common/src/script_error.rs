@@ -0,0 +1,55 @@
+// The exit codes of the oracle type scripts and the names of their Error variants, which must follow
+// contracts/index-state-type/src/error.rs and contracts/info-type/src/error.rs. They are used
+// off-chain to report the failures of the scripts.
+pub const INDEX_STATE_TYPE_ERRORS: &[(i8, &str)] = &[
+    (1, "IndexOutOfBound"),
+    (2, "ItemMissing"),
+    (3, "LengthNotEnough"),
+    (4, "Encoding"),
+    (5, "InvalidArgument"),
+    (6, "IndexStateTypeNotExist"),
+    (7, "IndexStateDataLenError"),
+    (8, "InfoAmountError"),
+    (9, "IndexStateOutOfBound"),
+    (10, "IndexIncreaseError"),
+    (11, "IndexStateVersionError"),
+    (12, "IndexStateSequenceError"),
+    (13, "IndexStateNotUnique"),
+    (14, "InfoNotUpdated"),
+    (15, "InvalidHistoryRoot"),
+    (16, "InvalidInfoProof"),
+    (17, "InvalidInfoRoot"),
+];
+
+pub const INFO_TYPE_ERRORS: &[(i8, &str)] = &[
+    (1, "IndexOutOfBound"),
+    (2, "ItemMissing"),
+    (3, "LengthNotEnough"),
+    (4, "Encoding"),
+    (5, "InvalidArgument"),
+    (6, "InfoDataLenError"),
+    (7, "IndexStateDataLenError"),
+    (8, "InfoTypeNotExist"),
+    (9, "InfoIndexNotSame"),
+    (10, "OutputTimestampNotBigger"),
+    (11, "OutputBlockNumberNotBigger"),
+    (12, "InvalidTimeInfoSince"),
+    (13, "IndexStateTypeNotExist"),
+    (14, "HeaderDepNotExist"),
+    (15, "InvalidHeaderDepTimestamp"),
+    (16, "InvalidHeaderDeps"),
+    (17, "InvalidMedianTimestamp"),
+    (18, "InvalidHeaderDepBlockHash"),
+    (19, "InvalidHistoryRoot"),
+    (20, "InvalidInfoSequence"),
+    (21, "InvalidInfoProof"),
+    (22, "InvalidInfoRoot"),
+    (23, "InfoNotUnique"),
+];
+
+pub fn error_name(errors: &[(i8, &'static str)], code: i8) -> Option<&'static str> {
+    errors
+        .iter()
+        .find(|(error_code, _)| *error_code == code)
+        .map(|(_, name)| *name)
+}
@@ -0,0 +1,51 @@
+use crate::error::MigrationError;
+use crate::{Migration, INDEX_STATE_TYPE, INFO_TYPE};
+use ckb_types::{packed::CellOutput, prelude::*, H256};
+use std::fs;
+use std::path::Path;
+
+// The binaries of build/release which are deployed by deployment.toml
+pub const ORACLE_BINARIES: [&str; 2] = [INDEX_STATE_TYPE, INFO_TYPE];
+
+// The ckb-blake2b hash of the binary, which is the data_hash of its cell recipe
+pub fn data_hash(binary: &[u8]) -> H256 {
+    CellOutput::calc_data_hash(binary).unpack()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryCheck {
+    pub name: String,
+    pub data_hash: H256,
+    pub recorded_data_hash: H256,
+}
+
+impl BinaryCheck {
+    pub fn is_matched(&self) -> bool {
+        self.data_hash == self.recorded_data_hash
+    }
+}
+
+impl Migration {
+    pub fn check_binary(&self, name: &str, binary: &[u8]) -> Result<BinaryCheck, MigrationError> {
+        Ok(BinaryCheck {
+            name: name.to_string(),
+            data_hash: data_hash(binary),
+            recorded_data_hash: self.cell_recipe(name)?.data_hash.clone(),
+        })
+    }
+
+    // Checks the oracle binaries of the directory, e.g. build/release
+    pub fn check_binaries<P: AsRef<Path>>(
+        &self,
+        binaries_dir: P,
+    ) -> Result<Vec<BinaryCheck>, MigrationError> {
+        ORACLE_BINARIES
+            .iter()
+            .map(|name| {
+                let binary = fs::read(binaries_dir.as_ref().join(name))
+                    .map_err(|err| MigrationError::Io(format!("{} {}", name, err)))?;
+                self.check_binary(name, &binary)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{CellRecipe, DepGroupRecipe, Migration};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellRecipeDiff {
+    pub name: String,
+    pub old: Option<CellRecipe>,
+    pub new: Option<CellRecipe>,
+}
+
+impl CellRecipeDiff {
+    pub fn is_data_changed(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => old.data_hash != new.data_hash,
+            _ => true,
+        }
+    }
+
+    // The consumers refer to the binary by the type id, which must be kept by an upgrade
+    pub fn is_type_id_stable(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => old.type_id.is_some() && old.type_id == new.type_id,
+            _ => false,
+        }
+    }
+
+    // The binary is deployed into another cell
+    pub fn is_cell_moved(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => old.out_point() != new.out_point(),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepGroupDiff {
+    pub name: String,
+    pub old: Option<DepGroupRecipe>,
+    pub new: Option<DepGroupRecipe>,
+}
+
+impl DepGroupDiff {
+    pub fn is_rebuilt(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => old.out_point() != new.out_point(),
+            _ => true,
+        }
+    }
+}
+
+// The recipes of two migrations by name, in the order of the old migration then the new one
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationDiff {
+    pub cells: Vec<CellRecipeDiff>,
+    pub dep_groups: Vec<DepGroupDiff>,
+}
+
+impl MigrationDiff {
+    pub fn new(old: &Migration, new: &Migration) -> MigrationDiff {
+        let cells = names(&old.cell_recipes, &new.cell_recipes, |recipe| &recipe.name)
+            .into_iter()
+            .map(|name| CellRecipeDiff {
+                old: old.cell_recipe(&name).ok().cloned(),
+                new: new.cell_recipe(&name).ok().cloned(),
+                name,
+            })
+            .collect();
+        let dep_groups = names(&old.dep_group_recipes, &new.dep_group_recipes, |recipe| {
+            &recipe.name
+        })
+        .into_iter()
+        .map(|name| DepGroupDiff {
+            old: old.dep_group_recipe(&name).ok().cloned(),
+            new: new.dep_group_recipe(&name).ok().cloned(),
+            name,
+        })
+        .collect();
+        MigrationDiff { cells, dep_groups }
+    }
+
+    // Every binary of the old migration is still referred to by the same type id
+    pub fn keeps_type_ids(&self) -> bool {
+        self.cells
+            .iter()
+            .filter(|cell| cell.old.is_some())
+            .all(|cell| cell.is_type_id_stable())
+    }
+
+    // A dep group which is not rebuilt still has the old cells, so the moved binaries are not
+    // in it
+    pub fn is_dep_group_stale(&self) -> bool {
+        self.cells.iter().any(|cell| cell.is_cell_moved())
+            && self
+                .dep_groups
+                .iter()
+                .any(|dep_group| !dep_group.is_rebuilt())
+    }
+}
+
+fn names<T, F>(old: &[T], new: &[T], name_of: F) -> Vec<String>
+where
+    F: Fn(&T) -> &String,
+{
+    let mut names: Vec<String> = Vec::new();
+    for recipe in old.iter().chain(new.iter()) {
+        let name = name_of(recipe);
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names
+}
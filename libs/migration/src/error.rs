@@ -0,0 +1,31 @@
+use crate::network::Network;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationError {
+    Io(String),
+    InvalidJson(String),
+    NoMigration(Network),
+    CellRecipeNotFound(String),
+    DepGroupRecipeNotFound(String),
+    TypeIdNotFound(String),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Io(err) => write!(f, "io error: {}", err),
+            MigrationError::InvalidJson(err) => write!(f, "invalid migration json: {}", err),
+            MigrationError::NoMigration(network) => {
+                write!(f, "no migration of {}", network.dir_name())
+            }
+            MigrationError::CellRecipeNotFound(name) => write!(f, "no cell recipe of {}", name),
+            MigrationError::DepGroupRecipeNotFound(name) => {
+                write!(f, "no dep group recipe of {}", name)
+            }
+            MigrationError::TypeIdNotFound(name) => write!(f, "{} has no type id", name),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
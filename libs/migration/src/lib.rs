@@ -0,0 +1,179 @@
+pub mod check;
+pub mod diff;
+pub mod error;
+pub mod network;
+
+use ckb_types::{
+    bytes::Bytes,
+    core::{DepType, ScriptHashType},
+    packed::{CellDep, OutPoint, Script},
+    prelude::*,
+    H256,
+};
+use error::MigrationError;
+use network::Network;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const INDEX_STATE_TYPE: &str = "index-state-type";
+pub const INFO_TYPE: &str = "info-type";
+// The dep group of the two oracle type scripts in deployment.toml
+pub const ORACLE_DEP_GROUP: &str = "oracle_dep_groups";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CellRecipe {
+    pub name: String,
+    pub tx_hash: H256,
+    pub index: u32,
+    pub occupied_capacity: u64,
+    pub data_hash: H256,
+    pub type_id: Option<H256>,
+}
+
+impl CellRecipe {
+    pub fn out_point(&self) -> OutPoint {
+        build_out_point(&self.tx_hash, self.index)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DepGroupRecipe {
+    pub name: String,
+    pub tx_hash: H256,
+    pub index: u32,
+    pub occupied_capacity: u64,
+}
+
+impl DepGroupRecipe {
+    pub fn out_point(&self) -> OutPoint {
+        build_out_point(&self.tx_hash, self.index)
+    }
+}
+
+fn build_out_point(tx_hash: &H256, index: u32) -> OutPoint {
+    OutPoint::new_builder()
+        .tx_hash(tx_hash.pack())
+        .index(index.pack())
+        .build()
+}
+
+// The migration JSON which capsule writes into migrations/<network> for every deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Migration {
+    pub cell_recipes: Vec<CellRecipe>,
+    pub dep_group_recipes: Vec<DepGroupRecipe>,
+}
+
+impl Migration {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Migration, MigrationError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| MigrationError::Io(err.to_string()))?;
+        serde_json::from_str(&content).map_err(|err| MigrationError::InvalidJson(err.to_string()))
+    }
+
+    // The migration files are named by their time, e.g. 2021-08-24-000000.json, so the latest one
+    // is the last by name
+    pub fn latest_path<P: AsRef<Path>>(
+        migrations_dir: P,
+        network: Network,
+    ) -> Result<PathBuf, MigrationError> {
+        let dir = migrations_dir.as_ref().join(network.dir_name());
+        let entries = fs::read_dir(&dir).map_err(|err| MigrationError::Io(err.to_string()))?;
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|err| MigrationError::Io(err.to_string()))?
+                .path();
+            if path.extension() == Some(OsStr::new("json")) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        paths.pop().ok_or(MigrationError::NoMigration(network))
+    }
+
+    pub fn latest<P: AsRef<Path>>(
+        migrations_dir: P,
+        network: Network,
+    ) -> Result<Migration, MigrationError> {
+        Migration::from_file(Migration::latest_path(migrations_dir, network)?)
+    }
+
+    pub fn cell_recipe(&self, name: &str) -> Result<&CellRecipe, MigrationError> {
+        self.cell_recipes
+            .iter()
+            .find(|recipe| recipe.name == name)
+            .ok_or_else(|| MigrationError::CellRecipeNotFound(name.to_string()))
+    }
+
+    pub fn dep_group_recipe(&self, name: &str) -> Result<&DepGroupRecipe, MigrationError> {
+        self.dep_group_recipes
+            .iter()
+            .find(|recipe| recipe.name == name)
+            .ok_or_else(|| MigrationError::DepGroupRecipeNotFound(name.to_string()))
+    }
+
+    // The type scripts of the oracle refer to their binaries by the type ids
+    pub fn type_id(&self, name: &str) -> Result<&H256, MigrationError> {
+        self.cell_recipe(name)?
+            .type_id
+            .as_ref()
+            .ok_or_else(|| MigrationError::TypeIdNotFound(name.to_string()))
+    }
+
+    pub fn index_state_type_code_hash(&self) -> Result<&H256, MigrationError> {
+        self.type_id(INDEX_STATE_TYPE)
+    }
+
+    pub fn info_type_code_hash(&self) -> Result<&H256, MigrationError> {
+        self.type_id(INFO_TYPE)
+    }
+
+    // The args of the type scripts of an oracle since version 2, the out point of the first input
+    // of its genesis transaction and then the info type code hash, see index-state-type
+    pub fn oracle_args(&self, out_point: &[u8]) -> Result<Vec<u8>, MigrationError> {
+        let mut args = out_point.to_vec();
+        args.extend_from_slice(self.info_type_code_hash()?.as_bytes());
+        Ok(args)
+    }
+
+    // Both type scripts of an oracle have the same args, the out point of the first input of its
+    // genesis transaction, which is followed by the info type code hash since version 2, see
+    // oracle_args
+    pub fn index_state_type_script(&self, args: &[u8]) -> Result<Script, MigrationError> {
+        Ok(build_type_id_script(
+            self.index_state_type_code_hash()?,
+            args,
+        ))
+    }
+
+    pub fn info_type_script(&self, args: &[u8]) -> Result<Script, MigrationError> {
+        Ok(build_type_id_script(self.info_type_code_hash()?, args))
+    }
+
+    // The cell dep of the deployed binary itself
+    pub fn cell_dep(&self, name: &str) -> Result<CellDep, MigrationError> {
+        Ok(CellDep::new_builder()
+            .out_point(self.cell_recipe(name)?.out_point())
+            .dep_type(DepType::Code.into())
+            .build())
+    }
+
+    // The dep group of both oracle type scripts
+    pub fn oracle_cell_dep(&self) -> Result<CellDep, MigrationError> {
+        Ok(CellDep::new_builder()
+            .out_point(self.dep_group_recipe(ORACLE_DEP_GROUP)?.out_point())
+            .dep_type(DepType::DepGroup.into())
+            .build())
+    }
+}
+
+fn build_type_id_script(code_hash: &H256, args: &[u8]) -> Script {
+    Script::new_builder()
+        .code_hash(code_hash.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(Bytes::copy_from_slice(args).pack())
+        .build()
+}
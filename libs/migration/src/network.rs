@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// The networks of the directories in migrations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Dev,
+    Testnet,
+    Mainnet,
+}
+
+impl Network {
+    pub const ALL: [Network; 3] = [Network::Dev, Network::Testnet, Network::Mainnet];
+
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            Network::Dev => "dev",
+            Network::Testnet => "testnet",
+            Network::Mainnet => "mainnet",
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL
+            .iter()
+            .copied()
+            .find(|network| network.dir_name() == s.to_lowercase())
+            .ok_or("no match")
+    }
+}
@@ -0,0 +1,163 @@
+use common::{aggregate::*, constants::*, freshness::*, history::InfoValue};
+
+const FRESHNESS_POLICY: FreshnessPolicy = FreshnessPolicy {
+    max_age: 600,
+    halted_age: 86400,
+};
+
+fn build_policy(aggregation: Aggregation, quorum: usize) -> AggregatePolicy {
+    AggregatePolicy {
+        aggregation,
+        quorum,
+        freshness: FRESHNESS_POLICY,
+    }
+}
+
+fn build_latest(data_type: DataType, content: &[u8]) -> InfoValue<'_> {
+    InfoValue {
+        index: 0,
+        data_type: data_type as u8,
+        sequence: None,
+        content,
+    }
+}
+
+#[test]
+fn test_aggregate_median_and_minimum() {
+    let contents = [
+        1614829090u64.to_be_bytes(),
+        1614829080u64.to_be_bytes(),
+        1614829100u64.to_be_bytes(),
+    ];
+    let latest_values: Vec<InfoValue> = contents
+        .iter()
+        .map(|content| build_latest(DataType::Timestamp, content))
+        .collect();
+    let reference = Reference::Since(SINCE_TIMESTAMP_BASE + 1614829200);
+
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            reference,
+            &build_policy(Aggregation::Median, 2)
+        ),
+        Ok(1614829090)
+    );
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            reference,
+            &build_policy(Aggregation::Minimum, 2)
+        ),
+        Ok(1614829080)
+    );
+}
+
+#[test]
+fn test_aggregate_lower_median_of_even_amount() {
+    let contents = [
+        10004u64.to_be_bytes(),
+        10001u64.to_be_bytes(),
+        10003u64.to_be_bytes(),
+        10002u64.to_be_bytes(),
+    ];
+    let latest_values: Vec<InfoValue> = contents
+        .iter()
+        .map(|content| build_latest(DataType::BlockNumber, content))
+        .collect();
+
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            Reference::HeaderNumber(10100),
+            &build_policy(Aggregation::Median, 4)
+        ),
+        Ok(10002)
+    );
+}
+
+#[test]
+fn test_aggregate_ignores_stuck_oracle() {
+    // The second oracle is stuck for a day, so the median comes from the fresh ones only
+    let contents = [
+        1614829080u64.to_be_bytes(),
+        1614742000u64.to_be_bytes(),
+        1614829100u64.to_be_bytes(),
+    ];
+    let latest_values: Vec<InfoValue> = contents
+        .iter()
+        .map(|content| build_latest(DataType::Timestamp, content))
+        .collect();
+    let reference = Reference::Since(SINCE_TIMESTAMP_BASE + 1614829200);
+
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            reference,
+            &build_policy(Aggregation::Minimum, 2)
+        ),
+        Ok(1614829080)
+    );
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            reference,
+            &build_policy(Aggregation::Median, 3)
+        ),
+        Err(AggregateError::QuorumNotReached(2))
+    );
+}
+
+#[test]
+fn test_aggregate_without_fresh_oracle() {
+    let content = 1614829080u64.to_be_bytes();
+    let latest_values = [build_latest(DataType::Timestamp, &content)];
+    let reference = Reference::Since(SINCE_TIMESTAMP_BASE + 1614829080 + 601);
+
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            reference,
+            &build_policy(Aggregation::Median, 0)
+        ),
+        Err(AggregateError::QuorumNotReached(0))
+    );
+    assert_eq!(
+        aggregate(&[], reference, &build_policy(Aggregation::Median, 0)),
+        Err(AggregateError::QuorumNotReached(0))
+    );
+}
+
+#[test]
+fn test_aggregate_inconsistent_data_type() {
+    let timestamp = 1614829080u64.to_be_bytes();
+    let block_number = 10003u64.to_be_bytes();
+    let latest_values = [
+        build_latest(DataType::Timestamp, &timestamp),
+        build_latest(DataType::BlockNumber, &block_number),
+    ];
+
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            Reference::HeaderNumber(10000),
+            &build_policy(Aggregation::Median, 1)
+        ),
+        Err(AggregateError::InconsistentDataType)
+    );
+}
+
+#[test]
+fn test_aggregate_metric_mismatch() {
+    let content = 1614829080u64.to_be_bytes();
+    let latest_values = [build_latest(DataType::Timestamp, &content)];
+
+    assert_eq!(
+        aggregate(
+            &latest_values,
+            Reference::HeaderNumber(10000),
+            &build_policy(Aggregation::Median, 1)
+        ),
+        Err(AggregateError::Freshness(FreshnessError::MetricMismatch))
+    );
+}
@@ -0,0 +1,638 @@
+use super::{build_mock_transaction, Loader};
+use cli::command::run;
+use cli::data::*;
+use cli::error::CliError;
+use cli::genesis::{build_genesis_tx, genesis_data, FundingCell, GenesisOptions};
+use cli::verify::{verify_mock_tx, MockTx};
+use common::{constants::*, index_state::IndexState, info::InfoLayout};
+use migration::{check::data_hash, CellRecipe, DepGroupRecipe, Migration};
+use serde_json::{json, to_string_pretty};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use updater::{tx::LiveCell, verify::LocalVerifier};
+
+use ckb_jsonrpc_types as json;
+use ckb_standalone_debugger::transaction::{
+    MockCellDep, MockInfo, MockInput, MockTransaction, ReprMockTransaction,
+};
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, DepType, ScriptHashType, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+    H256,
+};
+
+fn run_args(args: &[&str]) -> Result<String, CliError> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    run(&args)
+}
+
+#[test]
+fn test_format_utc() {
+    assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+    assert_eq!(format_utc(1614829080), "2021-03-04T03:38:00Z");
+    assert_eq!(format_utc(951782400), "2000-02-29T00:00:00Z");
+    assert_eq!(format_utc_millis(1614829080123), "2021-03-04T03:38:00.123Z");
+}
+
+#[test]
+fn test_decode_legacy_info() {
+    let output = run_args(&["decode-info", "0x03010000000060405618"]).unwrap();
+    assert_eq!(
+        output,
+        "index: 3\ntype: Timestamp(1)\nvalue: 1614829080 (2021-03-04T03:38:00Z)"
+    );
+}
+
+#[test]
+fn test_encode_and_decode_v3_info() {
+    let hex = run_args(&[
+        "encode-info",
+        "--type",
+        "median_timestamp",
+        "--index",
+        "300",
+        "--value",
+        "1614829080123",
+        "--version",
+        "3",
+        "--sequence",
+        "7",
+    ])
+    .unwrap();
+    assert_eq!(hex, "0x012c04000000000000000700000177fb504e3b");
+
+    let output = run_args(&["decode-info", &hex]).unwrap();
+    assert_eq!(
+        output,
+        "index: 300\ntype: MedianTimestamp(4)\nsequence: 7\nvalue: 1614829080123 (2021-03-04T03:38:00.123Z)"
+    );
+}
+
+#[test]
+fn test_encode_block_hash_info() {
+    let block_hash = format!("0x{}", "ab".repeat(32));
+    let hex = run_args(&[
+        "encode-info",
+        "--type",
+        "block_hash",
+        "--index",
+        "2",
+        "--value",
+        "100",
+        "--block-hash",
+        &block_hash,
+    ])
+    .unwrap();
+    let output = run_args(&["decode-info", &hex]).unwrap();
+    assert_eq!(
+        output,
+        format!(
+            "index: 2\ntype: BlockHash(5)\nvalue: block number 100, block hash {}",
+            block_hash
+        )
+    );
+
+    let result = run_args(&[
+        "encode-info",
+        "--type",
+        "block_hash",
+        "--index",
+        "2",
+        "--value",
+        "100",
+    ]);
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_decode_ambiguous_info() {
+    // The u16 index 8 of the version 1 layout reads as a legacy Payload info of the index 0
+    let data = encode_info(1, 8, DataType::Timestamp, None, &1u64.to_be_bytes()).unwrap();
+    assert!(InfoLayout::LEGACY.is_data_len_valid(&data));
+
+    assert!(matches!(
+        decode_info(&data, None),
+        Err(CliError::InvalidData(_))
+    ));
+    let output = decode_info(&data, Some(1)).unwrap();
+    assert_eq!(
+        output,
+        "index: 8\ntype: Timestamp(1)\nvalue: 1 (1970-01-01T00:00:01Z)"
+    );
+}
+
+#[test]
+fn test_encode_invalid_info() {
+    let result = encode_info(0, 300, DataType::Timestamp, None, &1u64.to_be_bytes());
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+
+    let result = encode_info(3, 3, DataType::Timestamp, None, &1u64.to_be_bytes());
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+
+    let result = encode_info(0, 3, DataType::Timestamp, None, &[0u8; 4]);
+    assert!(matches!(result, Err(CliError::InvalidData(_))));
+}
+
+#[test]
+fn test_decode_index_state() {
+    let output = run_args(&["decode-index", "0x0a14"]).unwrap();
+    assert_eq!(output, "version: 0\nindex: 10\nlength: 20");
+
+    let result = run_args(&["decode-index", "0x0a"]);
+    assert!(matches!(result, Err(CliError::InvalidData(_))));
+    let result = run_args(&["decode-index", "0x0g"]);
+    assert!(matches!(result, Err(CliError::InvalidHex(_))));
+}
+
+#[test]
+fn test_since() {
+    let output = run_args(&["since", "1614829080"]).unwrap();
+    assert_eq!(output, "4611686020042216984 (0x4000000060405618)");
+
+    let since = info_since(DataType::MedianTimestamp, 1614829080123).unwrap();
+    assert_eq!(since, SINCE_TIMESTAMP_BASE + 1614829080);
+    let since = info_since(DataType::BlockNumber, 1000).unwrap();
+    assert_eq!(since, 1000);
+
+    let result = run_args(&["since", "1", "--type", "hash"]);
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+    let result = info_since(DataType::BlockNumber, 1 << 56);
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_usage() {
+    assert!(matches!(run_args(&[]), Err(CliError::Usage(_))));
+    assert!(matches!(run_args(&["unknown"]), Err(CliError::Usage(_))));
+    let result = run_args(&["decode-info", "--version"]);
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+}
+
+fn build_script(code_hash: [u8; 32], args: &[u8]) -> Script {
+    Script::new_builder()
+        .code_hash(code_hash.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(Bytes::copy_from_slice(args).pack())
+        .build()
+}
+
+fn build_live_cell(tx_hash: u8, output: CellOutput, data: Bytes) -> LiveCell {
+    LiveCell {
+        out_point: OutPoint::new_builder()
+            .tx_hash([tx_hash; 32].pack())
+            .build(),
+        output,
+        data,
+    }
+}
+
+fn deploy_binary(tx_hash: u8, name: &str) -> (LiveCell, CellRecipe) {
+    // The type script works like the type id of the deployed cell
+    let type_script = build_script([9u8; 32], name.as_bytes());
+    let data = Loader::default().load_binary(name);
+    let output = CellOutput::new_builder()
+        .capacity(Capacity::bytes(data.len() + 200).unwrap().pack())
+        .type_(Some(type_script.clone()).pack())
+        .build();
+    let cell = build_live_cell(tx_hash, output, data);
+    let recipe = CellRecipe {
+        name: name.to_string(),
+        tx_hash: H256([tx_hash; 32]),
+        index: 0,
+        occupied_capacity: 0,
+        data_hash: H256([0u8; 32]),
+        type_id: Some(type_script.calc_script_hash().unpack()),
+    };
+    (cell, recipe)
+}
+
+// The migration of the deployed binaries and the cells which the genesis transaction resolves
+fn build_migration() -> (Migration, Vec<LiveCell>) {
+    let (index_state_cell, index_state_recipe) = deploy_binary(1, "index-state-type");
+    let (info_cell, info_recipe) = deploy_binary(2, "info-type");
+    let dep_group_data = OutPointVec::new_builder()
+        .push(index_state_cell.out_point.clone())
+        .push(info_cell.out_point.clone())
+        .build();
+    let dep_group_cell = build_live_cell(3, CellOutput::default(), dep_group_data.as_bytes());
+    let migration = Migration {
+        cell_recipes: vec![index_state_recipe, info_recipe],
+        dep_group_recipes: vec![DepGroupRecipe {
+            name: "oracle_dep_groups".to_string(),
+            tx_hash: H256([3u8; 32]),
+            index: 0,
+            occupied_capacity: 0,
+        }],
+    };
+    (migration, vec![index_state_cell, info_cell, dep_group_cell])
+}
+
+fn build_funding_cell(ckb: u64) -> LiveCell {
+    let output = CellOutput::new_builder()
+        .capacity(Capacity::bytes(ckb as usize).unwrap().pack())
+        .lock(build_script([4u8; 32], &[5u8; 20]))
+        .build();
+    build_live_cell(4, output, Bytes::new())
+}
+
+fn funding_of(cell: &LiveCell) -> FundingCell {
+    FundingCell {
+        out_point: cell.out_point.clone().into(),
+        output: cell.output.clone().into(),
+        cell_deps: vec![],
+    }
+}
+
+fn genesis_options(version: u8, length: u16) -> GenesisOptions {
+    GenesisOptions {
+        version,
+        length,
+        data_type: DataType::Timestamp,
+        content: 1614829080u64.to_be_bytes().to_vec(),
+        fee: 100_000,
+    }
+}
+
+fn verify_genesis(version: u8, length: u16) {
+    let (migration, mut cells) = build_migration();
+    let funding_cell = build_funding_cell(100_000);
+    let tx = build_genesis_tx(
+        &migration,
+        &funding_of(&funding_cell),
+        &genesis_options(version, length),
+    )
+    .unwrap();
+    assert_eq!(tx.outputs().len(), length as usize + 2);
+
+    // Both type scripts have the out point of the funding cell as the args, and since version 2
+    // the info type code hash
+    let index_state_type_script = tx.output(0).unwrap().type_().to_opt().unwrap();
+    let info_type_script = tx.output(1).unwrap().type_().to_opt().unwrap();
+    let args = index_state_type_script.args().raw_data();
+    assert_eq!(&args[..OUT_POINT_LEN], funding_cell.out_point.as_slice());
+    if version >= INDEX_STATE_VERSION_HISTORY_ROOT {
+        assert_eq!(args.len(), ORACLE_ARGS_LEN);
+        assert_eq!(
+            &args[OUT_POINT_LEN..],
+            migration.info_type_code_hash().unwrap().as_bytes()
+        );
+    } else {
+        assert_eq!(args.len(), OUT_POINT_LEN);
+    }
+    assert_eq!(info_type_script.args(), index_state_type_script.args());
+
+    // Every info cell has its occupied capacity only, the updater pays the fees by funding cells
+    let info_output = tx.output(1).unwrap();
+    let info_data_len = tx.outputs_data().get(1).unwrap().raw_data().len();
+    let occupied_capacity = info_output
+        .occupied_capacity(Capacity::bytes(info_data_len).unwrap())
+        .unwrap();
+    let info_capacity: Capacity = info_output.capacity().unpack();
+    assert_eq!(info_capacity, occupied_capacity);
+
+    let index_state =
+        IndexState::from_slice(&tx.outputs_data().get(0).unwrap().raw_data()).unwrap();
+    assert_eq!(index_state.version, version);
+    assert_eq!(index_state.index, length - 1);
+    assert_eq!(index_state.length, length);
+
+    cells.push(funding_cell);
+    let cycles = LocalVerifier::new(cells)
+        .verify(&tx, &index_state_type_script, &info_type_script)
+        .unwrap();
+    assert_eq!(cycles.len(), 2);
+}
+
+#[test]
+fn test_genesis_legacy_tx() {
+    verify_genesis(INDEX_STATE_VERSION_LEGACY, SUM_OF_INFO_CELLS as u16);
+}
+
+#[test]
+fn test_genesis_v4_tx() {
+    verify_genesis(INDEX_STATE_VERSION_INFO_ROOT, 300);
+}
+
+#[test]
+fn test_genesis_v5_tx() {
+    verify_genesis(INDEX_STATE_VERSION_UNIQUE, SUM_OF_INFO_CELLS as u16);
+}
+
+#[test]
+fn test_genesis_data() {
+    let (index_state_data, infos_data) =
+        genesis_data(&genesis_options(INDEX_STATE_VERSION_SEQUENCE, 3)).unwrap();
+    let index_state = IndexState::from_slice(&index_state_data).unwrap();
+    assert_eq!(index_state.sequence, Some(0));
+    assert_eq!(index_state.history_root, Some([0u8; HASH_LEN]));
+    assert_eq!(infos_data.len(), 3);
+    assert_eq!(InfoLayout::V3.index(&infos_data[2]), 2);
+    assert_eq!(InfoLayout::V3.sequence(&infos_data[2]), Some(0));
+
+    let result = genesis_data(&genesis_options(INDEX_STATE_VERSION_LEGACY, 20));
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+    let result = genesis_data(&genesis_options(INDEX_STATE_VERSION_U16, 0));
+    assert!(matches!(result, Err(CliError::InvalidArgument(_))));
+}
+
+// A version 5 ring is created unless the version is given
+#[test]
+fn test_genesis_command_default_version() {
+    let (migration, _) = build_migration();
+    let dir = env::temp_dir().join("ckb-time-cli-genesis-command");
+    fs::create_dir_all(&dir).unwrap();
+    let migration_path = dir.join("migration.json");
+    fs::write(&migration_path, to_string_pretty(&migration).unwrap()).unwrap();
+    let funding_path = dir.join("funding.json");
+    let funding = json!({
+        "out_point": json::OutPoint::from(build_funding_cell(100_000).out_point),
+        "output": json::CellOutput::from(build_funding_cell(100_000).output),
+    });
+    fs::write(&funding_path, to_string_pretty(&funding).unwrap()).unwrap();
+
+    let output = run_args(&[
+        "genesis",
+        "--migration",
+        migration_path.to_str().unwrap(),
+        "--funding",
+        funding_path.to_str().unwrap(),
+        "--type",
+        "timestamp",
+    ])
+    .unwrap();
+    let tx: json::Transaction = serde_json::from_str(&output).unwrap();
+    assert_eq!(tx.outputs.len(), SUM_OF_INFO_CELLS as usize + 2);
+    let index_state = IndexState::from_slice(tx.outputs_data[0].as_bytes()).unwrap();
+    assert_eq!(index_state.version, INDEX_STATE_VERSION_UNIQUE);
+    assert_eq!(index_state.index, SUM_OF_INFO_CELLS as u16 - 1);
+    assert_eq!(index_state.length, SUM_OF_INFO_CELLS as u16);
+}
+
+#[test]
+fn test_genesis_capacity_not_enough() {
+    let (migration, _) = build_migration();
+    let result = build_genesis_tx(
+        &migration,
+        &funding_of(&build_funding_cell(1000)),
+        &genesis_options(INDEX_STATE_VERSION_INFO_ROOT, 12),
+    );
+    assert!(matches!(result, Err(CliError::CapacityNotEnough(_))));
+}
+
+fn write_mock_tx(name: &str, mock_tx: MockTransaction) -> PathBuf {
+    let repr_tx: ReprMockTransaction = mock_tx.into();
+    let path = env::temp_dir().join(format!("ckb-time-cli-{}.json", name));
+    fs::write(&path, to_string_pretty(&repr_tx).unwrap()).expect("write mock tx");
+    path
+}
+
+fn binaries_dir() -> String {
+    Loader::default().path("").to_str().unwrap().to_string()
+}
+
+// A transaction which creates an index state cell, whose index-state-type is deployed as the
+// binary and referred to by the data hash like the other tests do
+fn build_index_state_tx(binary: Bytes, index_state_data: Bytes) -> (Context, TransactionView) {
+    let mut context = Context::default();
+    let index_state_out_point = context.deploy_cell(binary);
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+    let lock_script = context
+        .build_script(&always_success_out_point, Default::default())
+        .expect("script");
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let index_state_type_script = context
+        .build_script(
+            &index_state_out_point,
+            Bytes::copy_from_slice(input_out_point.as_slice()),
+        )
+        .expect("script");
+
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(input_out_point, 0))
+        .output(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(lock_script)
+                .type_(Some(index_state_type_script).pack())
+                .build(),
+        )
+        .output_data(index_state_data.pack())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(always_success_out_point)
+                .build(),
+        )
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(index_state_out_point)
+                .build(),
+        )
+        .witness(Bytes::new().pack())
+        .build();
+    (context, tx)
+}
+
+#[test]
+fn test_verify_mock_tx_with_built_binary() {
+    // The mock transaction was captured with another binary, which is replaced by the built one
+    let (context, tx) = build_index_state_tx(
+        Loader::default().load_binary("info-type"),
+        Bytes::from(vec![11u8, 12]),
+    );
+    let path = write_mock_tx("built-binary", build_mock_transaction(&tx, &context));
+
+    let output = run_args(&[
+        "verify",
+        path.to_str().unwrap(),
+        "--binaries",
+        &binaries_dir(),
+    ])
+    .unwrap();
+    assert!(output.starts_with("index-state-type: "));
+    assert!(output.ends_with(" cycles"));
+    assert_eq!(output.lines().count(), 1);
+}
+
+#[test]
+fn test_verify_mock_tx_error_name() {
+    let (context, tx) = build_index_state_tx(
+        Loader::default().load_binary("index-state-type"),
+        Bytes::from(vec![12u8, 12]),
+    );
+    let path = write_mock_tx("error-name", build_mock_transaction(&tx, &context));
+
+    let result = run_args(&[
+        "verify",
+        path.to_str().unwrap(),
+        "--binaries",
+        &binaries_dir(),
+    ]);
+    assert_eq!(
+        result,
+        Err(CliError::VerifyFailed(
+            "index-state-type failed with IndexStateOutOfBound(9)".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_verify_genesis_mock_tx() {
+    let (migration, cells) = build_migration();
+    let funding_cell = build_funding_cell(100_000);
+    let tx = build_genesis_tx(
+        &migration,
+        &funding_of(&funding_cell),
+        &genesis_options(INDEX_STATE_VERSION_INFO_ROOT, 12),
+    )
+    .unwrap();
+
+    // The dep group of the type ids and its cells
+    let dep_group_cell = cells[2].clone();
+    let mut cell_deps = vec![MockCellDep {
+        cell_dep: tx.cell_deps().get(0).unwrap(),
+        output: dep_group_cell.output,
+        data: dep_group_cell.data,
+        header: None,
+    }];
+    for cell in cells.into_iter().take(2) {
+        cell_deps.push(MockCellDep {
+            cell_dep: CellDep::new_builder()
+                .out_point(cell.out_point)
+                .dep_type(DepType::Code.into())
+                .build(),
+            output: cell.output,
+            data: cell.data,
+            header: None,
+        });
+    }
+    let mock_tx = MockTransaction {
+        mock_info: MockInfo {
+            inputs: vec![MockInput {
+                input: tx.inputs().get(0).unwrap(),
+                output: funding_cell.output,
+                data: funding_cell.data,
+                header: None,
+            }],
+            cell_deps,
+            header_deps: vec![],
+        },
+        tx: tx.data(),
+    };
+    let path = write_mock_tx("genesis", mock_tx);
+
+    let mock_tx = MockTx::from_file(&path).unwrap();
+    let results = verify_mock_tx(mock_tx, binaries_dir()).unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results[0]
+        .as_ref()
+        .unwrap()
+        .starts_with("index-state-type: "));
+    assert!(results[1].as_ref().unwrap().starts_with("info-type: "));
+}
+
+// The binaries and the migration of their data hashes, in which info-type is recorded as the data
+fn write_check_files(name: &str, info_type_data: &[u8]) -> (PathBuf, PathBuf) {
+    let dir = env::temp_dir().join(format!("ckb-time-cli-{}", name));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("index-state-type"), b"index-state-type").unwrap();
+    fs::write(dir.join("info-type"), b"info-type").unwrap();
+
+    let (mut migration, _) = build_migration();
+    migration.cell_recipes[0].data_hash = data_hash(b"index-state-type");
+    migration.cell_recipes[1].data_hash = data_hash(info_type_data);
+    let migration_path = dir.join("migration.json");
+    fs::write(&migration_path, to_string_pretty(&migration).unwrap()).unwrap();
+    (dir, migration_path)
+}
+
+#[test]
+fn test_check_binaries() {
+    let (dir, migration_path) = write_check_files("check-matched", b"info-type");
+    let output = run_args(&[
+        "check-binaries",
+        "--binaries",
+        dir.to_str().unwrap(),
+        "--migration",
+        migration_path.to_str().unwrap(),
+    ])
+    .unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], migration_path.to_str().unwrap());
+    assert!(lines[1].starts_with("    index-state-type: 0x"));
+    assert!(lines[2].ends_with(" matched"));
+
+    let (dir, migration_path) = write_check_files("check-mismatched", b"old info-type");
+    let result = run_args(&[
+        "check-binaries",
+        "--binaries",
+        dir.to_str().unwrap(),
+        "--migration",
+        migration_path.to_str().unwrap(),
+    ]);
+    let report = match result {
+        Err(CliError::BinaryMismatch(report)) => report,
+        _ => panic!("the binary should mismatch"),
+    };
+    assert!(report.lines().nth(1).unwrap().ends_with(" matched"));
+    assert_eq!(
+        report.lines().nth(2).unwrap(),
+        format!(
+            "    info-type: {:#x} mismatched, the migration has {:#x}",
+            data_hash(b"info-type"),
+            data_hash(b"old info-type")
+        )
+    );
+}
+
+fn migration_path(network: &str, name: &str) -> String {
+    format!("../migrations/{}/{}.json", network, name)
+}
+
+#[test]
+fn test_diff_migrations() {
+    let output = run_args(&[
+        "diff-migrations",
+        &migration_path("testnet", "2021-07-19-065245"),
+        &migration_path("testnet", "2021-07-19-065400"),
+    ])
+    .unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("index-state-type: binary changed 0xf1c76a"));
+    assert!(lines[0].ends_with(
+        ", type id stable 0x554cff969f3148e3c620749384004e9692e67c429f621554d139b505a281c7b8"
+    ));
+    assert!(lines[1].starts_with("info-type: binary unchanged 0xe993e8"));
+    assert!(lines[2].starts_with("oracle_dep_groups: rebuilt 0x5f9b8a"));
+
+    let result = run_args(&[
+        "diff-migrations",
+        &migration_path("mainnet", "2021-07-19-135012"),
+        &migration_path("mainnet", "2021-08-24-000000"),
+    ]);
+    let report = match result {
+        Err(CliError::UpgradeBroken(report)) => report,
+        _ => panic!("the type ids should change"),
+    };
+    assert!(report
+        .lines()
+        .next()
+        .unwrap()
+        .contains(", type id changed 0xf47e32"));
+    assert!(report.contains("oracle_dep_groups: not rebuilt 0x73d85d"));
+    assert_eq!(
+        report.lines().last(),
+        Some("a binary is moved, but the dep group is not rebuilt")
+    );
+}
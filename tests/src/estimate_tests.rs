@@ -0,0 +1,168 @@
+use common::{constants::*, estimate::*, history::history};
+
+fn build_info_cell_data(index: u8, type_: DataType, data: u64) -> Vec<u8> {
+    let mut info_data = vec![index, type_ as u8];
+    info_data.extend_from_slice(&data.to_be_bytes());
+    info_data
+}
+
+// The rates are 8, 12 and 9 seconds per block
+fn build_points() -> Vec<TimePoint> {
+    [(100, 1000), (110, 1080), (120, 1200), (130, 1290)]
+        .iter()
+        .map(|(block_number, timestamp)| TimePoint {
+            block_number: *block_number,
+            timestamp: *timestamp,
+        })
+        .collect()
+}
+
+#[test]
+fn test_estimate_timestamp_between_points() {
+    let estimate = estimate_timestamp(&build_points(), 115).expect("estimate");
+    assert_eq!(
+        estimate,
+        Estimate {
+            value: 1140,
+            min: 1080,
+            max: 1200
+        }
+    );
+}
+
+#[test]
+fn test_estimate_timestamp_of_recorded_point() {
+    let estimate = estimate_timestamp(&build_points(), 100).expect("estimate");
+    assert_eq!(estimate.value, 1000);
+}
+
+#[test]
+fn test_estimate_timestamp_after_points() {
+    let estimate = estimate_timestamp(&build_points(), 140).expect("estimate");
+    assert_eq!(
+        estimate,
+        Estimate {
+            value: 1386,
+            min: 1370,
+            max: 1410
+        }
+    );
+}
+
+#[test]
+fn test_estimate_timestamp_before_points() {
+    let estimate = estimate_timestamp(&build_points(), 90).expect("estimate");
+    assert_eq!(
+        estimate,
+        Estimate {
+            value: 904,
+            min: 880,
+            max: 920
+        }
+    );
+}
+
+#[test]
+fn test_estimate_block_number() {
+    let points = build_points();
+    let estimate = estimate_block_number(&points, 1140).expect("estimate");
+    assert_eq!(
+        estimate,
+        Estimate {
+            value: 115,
+            min: 110,
+            max: 120
+        }
+    );
+
+    let estimate = estimate_block_number(&points, 1390).expect("estimate");
+    assert_eq!(estimate.value, 140);
+    assert!(estimate.min <= estimate.value && estimate.value <= estimate.max);
+}
+
+#[test]
+fn test_estimate_from_paired_rings() {
+    let index_state_data = [1, SUM_OF_INFO_CELLS];
+    let timestamps = [
+        build_info_cell_data(0, DataType::Timestamp, 1000),
+        build_info_cell_data(1, DataType::Timestamp, 1080),
+    ];
+    let block_numbers = [
+        build_info_cell_data(1, DataType::BlockNumber, 110),
+        build_info_cell_data(0, DataType::BlockNumber, 100),
+    ];
+    let timestamps: Vec<&[u8]> = timestamps.iter().map(|data| data.as_slice()).collect();
+    let block_numbers: Vec<&[u8]> = block_numbers.iter().map(|data| data.as_slice()).collect();
+    let points = pair_history(
+        &history(&index_state_data, &timestamps).expect("history"),
+        &history(&index_state_data, &block_numbers).expect("history"),
+    )
+    .expect("pair");
+
+    assert_eq!(
+        estimate_timestamp(&points, 105).expect("estimate").value,
+        1040
+    );
+}
+
+#[test]
+fn test_error_estimate_not_enough_points() {
+    let points = &build_points()[..1];
+    assert_eq!(
+        estimate_timestamp(points, 100).unwrap_err(),
+        EstimateError::NotEnoughPoints
+    );
+}
+
+#[test]
+fn test_error_estimate_not_monotonic() {
+    let mut points = build_points();
+    points[3].timestamp = 1100;
+    assert_eq!(
+        estimate_timestamp(&points, 115).unwrap_err(),
+        EstimateError::NotMonotonic
+    );
+}
+
+#[test]
+fn test_error_pair_unpaired_history() {
+    let index_state_data = [1, SUM_OF_INFO_CELLS];
+    let timestamps = build_info_cell_data(1, DataType::Timestamp, 1080);
+    let block_numbers = build_info_cell_data(2, DataType::BlockNumber, 110);
+    let block_numbers_state_data = [2, SUM_OF_INFO_CELLS];
+    let err = pair_history(
+        &history(&index_state_data, &[&timestamps]).expect("history"),
+        &history(&block_numbers_state_data, &[&block_numbers]).expect("history"),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, EstimateError::UnpairedHistory);
+}
+
+#[test]
+fn test_error_pair_swapped_rings() {
+    let index_state_data = [1, SUM_OF_INFO_CELLS];
+    let timestamps = build_info_cell_data(1, DataType::Timestamp, 1080);
+    let block_numbers = build_info_cell_data(1, DataType::BlockNumber, 110);
+    let err = pair_history(
+        &history(&index_state_data, &[&block_numbers]).expect("history"),
+        &history(&index_state_data, &[&timestamps]).expect("history"),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, EstimateError::DataTypeMismatch);
+}
+
+#[test]
+fn test_error_pair_arbitrage_ring() {
+    let index_state_data = [1, SUM_OF_INFO_CELLS];
+    let timestamps = build_info_cell_data(1, DataType::Arbitrage, 1080);
+    let block_numbers = build_info_cell_data(1, DataType::BlockNumber, 110);
+    let err = pair_history(
+        &history(&index_state_data, &[&timestamps]).expect("history"),
+        &history(&index_state_data, &[&block_numbers]).expect("history"),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, EstimateError::DataTypeMismatch);
+}
@@ -0,0 +1,382 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::{BufMut, Bytes, BytesMut},
+    core::{TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use common::constants::*;
+
+const MAX_CYCLES: u64 = 10_000_000;
+
+const EXPIRED_AT: u64 = 1614829080;
+const GRACE_PERIOD: u64 = 30 * 86400;
+const MAX_AGE: u64 = 3600;
+
+// error numbers
+const EXPIRY_DATA_LEN_ERROR: i8 = 6;
+const EXPIRED_AT_DECREASED: i8 = 11;
+const ONLY_RENEWAL_IN_GRACE_PERIOD: i8 = 12;
+const ACCOUNT_EXPIRED: i8 = 13;
+const MULTIPLE_EXPIRY_CELLS: i8 = 14;
+const INVALID_SINCE: i8 = 15;
+const ORACLE_NOT_FRESH: i8 = 16;
+
+fn build_expiry_cell_data(expired_at: u64, account: &[u8]) -> Bytes {
+    let mut data_buf = BytesMut::with_capacity(8 + account.len());
+    data_buf.put_u64(expired_at);
+    data_buf.put_slice(account);
+    Bytes::from(data_buf.to_vec())
+}
+
+fn build_expiry_args(index_state_type_hash: &Byte32, info_type_code_hash: &Byte32) -> Bytes {
+    let mut args_buf = BytesMut::with_capacity(32 * 2 + 8 * 2);
+    args_buf.put_slice(index_state_type_hash.as_slice());
+    args_buf.put_slice(info_type_code_hash.as_slice());
+    args_buf.put_u64(GRACE_PERIOD);
+    args_buf.put_u64(MAX_AGE);
+    Bytes::from(args_buf.to_vec())
+}
+
+fn create_test_context(
+    latest_timestamp: u64,
+    input_data: Option<Bytes>,
+    output_data: Option<Bytes>,
+) -> (Context, TransactionView) {
+    create_test_context_with_accounts(
+        latest_timestamp,
+        input_data.into_iter().collect(),
+        output_data.into_iter().collect(),
+    )
+}
+
+fn create_test_context_with_accounts(
+    latest_timestamp: u64,
+    input_accounts: Vec<Bytes>,
+    output_accounts: Vec<Bytes>,
+) -> (Context, TransactionView) {
+    // deploy contract
+    let mut context = Context::default();
+    let expiry_bin: Bytes = Loader::default().load_binary("expiry-type");
+    let expiry_out_point = context.deploy_cell(expiry_bin);
+
+    // deploy always_success script
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    // prepare scripts
+    let lock_script = context
+        .build_script(&always_success_out_point, Default::default())
+        .expect("script");
+    let lock_script_dep = CellDep::new_builder()
+        .out_point(always_success_out_point)
+        .build();
+
+    // prepare oracle cells which are cell deps of the transaction
+    let latest_info_data = build_legacy_info_cell_data(3, DataType::Timestamp, latest_timestamp);
+    let oracle = create_oracle_cell_deps(
+        &mut context,
+        &lock_script,
+        build_legacy_index_state_cell_data(3),
+        &[latest_info_data],
+        None,
+    );
+
+    let expiry_type_script = context
+        .build_script(
+            &expiry_out_point,
+            build_expiry_args(
+                &oracle.index_state_type_script.calc_script_hash(),
+                &oracle.info_type_script.code_hash(),
+            ),
+        )
+        .expect("script");
+    let expiry_type_script_dep = CellDep::new_builder().out_point(expiry_out_point).build();
+
+    // prepare cells
+    let normal_input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let mut inputs = vec![CellInput::new_builder()
+        .previous_output(normal_input_out_point)
+        .build()];
+    for input_data in input_accounts {
+        let expiry_input_out_point = context.create_cell(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(lock_script.clone())
+                .type_(Some(expiry_type_script.clone()).pack())
+                .build(),
+            input_data,
+        );
+        // The oracle is fresh at the since of the expiry input
+        inputs.push(
+            CellInput::new_builder()
+                .previous_output(expiry_input_out_point)
+                .since((SINCE_TIMESTAMP_BASE + latest_timestamp).pack())
+                .build(),
+        );
+    }
+
+    let mut outputs = vec![CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script.clone())
+        .build()];
+    let mut outputs_data = vec![Bytes::new()];
+    for output_data in output_accounts {
+        outputs.push(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(lock_script.clone())
+                .type_(Some(expiry_type_script.clone()).pack())
+                .build(),
+        );
+        outputs_data.push(output_data);
+    }
+
+    let witnesses: Vec<Bytes> = inputs.iter().map(|_| Bytes::new()).collect();
+
+    // build transaction
+    let tx = TransactionBuilder::default()
+        .inputs(inputs)
+        .outputs(outputs)
+        .outputs_data(outputs_data.pack())
+        .cell_dep(lock_script_dep)
+        .cell_dep(expiry_type_script_dep)
+        .cell_deps(oracle.cell_deps)
+        .witnesses(witnesses.pack())
+        .build();
+    (context, tx)
+}
+
+// The expiry input is the second input
+fn set_expiry_input_since(tx: TransactionView, since: u64) -> TransactionView {
+    let inputs: Vec<CellInput> = tx
+        .inputs()
+        .into_iter()
+        .enumerate()
+        .map(|(index, input)| match index {
+            1 => input.as_builder().since(since.pack()).build(),
+            _ => input,
+        })
+        .collect();
+    tx.as_advanced_builder().set_inputs(inputs).build()
+}
+
+fn assert_expiry_error(err: ckb_tool::ckb_error::Error, error_code: i8) {
+    let script_cell_index = 1;
+    assert_type_script_error(err, error_code, script_cell_index);
+}
+
+#[test]
+fn test_create_expiry_cell_success() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT - 1,
+        None,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_update_expiry_cell_before_expiry_success() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT - 1,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit records")),
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_renew_expiry_cell_in_grace_period_success() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT + GRACE_PERIOD - 1,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        Some(build_expiry_cell_data(
+            EXPIRED_AT + 365 * 86400,
+            b"alice.bit",
+        )),
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_recycle_expired_cell_success() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT + GRACE_PERIOD,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        None,
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_create_expiry_cell_data_len() {
+    let (mut context, tx) =
+        create_test_context(EXPIRED_AT - 1, None, Some(Bytes::from(vec![0; 7])));
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(EXPIRY_DATA_LEN_ERROR).output_type_script(1)
+    );
+}
+
+#[test]
+fn test_error_expired_at_decreased() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT - 1,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        Some(build_expiry_cell_data(EXPIRED_AT - 1, b"alice.bit")),
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_expiry_error(err, EXPIRED_AT_DECREASED);
+}
+
+#[test]
+fn test_error_update_expiry_cell_in_grace_period() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        Some(build_expiry_cell_data(
+            EXPIRED_AT + 365 * 86400,
+            b"alice.bit records",
+        )),
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_expiry_error(err, ONLY_RENEWAL_IN_GRACE_PERIOD);
+}
+
+#[test]
+fn test_error_renew_expiry_cell_after_grace_period() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT + GRACE_PERIOD,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        Some(build_expiry_cell_data(
+            EXPIRED_AT + 365 * 86400,
+            b"alice.bit",
+        )),
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_expiry_error(err, ACCOUNT_EXPIRED);
+}
+
+#[test]
+fn test_create_expiry_cells_success() {
+    let (mut context, tx) = create_test_context_with_accounts(
+        EXPIRED_AT - 1,
+        vec![],
+        vec![
+            build_expiry_cell_data(EXPIRED_AT, b"alice.bit"),
+            build_expiry_cell_data(EXPIRED_AT, b"bob.bit"),
+        ],
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// The second account has expired, and it must not be updated along with the first one
+#[test]
+fn test_error_update_two_expiry_cells() {
+    let (mut context, tx) = create_test_context_with_accounts(
+        EXPIRED_AT + GRACE_PERIOD,
+        vec![
+            build_expiry_cell_data(EXPIRED_AT + 365 * 86400, b"alice.bit"),
+            build_expiry_cell_data(EXPIRED_AT, b"bob.bit"),
+        ],
+        vec![
+            build_expiry_cell_data(EXPIRED_AT + 365 * 86400, b"alice.bit"),
+            build_expiry_cell_data(EXPIRED_AT + 365 * 86400, b"bob.bit records"),
+        ],
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_expiry_error(err, MULTIPLE_EXPIRY_CELLS);
+}
+
+// The account has expired, but the oracle stopped before its expiry
+#[test]
+fn test_error_update_expiry_cell_with_stale_oracle() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT - 1,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit records")),
+    );
+    let tx = set_expiry_input_since(tx, SINCE_TIMESTAMP_BASE + EXPIRED_AT + MAX_AGE);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_expiry_error(err, ORACLE_NOT_FRESH);
+}
+
+#[test]
+fn test_error_update_expiry_cell_without_since() {
+    let (mut context, tx) = create_test_context(
+        EXPIRED_AT - 1,
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit")),
+        Some(build_expiry_cell_data(EXPIRED_AT, b"alice.bit records")),
+    );
+    let tx = set_expiry_input_since(tx, 0);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_expiry_error(err, INVALID_SINCE);
+}
@@ -0,0 +1,105 @@
+use common::{constants::*, freshness::*, history::InfoValue};
+
+const POLICY: FreshnessPolicy = FreshnessPolicy {
+    max_age: 600,
+    halted_age: 86400,
+};
+
+fn build_latest(data_type: DataType, content: &[u8]) -> InfoValue<'_> {
+    InfoValue {
+        index: 3,
+        data_type: data_type as u8,
+        sequence: None,
+        content,
+    }
+}
+
+#[test]
+fn test_fresh_timestamp_by_since() {
+    let content = 1614829080u64.to_be_bytes();
+    let latest = build_latest(DataType::Timestamp, &content);
+    let reference = Reference::Since(SINCE_TIMESTAMP_BASE + 1614829080 + 600);
+
+    assert_eq!(freshness(&latest, reference, &POLICY), Ok(Freshness::Fresh));
+    assert_eq!(fresh_value(&latest, reference, &POLICY), Ok(1614829080));
+}
+
+#[test]
+fn test_stale_timestamp_by_header_dep() {
+    let content = 1614829080u64.to_be_bytes();
+    let latest = build_latest(DataType::Timestamp, &content);
+    let reference = Reference::HeaderTimestamp((1614829080 + 601) * 1000);
+
+    assert_eq!(
+        freshness(&latest, reference, &POLICY),
+        Ok(Freshness::Stale(601))
+    );
+    assert_eq!(
+        fresh_value(&latest, reference, &POLICY),
+        Err(FreshnessError::NotFresh(Freshness::Stale(601)))
+    );
+}
+
+#[test]
+fn test_halted_timestamp_millis() {
+    let content = 1614829080000u64.to_be_bytes();
+    let latest = build_latest(DataType::TimestampMillis, &content);
+    let reference = Reference::Since(SINCE_TIMESTAMP_BASE + 1614829080 + 87);
+
+    assert_eq!(
+        freshness(&latest, reference, &POLICY),
+        Ok(Freshness::Halted)
+    );
+}
+
+#[test]
+fn test_fresh_block_number_ahead_of_reference() {
+    let content = 10003u64.to_be_bytes();
+    let latest = build_latest(DataType::BlockNumber, &content);
+
+    assert_eq!(
+        freshness(&latest, Reference::HeaderNumber(10000), &POLICY),
+        Ok(Freshness::Fresh)
+    );
+    assert_eq!(
+        freshness(&latest, Reference::Since(10003 + 601), &POLICY),
+        Ok(Freshness::Stale(601))
+    );
+}
+
+#[test]
+fn test_error_freshness_metric_mismatch() {
+    let content = 1614829080u64.to_be_bytes();
+    let latest = build_latest(DataType::Timestamp, &content);
+
+    assert_eq!(
+        freshness(&latest, Reference::Since(10000), &POLICY),
+        Err(FreshnessError::MetricMismatch)
+    );
+    assert_eq!(
+        freshness(&latest, Reference::HeaderNumber(10000), &POLICY),
+        Err(FreshnessError::MetricMismatch)
+    );
+}
+
+#[test]
+fn test_error_freshness_relative_since() {
+    let content = 1614829080u64.to_be_bytes();
+    let latest = build_latest(DataType::Timestamp, &content);
+    let reference = Reference::Since(SINCE_RELATIVE_FLAG | SINCE_TIMESTAMP_BASE | 600);
+
+    assert_eq!(
+        freshness(&latest, reference, &POLICY),
+        Err(FreshnessError::InvalidSince)
+    );
+}
+
+#[test]
+fn test_error_freshness_payload() {
+    let latest = build_latest(DataType::Payload, &[1, 2, 3]);
+
+    assert_eq!(
+        freshness(&latest, Reference::HeaderNumber(10000), &POLICY),
+        Err(FreshnessError::MetricMismatch)
+    );
+}
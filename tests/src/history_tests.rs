@@ -0,0 +1,223 @@
+use common::{constants::*, hash::blake2b_256, history::*, index_state::IndexStateError};
+
+fn build_index_state_cell_data(index: u8) -> Vec<u8> {
+    vec![index, SUM_OF_INFO_CELLS]
+}
+
+fn build_v1_index_state_cell_data(index: u16, sum: u16) -> Vec<u8> {
+    let mut data = vec![INDEX_STATE_VERSION_U16];
+    data.extend_from_slice(&index.to_be_bytes());
+    data.extend_from_slice(&sum.to_be_bytes());
+    data
+}
+
+fn build_v2_index_state_cell_data(index: u16, sum: u16, history_root: &[u8; HASH_LEN]) -> Vec<u8> {
+    let mut data = build_v1_index_state_cell_data(index, sum);
+    data[0] = INDEX_STATE_VERSION_HISTORY_ROOT;
+    data.extend_from_slice(history_root);
+    data
+}
+
+fn build_v3_index_state_cell_data(index: u16, sum: u16, sequence: u64) -> Vec<u8> {
+    let mut data = build_v2_index_state_cell_data(index, sum, &[0u8; HASH_LEN]);
+    data[0] = INDEX_STATE_VERSION_SEQUENCE;
+    data.extend_from_slice(&sequence.to_be_bytes());
+    data
+}
+
+fn build_info_cell_data(index: u8, type_: DataType, data: u64) -> Vec<u8> {
+    let mut info_data = vec![index, type_ as u8];
+    info_data.extend_from_slice(&data.to_be_bytes());
+    info_data
+}
+
+fn build_v1_info_cell_data(index: u16, type_: DataType, data: u64) -> Vec<u8> {
+    let mut info_data = index.to_be_bytes().to_vec();
+    info_data.push(type_ as u8);
+    info_data.extend_from_slice(&data.to_be_bytes());
+    info_data
+}
+
+fn build_v3_info_cell_data(index: u16, type_: DataType, sequence: u64, data: u64) -> Vec<u8> {
+    let mut info_data = index.to_be_bytes().to_vec();
+    info_data.push(type_ as u8);
+    info_data.extend_from_slice(&sequence.to_be_bytes());
+    info_data.extend_from_slice(&data.to_be_bytes());
+    info_data
+}
+
+// The ring whose info cell of index i has the timestamp 1614828000 + i * 60
+fn build_ring() -> Vec<Vec<u8>> {
+    (0..SUM_OF_INFO_CELLS)
+        .map(|index| {
+            build_info_cell_data(index, DataType::Timestamp, 1614828000 + index as u64 * 60)
+        })
+        .collect()
+}
+
+fn values_of(window: &[InfoValue]) -> Vec<u64> {
+    window.iter().map(|value| value.as_u64().unwrap()).collect()
+}
+
+#[test]
+fn test_history_of_whole_ring() {
+    let ring = build_ring();
+    let infos_data: Vec<&[u8]> = ring.iter().rev().map(|data| data.as_slice()).collect();
+    let window = history(&build_index_state_cell_data(3), &infos_data).expect("history");
+
+    let indexes: Vec<u16> = window.iter().map(|value| value.index).collect();
+    assert_eq!(indexes, vec![3, 2, 1, 0, 11, 10, 9, 8, 7, 6, 5, 4]);
+    assert_eq!(values_of(&window)[0], 1614828000 + 3 * 60);
+    assert!(window
+        .iter()
+        .all(|value| value.data_type == DataType::Timestamp as u8));
+}
+
+#[test]
+fn test_history_of_latest_window() {
+    let ring = build_ring();
+    let infos_data: Vec<&[u8]> = vec![&ring[11], &ring[0], &ring[1]];
+    let window = history(&build_index_state_cell_data(1), &infos_data).expect("history");
+
+    assert_eq!(
+        values_of(&window),
+        vec![1614828000 + 60, 1614828000, 1614828000 + 11 * 60]
+    );
+}
+
+#[test]
+fn test_history_of_v1_ring() {
+    let latest = build_v1_info_cell_data(300, DataType::BlockNumber, 10003);
+    let previous = build_v1_info_cell_data(299, DataType::BlockNumber, 10000);
+    let infos_data: Vec<&[u8]> = vec![&previous, &latest];
+    let window = history(&build_v1_index_state_cell_data(300, 1000), &infos_data).expect("history");
+
+    assert_eq!(values_of(&window), vec![10003, 10000]);
+}
+
+#[test]
+fn test_error_history_gap() {
+    let ring = build_ring();
+    let infos_data: Vec<&[u8]> = vec![&ring[5], &ring[3]];
+    let err = history(&build_index_state_cell_data(5), &infos_data).unwrap_err();
+
+    assert_eq!(err, HistoryError::InfoIndexMissing(4));
+}
+
+#[test]
+fn test_error_history_duplicate_index() {
+    let ring = build_ring();
+    let infos_data: Vec<&[u8]> = vec![&ring[5], &ring[5]];
+    let err = history(&build_index_state_cell_data(5), &infos_data).unwrap_err();
+
+    assert_eq!(err, HistoryError::InfoIndexMissing(4));
+}
+
+#[test]
+fn test_error_history_inconsistent_data_type() {
+    let latest = build_info_cell_data(5, DataType::BlockNumber, 10000);
+    let previous = build_info_cell_data(4, DataType::Timestamp, 1614828000);
+    let infos_data: Vec<&[u8]> = vec![&latest, &previous];
+    let err = history(&build_index_state_cell_data(5), &infos_data).unwrap_err();
+
+    assert_eq!(err, HistoryError::InconsistentDataType);
+}
+
+#[test]
+fn test_error_history_info_data_len() {
+    let ring = build_ring();
+    let short_info_data = vec![4, DataType::Timestamp as u8];
+    let infos_data: Vec<&[u8]> = vec![&ring[5], &short_info_data];
+    let err = history(&build_index_state_cell_data(5), &infos_data).unwrap_err();
+
+    assert_eq!(err, HistoryError::InfoDataLenError);
+}
+
+#[test]
+fn test_error_history_info_amount() {
+    let ring = build_ring();
+    let mut infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    infos_data.push(&ring[0]);
+    let err = history(&build_index_state_cell_data(5), &infos_data).unwrap_err();
+
+    assert_eq!(err, HistoryError::InfoAmountError);
+}
+
+#[test]
+fn test_error_history_index_state_data_len() {
+    let ring = build_ring();
+    let infos_data: Vec<&[u8]> = vec![&ring[5]];
+    let err = history(&[INDEX_STATE_VERSION_U16, 0, 5], &infos_data).unwrap_err();
+
+    assert_eq!(err, HistoryError::IndexState(IndexStateError::DataLenError));
+}
+
+#[test]
+fn test_history_of_v2_ring() {
+    let latest = build_v1_info_cell_data(0, DataType::Timestamp, 1614829080);
+    let previous = build_v1_info_cell_data(999, DataType::Timestamp, 1614828683);
+    let infos_data: Vec<&[u8]> = vec![&latest, &previous];
+    let index_state_data = build_v2_index_state_cell_data(0, 1000, &[1u8; HASH_LEN]);
+    let window = history(&index_state_data, &infos_data).expect("history");
+
+    assert_eq!(values_of(&window), vec![1614829080, 1614828683]);
+}
+
+#[test]
+fn test_extend_history_root() {
+    let history_root = [1u8; HASH_LEN];
+    let info_data = build_v1_info_cell_data(0, DataType::Timestamp, 1614829080);
+    let mut preimage = history_root.to_vec();
+    preimage.extend_from_slice(&info_data);
+
+    assert_eq!(
+        extend_history_root(&history_root, &info_data),
+        blake2b_256(&preimage)
+    );
+}
+
+#[test]
+fn test_verify_history_chain() {
+    let infos_data: Vec<Vec<u8>> = (0..3)
+        .map(|index| {
+            build_v1_info_cell_data(index, DataType::Timestamp, 1614828000 + index as u64 * 60)
+        })
+        .collect();
+    let from_root = [0u8; HASH_LEN];
+    let to_root = infos_data.iter().fold(from_root, |root, info_data| {
+        extend_history_root(&root, info_data)
+    });
+
+    let chain: Vec<&[u8]> = infos_data.iter().map(|data| data.as_slice()).collect();
+    assert!(verify_history_chain(&from_root, &chain, &to_root));
+
+    let reordered: Vec<&[u8]> = vec![&infos_data[1], &infos_data[0], &infos_data[2]];
+    assert!(!verify_history_chain(&from_root, &reordered, &to_root));
+
+    let missing: Vec<&[u8]> = vec![&infos_data[0], &infos_data[2]];
+    assert!(!verify_history_chain(&from_root, &missing, &to_root));
+}
+
+#[test]
+fn test_history_of_v3_ring() {
+    let latest = build_v3_info_cell_data(0, DataType::BlockNumber, 1000, 10003);
+    let previous = build_v3_info_cell_data(999, DataType::BlockNumber, 999, 10000);
+    let infos_data: Vec<&[u8]> = vec![&previous, &latest];
+    let window =
+        history(&build_v3_index_state_cell_data(0, 1000, 1000), &infos_data).expect("history");
+
+    assert_eq!(values_of(&window), vec![10003, 10000]);
+    assert_eq!(window[0].sequence, Some(1000));
+    assert_eq!(window[1].sequence, Some(999));
+}
+
+#[test]
+fn test_error_history_info_sequence_mismatch() {
+    // The info cell of index 999 is left behind by a skipped update
+    let latest = build_v3_info_cell_data(0, DataType::BlockNumber, 1000, 10003);
+    let previous = build_v3_info_cell_data(999, DataType::BlockNumber, 0, 0);
+    let infos_data: Vec<&[u8]> = vec![&latest, &previous];
+    let err = history(&build_v3_index_state_cell_data(0, 1000, 1000), &infos_data).unwrap_err();
+
+    assert_eq!(err, HistoryError::InfoSequenceMismatch(999));
+}
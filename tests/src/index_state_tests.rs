@@ -5,21 +5,37 @@ use ckb_tool::ckb_script::ScriptError;
 use ckb_tool::ckb_types::{
     bytes::{BufMut, Bytes, BytesMut},
     core::{TransactionBuilder, TransactionView},
-    packed::*,
+    packed::{self, *},
     prelude::*,
 };
 use ckb_x64_simulator::RunningSetup;
-use common::constants::*;
+use common::{
+    constants::*,
+    history::extend_history_root,
+    index_state::IndexState,
+    info::InfoLayout,
+    merkle::{info_proof, info_root},
+};
 use std::collections::HashMap;
 
 const MAX_CYCLES: u64 = 10_000_000;
 
 // error numbers
+const INVALID_ARGUMENT: i8 = 5;
 const INDEX_STATE_TYPE_NOT_EXIST: i8 = 6;
 const INDEX_STATE_DATA_LEN_ERROR: i8 = 7;
 const TIME_INFO_AMOUNT_ERROR: i8 = 8;
 const TIME_INDEX_OUT_OF_BOUND: i8 = 9;
 const TIME_INDEX_INCREASE_ERROR: i8 = 10;
+const INDEX_STATE_VERSION_ERROR: i8 = 11;
+const INDEX_STATE_SEQUENCE_ERROR: i8 = 12;
+const INDEX_STATE_NOT_UNIQUE: i8 = 13;
+const INFO_NOT_UPDATED: i8 = 14;
+const INVALID_INFO_ROOT: i8 = 17;
+
+// The errors of info-type, which checks the roots of an update too
+const INFO_INVALID_ARGUMENT: i8 = 5;
+const INFO_INVALID_INFO_ROOT: i8 = 22;
 
 fn build_index_state_cell_data(index: u8, sum: u8) -> Bytes {
     let mut time_buf = BytesMut::with_capacity(INDEX_STATE_CELL_DATA_LEN);
@@ -28,6 +44,63 @@ fn build_index_state_cell_data(index: u8, sum: u8) -> Bytes {
     Bytes::from(time_buf.to_vec())
 }
 
+fn build_v1_index_state_cell_data(index: u16, sum: u16) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_V1_CELL_DATA_LEN);
+    time_buf.put_u8(INDEX_STATE_VERSION_U16);
+    time_buf.put_u16(index);
+    time_buf.put_u16(sum);
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_v2_index_state_cell_data(index: u16, sum: u16, history_root: &[u8; HASH_LEN]) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_V2_CELL_DATA_LEN);
+    time_buf.put_u8(INDEX_STATE_VERSION_HISTORY_ROOT);
+    time_buf.put_u16(index);
+    time_buf.put_u16(sum);
+    time_buf.put_slice(history_root);
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_v3_index_state_cell_data(index: u16, sum: u16, sequence: u64) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_V3_CELL_DATA_LEN);
+    time_buf.put_u8(INDEX_STATE_VERSION_SEQUENCE);
+    time_buf.put_u16(index);
+    time_buf.put_u16(sum);
+    time_buf.put_slice(&[0u8; HASH_LEN]);
+    time_buf.put_u64(sequence);
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_v3_index_state(index: u16, sequence: u64, history_root: [u8; HASH_LEN]) -> IndexState {
+    IndexState {
+        version: INDEX_STATE_VERSION_SEQUENCE,
+        index,
+        length: 1000,
+        history_root: Some(history_root),
+        sequence: Some(sequence),
+        info_root: None,
+    }
+}
+
+fn build_v3_info_cell_data(index: u16, sequence: u64, value: u64) -> Bytes {
+    let layout = InfoLayout::of_version(INDEX_STATE_VERSION_SEQUENCE);
+    let data = layout.build(
+        index,
+        DataType::Arbitrage as u8,
+        Some(sequence),
+        &value.to_be_bytes(),
+    );
+    Bytes::from(data)
+}
+
+// Since version 2 the args pin the info type code hash, which is the data hash of info-type in tests
+fn build_oracle_args(out_point: &OutPoint) -> Bytes {
+    let info_bin: Bytes = Loader::default().load_binary("info-type");
+    let mut args = out_point.as_slice().to_vec();
+    args.extend_from_slice(CellOutput::calc_data_hash(&info_bin).as_slice());
+    Bytes::from(args)
+}
+
 fn build_invalid_index_state_cell_data() -> Bytes {
     let mut time_buf = BytesMut::with_capacity(3);
     for _ in 0..3 {
@@ -68,7 +141,7 @@ fn create_test_context(
     let args = if is_type_args_error {
         Bytes::new()
     } else {
-        Bytes::copy_from_slice(input_out_point.as_slice().clone())
+        build_oracle_args(&input_out_point)
     };
     let index_state_type_script = context
         .build_script(&index_state_out_point, args)
@@ -135,7 +208,7 @@ fn create_test_context_with_index_state_inputs(
         Bytes::new(),
     );
 
-    let args = Bytes::copy_from_slice(normal_out_point.as_slice().clone());
+    let args = build_oracle_args(&normal_out_point);
     let index_state_type_script = context
         .build_script(&index_state_out_point, args)
         .expect("script");
@@ -199,6 +272,164 @@ fn create_test_context_with_index_state_inputs(
     (context, tx)
 }
 
+// Updates the index state cell with the info cell of the index, the info witness is the proof
+fn create_test_context_with_info_update(
+    input_data: Bytes,
+    output_data: Bytes,
+    input_info_data: Bytes,
+    output_info_data: Bytes,
+    info_witness: Bytes,
+) -> (Context, TransactionView) {
+    // deploy contract
+    let mut context = Context::default();
+    let index_state_bin: Bytes = Loader::default().load_binary("index-state-type");
+    let index_state_out_point = context.deploy_cell(index_state_bin);
+
+    let info_bin: Bytes = Loader::default().load_binary("info-type");
+    let info_out_point = context.deploy_cell(info_bin);
+
+    // deploy always_success script
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    // prepare scripts
+    let lock_script = context
+        .build_script(&always_success_out_point, Default::default())
+        .expect("script");
+    let lock_script_dep = CellDep::new_builder()
+        .out_point(always_success_out_point)
+        .build();
+
+    let genesis_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let args = build_oracle_args(&genesis_out_point);
+    let index_state_type_script = context
+        .build_script(&index_state_out_point, args.clone())
+        .expect("script");
+    let info_type_script = context.build_script(&info_out_point, args).expect("script");
+
+    let index_state_output = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script.clone())
+        .type_(Some(index_state_type_script).pack())
+        .build();
+    let info_output = CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script)
+        .type_(Some(info_type_script).pack())
+        .build();
+    let index_state_input_out_point = context.create_cell(index_state_output.clone(), input_data);
+    let info_input_out_point = context.create_cell(info_output.clone(), input_info_data);
+
+    let inputs = vec![
+        CellInput::new_builder()
+            .previous_output(index_state_input_out_point)
+            .build(),
+        CellInput::new_builder()
+            .previous_output(info_input_out_point)
+            .build(),
+    ];
+    let info_witness = WitnessArgs::new_builder()
+        .input_type(Some(info_witness).pack())
+        .build();
+    let witnesses = vec![Bytes::new(), info_witness.as_bytes()];
+
+    // build transaction
+    let tx = TransactionBuilder::default()
+        .inputs(inputs)
+        .outputs(vec![index_state_output, info_output])
+        .outputs_data(vec![output_data, output_info_data].pack())
+        .cell_dep(lock_script_dep)
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(index_state_out_point)
+                .build(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(info_out_point).build())
+        .witnesses(witnesses.pack())
+        .build();
+    (context, tx)
+}
+
+// Creates the index state cell with the info cells of the ring
+fn create_test_context_with_info_outputs(
+    index_state_data: Bytes,
+    infos_data: Vec<Bytes>,
+) -> (Context, TransactionView) {
+    // deploy contract
+    let mut context = Context::default();
+    let index_state_bin: Bytes = Loader::default().load_binary("index-state-type");
+    let index_state_out_point = context.deploy_cell(index_state_bin);
+
+    let info_bin: Bytes = Loader::default().load_binary("info-type");
+    let info_out_point = context.deploy_cell(info_bin);
+
+    // deploy always_success script
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    // prepare scripts
+    let lock_script = context
+        .build_script(&always_success_out_point, Default::default())
+        .expect("script");
+    let lock_script_dep = CellDep::new_builder()
+        .out_point(always_success_out_point)
+        .build();
+
+    let input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(10000u64.pack())
+            .lock(lock_script.clone())
+            .build(),
+        Bytes::new(),
+    );
+    let args = build_oracle_args(&input_out_point);
+    let index_state_type_script = context
+        .build_script(&index_state_out_point, args.clone())
+        .expect("script");
+    let info_type_script = context.build_script(&info_out_point, args).expect("script");
+
+    let mut outputs = vec![CellOutput::new_builder()
+        .capacity(500u64.pack())
+        .lock(lock_script.clone())
+        .type_(Some(index_state_type_script).pack())
+        .build()];
+    let mut outputs_data = vec![index_state_data];
+    for info_data in infos_data {
+        outputs.push(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(lock_script.clone())
+                .type_(Some(info_type_script.clone()).pack())
+                .build(),
+        );
+        outputs_data.push(info_data);
+    }
+
+    // build transaction
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(input_out_point)
+                .build(),
+        )
+        .outputs(outputs)
+        .outputs_data(outputs_data.pack())
+        .cell_dep(lock_script_dep)
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(index_state_out_point)
+                .build(),
+        )
+        .cell_dep(CellDep::new_builder().out_point(info_out_point).build())
+        .witness(Bytes::new().pack())
+        .build();
+    (context, tx)
+}
+
 #[test]
 fn test_create_index_state_cells_success() {
     let outputs_data = vec![
@@ -447,6 +678,565 @@ fn test_error_index_not_increase() {
     );
 }
 
+#[test]
+fn test_create_v1_index_state_cells_success() {
+    let outputs_data = vec![build_v1_index_state_cell_data(0, 1000), Bytes::new()];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_update_v1_index_state_cells_over_u8_success() {
+    let input_data = build_v1_index_state_cell_data(255, 1000);
+    let outputs_data = vec![build_v1_index_state_cell_data(256, 1000), Bytes::new()];
+    let (mut context, tx) =
+        create_test_context_with_index_state_inputs(input_data, &outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_update_full_v1_index_state_cells_success() {
+    let input_data = build_v1_index_state_cell_data(999, 1000);
+    let outputs_data = vec![build_v1_index_state_cell_data(0, 1000), Bytes::new()];
+    let (mut context, tx) =
+        create_test_context_with_index_state_inputs(input_data, &outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_v1_index_out_of_bound() {
+    let outputs_data = vec![build_v1_index_state_cell_data(1000, 1000), Bytes::new()];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(TIME_INDEX_OUT_OF_BOUND)
+            .output_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_v1_info_amount() {
+    let outputs_data = vec![build_v1_index_state_cell_data(0, 0), Bytes::new()];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(TIME_INFO_AMOUNT_ERROR)
+            .output_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_unknown_index_state_version() {
+    let mut data = build_v1_index_state_cell_data(0, 1000).to_vec();
+    data[0] = 0xff;
+    let outputs_data = vec![Bytes::from(data), Bytes::new()];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_VERSION_ERROR)
+            .output_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_index_state_version_changed() {
+    let input_data = build_index_state_cell_data(1, SUM_OF_INFO_CELLS);
+    let outputs_data = vec![
+        build_v1_index_state_cell_data(2, SUM_OF_INFO_CELLS as u16),
+        Bytes::new(),
+    ];
+    let (mut context, tx) =
+        create_test_context_with_index_state_inputs(input_data, &outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_VERSION_ERROR)
+            .input_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_create_v2_index_state_cells_success() {
+    let outputs_data = vec![
+        build_v2_index_state_cell_data(0, 1000, &[0u8; HASH_LEN]),
+        Bytes::new(),
+    ];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// Without the info cell info-type doesn't run, so the root could be set to anything
+#[test]
+fn test_error_update_v2_index_state_without_info() {
+    let input_data = build_v2_index_state_cell_data(999, 1000, &[0u8; HASH_LEN]);
+    let outputs_data = vec![
+        build_v2_index_state_cell_data(0, 1000, &[1u8; HASH_LEN]),
+        Bytes::new(),
+    ];
+    let (mut context, tx) =
+        create_test_context_with_index_state_inputs(input_data, &outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INFO_NOT_UPDATED).input_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_v2_index_state_len() {
+    let mut data = build_v2_index_state_cell_data(0, 1000, &[0u8; HASH_LEN]).to_vec();
+    data.pop();
+    let outputs_data = vec![Bytes::from(data), Bytes::new()];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_DATA_LEN_ERROR)
+            .output_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_index_state_version_changed_to_v2() {
+    let input_data = build_v1_index_state_cell_data(1, 1000);
+    let outputs_data = vec![
+        build_v2_index_state_cell_data(2, 1000, &[0u8; HASH_LEN]),
+        Bytes::new(),
+    ];
+    let (mut context, tx) =
+        create_test_context_with_index_state_inputs(input_data, &outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_VERSION_ERROR)
+            .input_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_create_v3_index_state_cells_success() {
+    let outputs_data = vec![build_v3_index_state_cell_data(0, 1000, 0), Bytes::new()];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_update_v3_index_state_cells_success() {
+    let input_info_data = build_v3_info_cell_data(0, 5000, 1);
+    let output_info_data = build_v3_info_cell_data(0, 6000, 2);
+    let history_root = extend_history_root(&[1u8; HASH_LEN], &output_info_data);
+    let (mut context, tx) = create_test_context_with_info_update(
+        Bytes::from(build_v3_index_state(999, 5999, [1u8; HASH_LEN]).to_vec()),
+        Bytes::from(build_v3_index_state(0, 6000, history_root).to_vec()),
+        input_info_data,
+        output_info_data,
+        Bytes::new(),
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_create_v3_index_state_sequence() {
+    let outputs_data = vec![build_v3_index_state_cell_data(0, 1000, 5), Bytes::new()];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_SEQUENCE_ERROR)
+            .output_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_v3_index_state_sequence_skipped() {
+    let input_data = build_v3_index_state_cell_data(1, 1000, 1);
+    let outputs_data = vec![build_v3_index_state_cell_data(2, 1000, 3), Bytes::new()];
+    let (mut context, tx) =
+        create_test_context_with_index_state_inputs(input_data, &outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_SEQUENCE_ERROR)
+            .input_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_v3_index_state_sequence_replayed() {
+    let input_data = build_v3_index_state_cell_data(1, 1000, 1);
+    let outputs_data = vec![build_v3_index_state_cell_data(2, 1000, 1), Bytes::new()];
+    let (mut context, tx) =
+        create_test_context_with_index_state_inputs(input_data, &outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_SEQUENCE_ERROR)
+            .input_type_script(script_cell_index)
+    );
+}
+
+// A version 4 ring of 12 info cells whose index state is at the last index, and the ring after
+// the info cell of index 0 is updated, with the proof of the index 0
+fn build_v4_update() -> (IndexState, IndexState, Vec<Bytes>, Vec<Bytes>, Bytes) {
+    let input_ring: Vec<Bytes> = (0..12)
+        .map(|index| build_v3_info_cell_data(index, index as u64, 1))
+        .collect();
+    let mut output_ring = input_ring.clone();
+    output_ring[0] = build_v3_info_cell_data(0, 12, 2);
+    let root_of = |ring: &[Bytes]| {
+        let leaves: Vec<&[u8]> = ring.iter().map(|data| data.as_ref()).collect();
+        info_root(&leaves)
+    };
+    let leaves: Vec<&[u8]> = input_ring.iter().map(|data| data.as_ref()).collect();
+    let proof = Bytes::from(info_proof(&leaves, 0));
+
+    let input_index_state = IndexState {
+        version: INDEX_STATE_VERSION_INFO_ROOT,
+        index: 11,
+        length: 12,
+        history_root: Some([1u8; HASH_LEN]),
+        sequence: Some(11),
+        info_root: Some(root_of(&input_ring)),
+    };
+    let output_index_state = IndexState {
+        index: 0,
+        history_root: Some(extend_history_root(&[1u8; HASH_LEN], &output_ring[0])),
+        sequence: Some(12),
+        info_root: Some(root_of(&output_ring)),
+        ..input_index_state
+    };
+    (
+        input_index_state,
+        output_index_state,
+        input_ring,
+        output_ring,
+        proof,
+    )
+}
+
+#[test]
+fn test_update_v4_index_state_cells_success() {
+    let (input_index_state, output_index_state, input_ring, output_ring, proof) = build_v4_update();
+    let (mut context, tx) = create_test_context_with_info_update(
+        Bytes::from(input_index_state.to_vec()),
+        Bytes::from(output_index_state.to_vec()),
+        input_ring[0].clone(),
+        output_ring[0].clone(),
+        proof,
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// Info-type rejects the forged root too, and either of them can fail first
+#[test]
+fn test_error_update_v4_forged_info_root() {
+    let (input_index_state, output_index_state, input_ring, output_ring, proof) = build_v4_update();
+    let output_index_state = IndexState {
+        info_root: Some([0u8; HASH_LEN]),
+        ..output_index_state
+    };
+    let (mut context, tx) = create_test_context_with_info_update(
+        Bytes::from(input_index_state.to_vec()),
+        Bytes::from(output_index_state.to_vec()),
+        input_ring[0].clone(),
+        output_ring[0].clone(),
+        proof,
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    assert_any_type_script_error(err, &[(INVALID_INFO_ROOT, 0), (INFO_INVALID_INFO_ROOT, 1)]);
+}
+
+// A ring of 13 has the same proof depth as the ring of 12, so only the length check rejects it
+#[test]
+fn test_error_update_v4_length_changed() {
+    let (input_index_state, output_index_state, input_ring, output_ring, proof) = build_v4_update();
+    let input_index_state = IndexState {
+        length: 13,
+        ..input_index_state
+    };
+    let (mut context, tx) = create_test_context_with_info_update(
+        Bytes::from(input_index_state.to_vec()),
+        Bytes::from(output_index_state.to_vec()),
+        input_ring[0].clone(),
+        output_ring[0].clone(),
+        proof,
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    assert_any_type_script_error(
+        err,
+        &[(TIME_INFO_AMOUNT_ERROR, 0), (INFO_INVALID_INFO_ROOT, 1)],
+    );
+}
+
+// A version 4 ring of 4 info cells at sequence 0, and the index state at the last index
+fn build_v4_genesis() -> (IndexState, Vec<Bytes>) {
+    let ring: Vec<Bytes> = (0..4)
+        .map(|index| build_v3_info_cell_data(index, 0, 1))
+        .collect();
+    let leaves: Vec<&[u8]> = ring.iter().map(|data| data.as_ref()).collect();
+    let index_state = IndexState {
+        version: INDEX_STATE_VERSION_INFO_ROOT,
+        index: 3,
+        length: 4,
+        history_root: Some([0u8; HASH_LEN]),
+        sequence: Some(0),
+        info_root: Some(info_root(&leaves)),
+    };
+    (index_state, ring)
+}
+
+#[test]
+fn test_create_v4_index_state_cells_success() {
+    let (index_state, ring) = build_v4_genesis();
+    let (mut context, tx) =
+        create_test_context_with_info_outputs(Bytes::from(index_state.to_vec()), ring);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// Info-type doesn't check the root of a new ring, so it is only checked here
+#[test]
+fn test_error_create_v4_forged_info_root() {
+    let (index_state, ring) = build_v4_genesis();
+    let index_state = IndexState {
+        info_root: Some([0u8; HASH_LEN]),
+        ..index_state
+    };
+    let (mut context, tx) =
+        create_test_context_with_info_outputs(Bytes::from(index_state.to_vec()), ring);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INVALID_INFO_ROOT).output_type_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_create_v4_info_cell_missing() {
+    let (index_state, mut ring) = build_v4_genesis();
+    ring.pop();
+    let (mut context, tx) =
+        create_test_context_with_info_outputs(Bytes::from(index_state.to_vec()), ring);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(TIME_INFO_AMOUNT_ERROR)
+            .output_type_script(script_cell_index)
+    );
+}
+
+// The version 4 genesis with the version 5 index state, whose oracle cells can only be created once
+fn build_v5_genesis() -> (IndexState, Vec<Bytes>) {
+    let (index_state, ring) = build_v4_genesis();
+    let index_state = IndexState {
+        version: INDEX_STATE_VERSION_UNIQUE,
+        ..index_state
+    };
+    (index_state, ring)
+}
+
+// Another cell becomes the first input, so the args are no longer its out point
+fn prepend_input(context: &mut Context, tx: TransactionView) -> TransactionView {
+    let lock_script = tx.output(0).expect("output").lock();
+    let out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let mut inputs = vec![CellInput::new_builder().previous_output(out_point).build()];
+    inputs.extend(tx.inputs().into_iter());
+    tx.as_advanced_builder().set_inputs(inputs).build()
+}
+
+#[test]
+fn test_create_v5_index_state_cells_success() {
+    let (index_state, ring) = build_v5_genesis();
+    let (mut context, tx) =
+        create_test_context_with_info_outputs(Bytes::from(index_state.to_vec()), ring);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// Before version 5 the args don't have to be the out point of the first input
+#[test]
+fn test_create_v4_index_state_cells_args_not_type_id_success() {
+    let (index_state, ring) = build_v4_genesis();
+    let (mut context, tx) =
+        create_test_context_with_info_outputs(Bytes::from(index_state.to_vec()), ring);
+    let tx = prepend_input(&mut context, tx);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// Since version 5 the args must be the out point of the first input, or anyone could create
+// another index state cell or more info cells with the type scripts of an existing oracle, and
+// both scripts check it
+#[test]
+fn test_error_create_v5_index_state_args_not_type_id() {
+    let (index_state, ring) = build_v5_genesis();
+    let (mut context, tx) =
+        create_test_context_with_info_outputs(Bytes::from(index_state.to_vec()), ring);
+    let tx = prepend_input(&mut context, tx);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    assert_any_type_script_error(err, &[(INVALID_ARGUMENT, 0), (INFO_INVALID_ARGUMENT, 1)]);
+}
+
+#[test]
+fn test_error_create_two_v5_index_state_cells() {
+    let (index_state, ring) = build_v5_genesis();
+    let (mut context, tx) =
+        create_test_context_with_info_outputs(Bytes::from(index_state.to_vec()), ring);
+    let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+    outputs.insert(1, outputs[0].clone());
+    let mut outputs_data: Vec<packed::Bytes> = tx.outputs_data().into_iter().collect();
+    outputs_data.insert(1, outputs_data[0].clone());
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs(outputs)
+        .set_outputs_data(outputs_data)
+        .build();
+
+    let tx = context.complete_tx(tx);
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INDEX_STATE_NOT_UNIQUE)
+            .output_type_script(script_cell_index)
+    );
+}
+
 #[test]
 fn test_consume_index_state_cells() {
     // deploy contract
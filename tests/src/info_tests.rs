@@ -4,13 +4,17 @@ use ckb_tool::ckb_error::assert_error_eq;
 use ckb_tool::ckb_script::ScriptError;
 use ckb_tool::ckb_types::{
     bytes::{BufMut, Bytes, BytesMut},
-    core::{TransactionBuilder, TransactionView},
+    core::{HeaderBuilder, HeaderView, TransactionBuilder, TransactionView},
     packed::*,
     prelude::*,
 };
 
 use ckb_x64_simulator::RunningSetup;
-use common::constants::*;
+use common::{
+    constants::*,
+    history::extend_history_root,
+    merkle::{info_proof, info_root},
+};
 use std::collections::HashMap;
 
 const MAX_CYCLES: u64 = 10_000_000;
@@ -24,6 +28,22 @@ const TIME_INFO_INDEX_NOT_SAME: i8 = 9;
 const OUTPUT_TIMESTAMP_NOT_BIGGER: i8 = 10;
 const OUTPUT_BLOCK_NUMBER_NOT_BIGGER: i8 = 11;
 const INVALID_TIME_INFO_SINCE: i8 = 12;
+const HEADER_DEP_NOT_EXIST: i8 = 14;
+const INVALID_HEADER_DEP_TIMESTAMP: i8 = 15;
+const INVALID_HEADER_DEPS: i8 = 16;
+const INVALID_MEDIAN_TIMESTAMP: i8 = 17;
+const INVALID_HEADER_DEP_BLOCK_HASH: i8 = 18;
+const INVALID_HISTORY_ROOT: i8 = 19;
+const INVALID_INFO_SEQUENCE: i8 = 20;
+const INVALID_INFO_PROOF: i8 = 21;
+const INVALID_INFO_ROOT: i8 = 22;
+const INFO_NOT_UNIQUE: i8 = 23;
+
+// The errors of index-state-type, which checks the info cells of an update too
+const INDEX_STATE_INFO_NOT_UPDATED: i8 = 14;
+const INDEX_STATE_INVALID_HISTORY_ROOT: i8 = 15;
+const INDEX_STATE_INVALID_INFO_PROOF: i8 = 16;
+const INDEX_STATE_INVALID_INFO_ROOT: i8 = 17;
 
 fn build_index_state_cell_data(index: u8, is_data_len_err: bool) -> Bytes {
     let mut time_buf = BytesMut::with_capacity(INDEX_STATE_CELL_DATA_LEN);
@@ -34,6 +54,140 @@ fn build_index_state_cell_data(index: u8, is_data_len_err: bool) -> Bytes {
     Bytes::from(time_buf.to_vec())
 }
 
+fn build_v1_index_state_cell_data(index: u16, sum: u16) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_V1_CELL_DATA_LEN);
+    time_buf.put_u8(INDEX_STATE_VERSION_U16);
+    time_buf.put_u16(index);
+    time_buf.put_u16(sum);
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_v2_index_state_cell_data(index: u16, sum: u16, history_root: &[u8; HASH_LEN]) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_V2_CELL_DATA_LEN);
+    time_buf.put_u8(INDEX_STATE_VERSION_HISTORY_ROOT);
+    time_buf.put_u16(index);
+    time_buf.put_u16(sum);
+    time_buf.put_slice(history_root);
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_v3_index_state_cell_data(
+    index: u16,
+    sum: u16,
+    history_root: &[u8; HASH_LEN],
+    sequence: u64,
+) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_V3_CELL_DATA_LEN);
+    time_buf.put_u8(INDEX_STATE_VERSION_SEQUENCE);
+    time_buf.put_u16(index);
+    time_buf.put_u16(sum);
+    time_buf.put_slice(history_root);
+    time_buf.put_u64(sequence);
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_v4_index_state_cell_data(
+    index: u16,
+    sum: u16,
+    history_root: &[u8; HASH_LEN],
+    sequence: u64,
+    info_root: &[u8; HASH_LEN],
+) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_V4_CELL_DATA_LEN);
+    time_buf.put_u8(INDEX_STATE_VERSION_INFO_ROOT);
+    time_buf.put_u16(index);
+    time_buf.put_u16(sum);
+    time_buf.put_slice(history_root);
+    time_buf.put_u64(sequence);
+    time_buf.put_slice(info_root);
+    Bytes::from(time_buf.to_vec())
+}
+
+// The v3 info cells of a ring at the sequence, the info cell of index i has the timestamp 1614828000 + i * 60
+fn build_v3_ring(length: u16, sequence: u64) -> Vec<Bytes> {
+    (0..length)
+        .map(|index| {
+            build_v3_info_cell_data(
+                index,
+                DataType::Timestamp,
+                sequence - (length - 1 - index) as u64,
+                1614828000 + index as u64 * 60,
+            )
+        })
+        .collect()
+}
+
+fn attach_info_proof(tx: TransactionView, proof: &[u8]) -> TransactionView {
+    let witness_args = WitnessArgs::new_builder()
+        .input_type(Some(Bytes::copy_from_slice(proof)).pack())
+        .build();
+    tx.as_advanced_builder()
+        .set_witnesses(vec![Bytes::new().pack(), witness_args.as_bytes().pack()])
+        .build()
+}
+
+// The info cell of index 5 is replaced by the timestamp 1614829080 at the sequence 12
+fn build_v4_update(output_info_root: Option<[u8; HASH_LEN]>) -> (Vec<Bytes>, Vec<Bytes>, Vec<u8>) {
+    let history_root = [1u8; HASH_LEN];
+    let mut ring = build_v3_ring(12, 11);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_ref()).collect();
+    let input_info_root = info_root(&infos_data);
+    let proof = info_proof(&infos_data, 5);
+
+    let input_info_data = ring[5].clone();
+    ring[5] = build_v3_info_cell_data(5, DataType::Timestamp, 12, 1614829080);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_ref()).collect();
+    let output_info_root = output_info_root.unwrap_or_else(|| info_root(&infos_data));
+
+    let inputs_data = vec![
+        build_v4_index_state_cell_data(4, 12, &history_root, 11, &input_info_root),
+        input_info_data,
+    ];
+    let outputs_data = vec![
+        build_v4_index_state_cell_data(
+            5,
+            12,
+            &extend_history_root(&history_root, &ring[5]),
+            12,
+            &output_info_root,
+        ),
+        ring[5].clone(),
+    ];
+    (inputs_data, outputs_data, proof)
+}
+
+// The update of build_v4_update with the version 5 index state, which has the same data
+fn build_v5_update() -> (Vec<Bytes>, Vec<Bytes>, Vec<u8>) {
+    let (inputs_data, outputs_data, proof) = build_v4_update(None);
+    let to_v5 = |data: &Bytes| {
+        let mut data = data.to_vec();
+        data[0] = INDEX_STATE_VERSION_UNIQUE;
+        Bytes::from(data)
+    };
+    (
+        vec![to_v5(&inputs_data[0]), inputs_data[1].clone()],
+        vec![to_v5(&outputs_data[0]), outputs_data[1].clone()],
+        proof,
+    )
+}
+
+fn build_v3_info_cell_data(index: u16, type_: DataType, sequence: u64, data: u64) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INFO_CELL_V3_META_LEN + INFO_CELL_CONTENT_LEN);
+    time_buf.put_u16(index);
+    time_buf.put_u8(type_ as u8);
+    time_buf.put_u64(sequence);
+    time_buf.put_u64(data);
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_v1_info_cell_data(index: u16, type_: DataType, data: u64) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INFO_CELL_V1_META_LEN + INFO_CELL_CONTENT_LEN);
+    time_buf.put_u16(index);
+    time_buf.put_u8(type_ as u8);
+    time_buf.put_u64(data);
+    Bytes::from(time_buf.to_vec())
+}
+
 fn build_info_cell_data(index: u8, type_: DataType, data: u64) -> Bytes {
     let mut time_buf = BytesMut::with_capacity(INFO_CELL_DATA_LEN);
     time_buf.put_u8(index);
@@ -42,6 +196,23 @@ fn build_info_cell_data(index: u8, type_: DataType, data: u64) -> Bytes {
     Bytes::from(time_buf.to_vec())
 }
 
+fn build_block_hash_info_cell_data(index: u8, block_number: u64, block_hash: &Byte32) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(BLOCK_HASH_INFO_CELL_DATA_LEN);
+    time_buf.put_u8(index);
+    time_buf.put_u8(DataType::BlockHash as u8);
+    time_buf.put_u64(block_number);
+    time_buf.put_slice(block_hash.as_slice());
+    Bytes::from(time_buf.to_vec())
+}
+
+fn build_info_cell_data_with_content(index: u8, type_: DataType, content: &[u8]) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INFO_CELL_META_LEN + content.len());
+    time_buf.put_u8(index);
+    time_buf.put_u8(type_ as u8);
+    time_buf.put_slice(content);
+    Bytes::from(time_buf.to_vec())
+}
+
 fn build_wrong_info_cell_data(index: u8, type_: DataType) -> Bytes {
     let mut time_buf = BytesMut::with_capacity(INFO_CELL_DATA_LEN - 1);
     time_buf.put_u8(index);
@@ -49,6 +220,56 @@ fn build_wrong_info_cell_data(index: u8, type_: DataType) -> Bytes {
     Bytes::from(time_buf.to_vec())
 }
 
+fn build_header(number: u64, timestamp: u64) -> HeaderView {
+    HeaderBuilder::default()
+        .number(number.pack())
+        .timestamp(timestamp.pack())
+        .build()
+}
+
+// Consecutive headers from start_number, the median of their timestamps is 1614829089000
+fn build_median_headers(start_number: u64) -> Vec<HeaderView> {
+    let timestamps: [u64; MEDIAN_TIMESTAMP_HEADERS] = [
+        1614829080000,
+        1614829085000,
+        1614829070000,
+        1614829093000,
+        1614829089000,
+        1614829088000,
+        1614829099000,
+        1614829061000,
+        1614829100000,
+        1614829095000,
+        1614829999000,
+    ];
+    timestamps
+        .iter()
+        .enumerate()
+        .map(|(i, timestamp)| build_header(start_number + i as u64, *timestamp))
+        .collect()
+}
+
+fn attach_header_deps(
+    context: &mut Context,
+    tx: TransactionView,
+    headers: Vec<HeaderView>,
+) -> TransactionView {
+    let mut builder = tx.as_advanced_builder();
+    for header in headers {
+        builder = builder.header_dep(header.hash());
+        context.insert_header(header);
+    }
+    builder.build()
+}
+
+// Since version 2 the args pin the info type code hash, which is the data hash of info-type in tests
+fn build_oracle_args(out_point: &OutPoint) -> Bytes {
+    let info_bin: Bytes = Loader::default().load_binary("info-type");
+    let mut args = out_point.as_slice().to_vec();
+    args.extend_from_slice(CellOutput::calc_data_hash(&info_bin).as_slice());
+    Bytes::from(args)
+}
+
 fn create_test_context(
     outputs_data: &Vec<Bytes>,
     is_type_args_error: bool,
@@ -81,11 +302,9 @@ fn create_test_context(
         Bytes::new(),
     );
 
+    let oracle_args = build_oracle_args(&normal_input_out_point);
     let index_state_type_script = context
-        .build_script(
-            &index_state_out_point,
-            Bytes::copy_from_slice(&normal_input_out_point.as_slice()),
-        )
+        .build_script(&index_state_out_point, oracle_args.clone())
         .expect("script");
     let index_state_type_script_dep = CellDep::new_builder()
         .out_point(index_state_out_point)
@@ -94,7 +313,7 @@ fn create_test_context(
     let args = if is_type_args_error {
         Bytes::new()
     } else {
-        Bytes::copy_from_slice(&normal_input_out_point.as_slice())
+        oracle_args
     };
     let info_type_script = context.build_script(&info_out_point, args).expect("script");
     let info_type_script_dep = CellDep::new_builder().out_point(info_out_point).build();
@@ -178,7 +397,7 @@ fn create_test_context_with_info_inputs(
         Bytes::new(),
     );
 
-    let args = Bytes::copy_from_slice(&normal_input_out_point.as_slice());
+    let args = build_oracle_args(&normal_input_out_point);
     let index_state_type_script = context
         .build_script(&index_state_out_point, args.clone())
         .expect("script");
@@ -340,6 +559,29 @@ fn test_update_info_block_number_cells_success() {
     println!("consume cycles: {}", cycles);
 }
 
+#[test]
+fn test_update_info_timestamp_millis_cells_success() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::TimestampMillis, 1614828683123),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::TimestampMillis, 1614829080456),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, vec![build_header(100, 1614829080456)]);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
 #[test]
 fn test_error_create_info_cells_invalid_args() {
     let outputs_data = vec![
@@ -714,3 +956,692 @@ fn test_consume_info_cells() {
         .expect("pass verification");
     println!("consume cycles: {}", cycles);
 }
+
+#[test]
+fn test_error_timestamp_millis_header_dep_not_exist() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::TimestampMillis, 1614828683123),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::TimestampMillis, 1614829080456),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, HEADER_DEP_NOT_EXIST, script_cell_index);
+}
+
+#[test]
+fn test_error_timestamp_millis_not_header_dep_timestamp() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::TimestampMillis, 1614828683123),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::TimestampMillis, 1614829080456),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, vec![build_header(100, 1614829080999)]);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, INVALID_HEADER_DEP_TIMESTAMP, script_cell_index);
+}
+
+#[test]
+fn test_error_timestamp_millis_since() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::TimestampMillis, 1614828683123),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::TimestampMillis, 1614829080456),
+    ];
+    // since must be the seconds of the millisecond timestamp, not the milliseconds
+    let since = SINCE_TIMESTAMP_BASE + 1614829080456;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, vec![build_header(100, 1614829080456)]);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, INVALID_TIME_INFO_SINCE, script_cell_index);
+}
+
+#[test]
+fn test_update_info_median_timestamp_cells_success() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::MedianTimestamp, 1614828683000),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::MedianTimestamp, 1614829089000),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829089;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, build_median_headers(100));
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_median_timestamp_not_enough_headers() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::MedianTimestamp, 1614828683000),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::MedianTimestamp, 1614829089000),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829089;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let mut headers = build_median_headers(100);
+    headers.pop();
+    let tx = attach_header_deps(&mut context, tx, headers);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, HEADER_DEP_NOT_EXIST, script_cell_index);
+}
+
+#[test]
+fn test_error_median_timestamp_headers_not_consecutive() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::MedianTimestamp, 1614828683000),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::MedianTimestamp, 1614829089000),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829089;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let mut headers = build_median_headers(100);
+    headers.swap(0, 1);
+    let tx = attach_header_deps(&mut context, tx, headers);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, INVALID_HEADER_DEPS, script_cell_index);
+}
+
+#[test]
+fn test_error_median_timestamp_not_median() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::MedianTimestamp, 1614828683000),
+    ];
+    // the latest header timestamp instead of the median one
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::MedianTimestamp, 1614829999000),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829999;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, build_median_headers(100));
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, INVALID_MEDIAN_TIMESTAMP, script_cell_index);
+}
+
+// The script can't read the tip, so an older window is only bounded by the previous value
+#[test]
+fn test_error_median_timestamp_window_not_newer() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data(2, DataType::MedianTimestamp, 1614829089000),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::MedianTimestamp, 1614829089000),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829089;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, build_median_headers(1));
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, OUTPUT_TIMESTAMP_NOT_BIGGER, script_cell_index);
+}
+
+#[test]
+fn test_update_info_block_hash_cells_success() {
+    let input_header = build_header(10000, 1614828683000);
+    let output_header = build_header(10003, 1614829080000);
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_block_hash_info_cell_data(2, 10000, &input_header.hash()),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_block_hash_info_cell_data(3, 10003, &output_header.hash()),
+    ];
+    let since: u64 = 10003;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, vec![output_header]);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_block_hash_not_header_dep_hash() {
+    let input_header = build_header(10000, 1614828683000);
+    let output_header = build_header(10003, 1614829080000);
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_block_hash_info_cell_data(2, 10000, &input_header.hash()),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_block_hash_info_cell_data(3, 10003, &input_header.hash()),
+    ];
+    let since: u64 = 10003;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, vec![output_header]);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, INVALID_HEADER_DEP_BLOCK_HASH, script_cell_index);
+}
+
+#[test]
+fn test_error_block_hash_not_header_dep_number() {
+    let input_header = build_header(10000, 1614828683000);
+    let output_header = build_header(10003, 1614829080000);
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_block_hash_info_cell_data(2, 10000, &input_header.hash()),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_block_hash_info_cell_data(3, 10004, &output_header.hash()),
+    ];
+    let since: u64 = 10004;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, vec![output_header]);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, INVALID_HEADER_DEP_BLOCK_HASH, script_cell_index);
+}
+
+#[test]
+fn test_error_block_hash_info_cell_data_len() {
+    let input_header = build_header(10000, 1614828683000);
+    let output_header = build_header(10003, 1614829080000);
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_block_hash_info_cell_data(2, 10000, &input_header.hash()),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::BlockHash, 10003),
+    ];
+    let since: u64 = 10003;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_header_deps(&mut context, tx, vec![output_header]);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, TIME_INFO_DATA_LEN_ERROR, script_cell_index);
+}
+
+#[test]
+fn test_create_info_uint128_cells_success() {
+    let outputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data_with_content(2, DataType::Uint128, &u128::max_value().to_be_bytes()),
+    ];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_update_info_payload_cells_success() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data_with_content(2, DataType::Payload, &[1, 2, 3]),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data_with_content(3, DataType::Payload, &[4; MAX_INFO_CELL_CONTENT_LEN]),
+    ];
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, 0, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_create_info_hash_cell_data_len() {
+    let outputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data_with_content(2, DataType::Hash, &[0; 31]),
+    ];
+    let (mut context, tx) = create_test_context(&outputs_data, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, TIME_INFO_DATA_LEN_ERROR, script_cell_index);
+}
+
+#[test]
+fn test_error_update_info_payload_cell_data_len() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data_with_content(2, DataType::Payload, &[1, 2, 3]),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data_with_content(
+            3,
+            DataType::Payload,
+            &[4; MAX_INFO_CELL_CONTENT_LEN + 1],
+        ),
+    ];
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, 0, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, TIME_INFO_DATA_LEN_ERROR, script_cell_index);
+}
+
+#[test]
+fn test_error_update_info_timestamp_from_short_payload() {
+    let inputs_data = vec![
+        build_index_state_cell_data(2, false),
+        build_info_cell_data_with_content(2, DataType::Payload, &[1, 2, 3]),
+    ];
+    let outputs_data = vec![
+        build_index_state_cell_data(3, false),
+        build_info_cell_data(3, DataType::Timestamp, 1614829080),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, TIME_INFO_DATA_LEN_ERROR, script_cell_index);
+}
+
+#[test]
+fn test_update_v1_info_timestamp_cells_success() {
+    let inputs_data = vec![
+        build_v1_index_state_cell_data(999, 1000),
+        build_v1_info_cell_data(999, DataType::Timestamp, 1614828683),
+    ];
+    let outputs_data = vec![
+        build_v1_index_state_cell_data(0, 1000),
+        build_v1_info_cell_data(0, DataType::Timestamp, 1614829080),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_v1_info_index_not_same() {
+    let inputs_data = vec![
+        build_v1_index_state_cell_data(299, 1000),
+        build_v1_info_cell_data(299, DataType::Timestamp, 1614828683),
+    ];
+    // 300 and 44 have the same low byte
+    let outputs_data = vec![
+        build_v1_index_state_cell_data(300, 1000),
+        build_v1_info_cell_data(44, DataType::Timestamp, 1614829080),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, TIME_INFO_INDEX_NOT_SAME, script_cell_index);
+}
+
+#[test]
+fn test_error_v1_info_cell_with_legacy_layout() {
+    let inputs_data = vec![
+        build_v1_index_state_cell_data(2, 1000),
+        build_v1_info_cell_data(2, DataType::Timestamp, 1614828683),
+    ];
+    let outputs_data = vec![
+        build_v1_index_state_cell_data(3, 1000),
+        build_info_cell_data(3, DataType::Timestamp, 1614829080),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, TIME_INFO_DATA_LEN_ERROR, script_cell_index);
+}
+
+#[test]
+fn test_update_v2_info_timestamp_cells_success() {
+    let history_root = [1u8; HASH_LEN];
+    let output_info_data = build_v1_info_cell_data(0, DataType::Timestamp, 1614829080);
+    let inputs_data = vec![
+        build_v2_index_state_cell_data(999, 1000, &history_root),
+        build_v1_info_cell_data(999, DataType::Timestamp, 1614828683),
+    ];
+    let outputs_data = vec![
+        build_v2_index_state_cell_data(
+            0,
+            1000,
+            &extend_history_root(&history_root, &output_info_data),
+        ),
+        output_info_data,
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_v2_history_root_not_extended() {
+    let history_root = [1u8; HASH_LEN];
+    let inputs_data = vec![
+        build_v2_index_state_cell_data(999, 1000, &history_root),
+        build_v1_info_cell_data(999, DataType::Timestamp, 1614828683),
+    ];
+    // the root is extended by the input info data instead of the output one
+    let outputs_data = vec![
+        build_v2_index_state_cell_data(
+            0,
+            1000,
+            &extend_history_root(&history_root, &inputs_data[1]),
+        ),
+        build_v1_info_cell_data(0, DataType::Timestamp, 1614829080),
+    ];
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    assert_any_type_script_error(
+        err,
+        &[
+            (INVALID_HISTORY_ROOT, 1),
+            (INDEX_STATE_INVALID_HISTORY_ROOT, 0),
+        ],
+    );
+}
+
+#[test]
+fn test_update_v3_info_block_number_cells_success() {
+    let history_root = [1u8; HASH_LEN];
+    let output_info_data = build_v3_info_cell_data(0, DataType::BlockNumber, 6000, 10003);
+    let inputs_data = vec![
+        build_v3_index_state_cell_data(999, 1000, &history_root, 5999),
+        build_v3_info_cell_data(999, DataType::BlockNumber, 5999, 10000),
+    ];
+    let outputs_data = vec![
+        build_v3_index_state_cell_data(
+            0,
+            1000,
+            &extend_history_root(&history_root, &output_info_data),
+            6000,
+        ),
+        output_info_data,
+    ];
+    let since = 10003;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_v3_info_sequence() {
+    let history_root = [1u8; HASH_LEN];
+    let output_info_data = build_v3_info_cell_data(0, DataType::BlockNumber, 5999, 10003);
+    let inputs_data = vec![
+        build_v3_index_state_cell_data(999, 1000, &history_root, 5999),
+        build_v3_info_cell_data(999, DataType::BlockNumber, 5999, 10000),
+    ];
+    let outputs_data = vec![
+        build_v3_index_state_cell_data(
+            0,
+            1000,
+            &extend_history_root(&history_root, &output_info_data),
+            6000,
+        ),
+        output_info_data,
+    ];
+    let since = 10003;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 1;
+    assert_type_script_error(err, INVALID_INFO_SEQUENCE, script_cell_index);
+}
+
+#[test]
+fn test_update_v4_info_timestamp_cells_success() {
+    let (inputs_data, outputs_data, proof) = build_v4_update(None);
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_info_proof(tx, &proof);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_v4_info_root() {
+    let (inputs_data, outputs_data, proof) = build_v4_update(Some([0u8; HASH_LEN]));
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_info_proof(tx, &proof);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    assert_any_type_script_error(
+        err,
+        &[(INVALID_INFO_ROOT, 1), (INDEX_STATE_INVALID_INFO_ROOT, 0)],
+    );
+}
+
+#[test]
+fn test_error_v4_info_proof_not_exist() {
+    let (inputs_data, outputs_data, _) = build_v4_update(None);
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    assert_any_type_script_error(
+        err,
+        &[(INVALID_INFO_PROOF, 1), (INDEX_STATE_INVALID_INFO_PROOF, 0)],
+    );
+}
+
+#[test]
+fn test_update_v5_info_timestamp_cells_success() {
+    let (inputs_data, outputs_data, proof) = build_v5_update();
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_info_proof(tx, &proof);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+// Since version 5 an update has exactly one info cell input and output, index-state-type rejects
+// another info output too
+#[test]
+fn test_error_update_v5_info_cells_duplicated() {
+    let (inputs_data, outputs_data, proof) = build_v5_update();
+    let since = SINCE_TIMESTAMP_BASE + 1614829080;
+    let (mut context, tx) =
+        create_test_context_with_info_inputs(&inputs_data, &outputs_data, since, false);
+    let tx = attach_info_proof(tx, &proof);
+    // another info cell of the index 5 which wouldn't be checked
+    let tx = tx
+        .as_advanced_builder()
+        .output(tx.output(1).expect("output"))
+        .output_data(build_v3_info_cell_data(5, DataType::Timestamp, 12, 99999).pack())
+        .build();
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    assert_any_type_script_error(
+        err,
+        &[(INFO_NOT_UNIQUE, 1), (INDEX_STATE_INFO_NOT_UPDATED, 0)],
+    );
+}
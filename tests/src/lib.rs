@@ -7,8 +7,13 @@ use ckb_standalone_debugger::transaction::{
 use ckb_testtool::context::Context;
 use ckb_tool::ckb_error::Error;
 use ckb_tool::ckb_script::ScriptError;
-use ckb_tool::ckb_types::bytes::Bytes;
+use ckb_tool::ckb_types::{
+    bytes::{BufMut, Bytes, BytesMut},
+    packed::{CellDep, CellOutput, Script},
+    prelude::*,
+};
 use ckb_x64_simulator::RunningSetup;
+use common::constants::*;
 use serde_json::to_string_pretty;
 use std::env;
 use std::fs;
@@ -23,6 +28,42 @@ mod info_tests;
 #[cfg(test)]
 mod index_state_tests;
 
+#[cfg(test)]
+mod history_tests;
+
+#[cfg(test)]
+mod estimate_tests;
+
+#[cfg(test)]
+mod freshness_tests;
+
+#[cfg(test)]
+mod time_lock_tests;
+
+#[cfg(test)]
+mod expiry_tests;
+
+#[cfg(test)]
+mod vesting_tests;
+
+#[cfg(test)]
+mod aggregate_tests;
+
+#[cfg(test)]
+mod merkle_tests;
+
+#[cfg(test)]
+mod updater_tests;
+
+#[cfg(test)]
+mod cli_tests;
+
+#[cfg(test)]
+mod migration_tests;
+
+#[cfg(test)]
+mod script_error_tests;
+
 lazy_static! {
     static ref LOADER: Loader = Loader::default();
     static ref TX_FOLDER: PathBuf = {
@@ -94,7 +135,7 @@ impl Loader {
     }
 }
 
-pub fn assert_type_script_error(err: Error, error_code: i8, script_cell_index: usize) {
+fn is_type_script_error(error: &str, error_code: i8, script_cell_index: usize) -> bool {
     let input_type_error = Into::<Error>::into(
         ScriptError::ValidationFailure(error_code).input_type_script(script_cell_index),
     )
@@ -103,9 +144,104 @@ pub fn assert_type_script_error(err: Error, error_code: i8, script_cell_index: u
         ScriptError::ValidationFailure(error_code).output_type_script(script_cell_index),
     )
     .to_string();
-    let error = Into::<Error>::into(err).to_string();
-    let result = input_type_error == error || output_type_error == error;
-    assert!(result);
+    input_type_error == error || output_type_error == error
+}
+
+pub fn assert_type_script_error(err: Error, error_code: i8, script_cell_index: usize) {
+    let error = err.to_string();
+    assert!(is_type_script_error(&error, error_code, script_cell_index));
+}
+
+// Both index-state-type and info-type check the roots of an update, and either of them can fail
+// first, so the error is one of the (error code, script cell index)
+pub fn assert_any_type_script_error(err: Error, errors: &[(i8, usize)]) {
+    let error = err.to_string();
+    assert!(errors
+        .iter()
+        .any(|(error_code, script_cell_index)| is_type_script_error(
+            &error,
+            *error_code,
+            *script_cell_index
+        )));
+}
+
+pub fn build_legacy_index_state_cell_data(index: u8) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INDEX_STATE_CELL_DATA_LEN);
+    time_buf.put_u8(index);
+    time_buf.put_u8(SUM_OF_INFO_CELLS);
+    Bytes::from(time_buf.to_vec())
+}
+
+pub fn build_legacy_info_cell_data(index: u8, type_: DataType, data: u64) -> Bytes {
+    let mut time_buf = BytesMut::with_capacity(INFO_CELL_DATA_LEN);
+    time_buf.put_u8(index);
+    time_buf.put_u8(type_ as u8);
+    time_buf.put_u64(data);
+    Bytes::from(time_buf.to_vec())
+}
+
+// The oracle which a consumer script reads from the cell deps
+pub struct OracleFixture {
+    pub index_state_type_script: Script,
+    pub info_type_script: Script,
+    // The index state cell dep and then the info cell deps
+    pub cell_deps: Vec<CellDep>,
+}
+
+// Deploys index-state-type and info-type, and creates the oracle cells with the lock. The info type
+// script has the args of the index state type script, unless the info args are given.
+pub fn create_oracle_cell_deps(
+    context: &mut Context,
+    lock_script: &Script,
+    index_state_data: Bytes,
+    infos_data: &[Bytes],
+    info_args: Option<Bytes>,
+) -> OracleFixture {
+    let index_state_bin: Bytes = Loader::default().load_binary("index-state-type");
+    let index_state_out_point = context.deploy_cell(index_state_bin);
+    let info_bin: Bytes = Loader::default().load_binary("info-type");
+    let info_out_point = context.deploy_cell(info_bin);
+
+    let oracle_args = Bytes::from(vec![2; OUT_POINT_LEN]);
+    let index_state_type_script = context
+        .build_script(&index_state_out_point, oracle_args.clone())
+        .expect("script");
+    let info_type_script = context
+        .build_script(&info_out_point, info_args.unwrap_or(oracle_args))
+        .expect("script");
+
+    let index_state_cell_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(500u64.pack())
+            .lock(lock_script.clone())
+            .type_(Some(index_state_type_script.clone()).pack())
+            .build(),
+        index_state_data,
+    );
+    let mut cell_deps = vec![CellDep::new_builder()
+        .out_point(index_state_cell_out_point)
+        .build()];
+    for info_data in infos_data {
+        let info_cell_out_point = context.create_cell(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(lock_script.clone())
+                .type_(Some(info_type_script.clone()).pack())
+                .build(),
+            info_data.clone(),
+        );
+        cell_deps.push(
+            CellDep::new_builder()
+                .out_point(info_cell_out_point)
+                .build(),
+        );
+    }
+
+    OracleFixture {
+        index_state_type_script,
+        info_type_script,
+        cell_deps,
+    }
 }
 
 fn create_test_folder(name: &str) -> PathBuf {
@@ -0,0 +1,149 @@
+use common::{constants::*, merkle::*};
+
+fn build_v3_info_cell_data(index: u16, type_: DataType, sequence: u64, data: u64) -> Vec<u8> {
+    let mut info_data = index.to_be_bytes().to_vec();
+    info_data.push(type_ as u8);
+    info_data.extend_from_slice(&sequence.to_be_bytes());
+    info_data.extend_from_slice(&data.to_be_bytes());
+    info_data
+}
+
+// The ring whose info cell of index i has the timestamp 1614828000 + i * 60
+fn build_ring(length: u16) -> Vec<Vec<u8>> {
+    (0..length)
+        .map(|index| {
+            build_v3_info_cell_data(
+                index,
+                DataType::Timestamp,
+                index as u64,
+                1614828000 + index as u64 * 60,
+            )
+        })
+        .collect()
+}
+
+fn build_proofs_data(infos_data: &[&[u8]], proofs: &[Vec<u8>]) -> Vec<u8> {
+    let mut proofs_data = Vec::new();
+    for (info_data, proof) in infos_data.iter().zip(proofs.iter()) {
+        proofs_data.extend_from_slice(&(info_data.len() as u16).to_be_bytes());
+        proofs_data.extend_from_slice(info_data);
+        proofs_data.extend_from_slice(proof);
+    }
+    proofs_data
+}
+
+#[test]
+fn test_merkle_depth() {
+    assert_eq!(merkle_depth(1), 0);
+    assert_eq!(merkle_depth(2), 1);
+    assert_eq!(merkle_depth(SUM_OF_INFO_CELLS as u16), 4);
+    assert_eq!(merkle_depth(1024), 10);
+    assert_eq!(merkle_depth(1025), 11);
+}
+
+#[test]
+fn test_info_proof_of_every_index() {
+    for length in [1u16, 12, 16, 100].iter() {
+        let ring = build_ring(*length);
+        let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+        let root = info_root(&infos_data);
+
+        for index in 0..*length {
+            let proof = info_proof(&infos_data, index);
+            assert_eq!(
+                root_from_proof(&ring[index as usize], index, *length, &proof),
+                Ok(root)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_info_root_of_single_info() {
+    let ring = build_ring(1);
+    let infos_data: Vec<&[u8]> = vec![&ring[0]];
+
+    assert_eq!(info_root(&infos_data), info_leaf(&ring[0]));
+}
+
+#[test]
+fn test_info_proof_after_update() {
+    // The siblings don't change when the leaf is replaced, so one proof leads to both roots
+    let mut ring = build_ring(12);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    let proof = info_proof(&infos_data, 5);
+
+    ring[5] = build_v3_info_cell_data(5, DataType::Timestamp, 17, 1614829080);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+
+    assert_eq!(
+        root_from_proof(&ring[5], 5, 12, &proof),
+        Ok(info_root(&infos_data))
+    );
+}
+
+#[test]
+fn test_info_proof_of_another_index() {
+    let ring = build_ring(12);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    let proof = info_proof(&infos_data, 5);
+
+    assert_ne!(
+        root_from_proof(&ring[5], 4, 12, &proof),
+        Ok(info_root(&infos_data))
+    );
+    assert_ne!(
+        root_from_proof(&ring[4], 5, 12, &proof),
+        Ok(info_root(&infos_data))
+    );
+}
+
+#[test]
+fn test_error_info_proof_len() {
+    let ring = build_ring(12);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    let proof = info_proof(&infos_data, 5);
+
+    assert_eq!(
+        root_from_proof(&ring[5], 5, 12, &proof[HASH_LEN..]),
+        Err(MerkleError::ProofLenError)
+    );
+    assert_eq!(
+        root_from_proof(&ring[5], 12, 12, &proof),
+        Err(MerkleError::ProofLenError)
+    );
+}
+
+#[test]
+fn test_parse_info_proofs() {
+    let ring = build_ring(12);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    let proven: Vec<&[u8]> = vec![&ring[11], &ring[10]];
+    let proofs = vec![info_proof(&infos_data, 11), info_proof(&infos_data, 10)];
+    let proofs_data = build_proofs_data(&proven, &proofs);
+
+    let info_proofs = parse_info_proofs(&proofs_data, 12).expect("parse proofs");
+    assert_eq!(info_proofs.len(), 2);
+    assert_eq!(info_proofs[0].info_data, &ring[11][..]);
+    assert_eq!(info_proofs[1].proof, &proofs[1][..]);
+}
+
+#[test]
+fn test_error_parse_info_proofs() {
+    let ring = build_ring(12);
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    let proofs_data = build_proofs_data(&[&ring[11]], &[info_proof(&infos_data, 11)]);
+
+    assert_eq!(
+        parse_info_proofs(&proofs_data[..proofs_data.len() - 1], 12),
+        Err(MerkleError::ProofLenError)
+    );
+    assert_eq!(
+        parse_info_proofs(&proofs_data[..1], 12),
+        Err(MerkleError::InfoDataLenError)
+    );
+    assert_eq!(
+        parse_info_proofs(&[], 12),
+        Err(MerkleError::InfoAmountError)
+    );
+}
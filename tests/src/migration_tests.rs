@@ -0,0 +1,215 @@
+use migration::{
+    check::{data_hash, ORACLE_BINARIES},
+    diff::MigrationDiff,
+    error::MigrationError,
+    network::Network,
+    Migration, INDEX_STATE_TYPE, INFO_TYPE,
+};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use ckb_tool::ckb_types::{core::DepType, core::ScriptHashType, packed::*, prelude::*, H256};
+
+const MAINNET_INDEX_STATE_TYPE_ID: &str =
+    "3a468d53352eb855521dabed0dc7036929bfe72766ad58f801edfbae564f7b43";
+const MAINNET_INFO_TYPE_ID: &str =
+    "9e537bf5b8ec044ca3f53355e879f3fd8832217e4a9b41d9994cf0c547241a79";
+const MAINNET_DEP_GROUP_TX_HASH: &str =
+    "73d85dde3923672f0b157f0ba119e891065ecdf6d07d024be6240b8da68600a5";
+
+fn migrations_dir() -> PathBuf {
+    env::current_dir().unwrap().join("..").join("migrations")
+}
+
+fn h256(hex_str: &str) -> H256 {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hex::decode(hex_str).unwrap());
+    H256(hash)
+}
+
+#[test]
+fn test_latest_migration() {
+    let path = Migration::latest_path(migrations_dir(), Network::Mainnet).unwrap();
+    assert_eq!(path.file_name().unwrap(), "2021-08-24-000000.json");
+    let path = Migration::latest_path(migrations_dir(), Network::Testnet).unwrap();
+    assert_eq!(path.file_name().unwrap(), "2021-08-09-103746.json");
+
+    for network in Network::ALL.iter() {
+        let migration = Migration::latest(migrations_dir(), *network).unwrap();
+        assert!(migration.index_state_type_code_hash().is_ok());
+        assert!(migration.info_type_code_hash().is_ok());
+        assert!(migration.oracle_cell_dep().is_ok());
+    }
+}
+
+#[test]
+fn test_mainnet_scripts() {
+    let migration = Migration::latest(migrations_dir(), Network::Mainnet).unwrap();
+    assert_eq!(
+        migration.index_state_type_code_hash(),
+        Ok(&h256(MAINNET_INDEX_STATE_TYPE_ID))
+    );
+    assert_eq!(
+        migration.info_type_code_hash(),
+        Ok(&h256(MAINNET_INFO_TYPE_ID))
+    );
+
+    let args = [7u8; 36];
+    let index_state_type_script = migration.index_state_type_script(&args).unwrap();
+    let info_type_script = migration.info_type_script(&args).unwrap();
+    assert_eq!(
+        index_state_type_script.code_hash(),
+        h256(MAINNET_INDEX_STATE_TYPE_ID).pack()
+    );
+    let hash_type: Byte = ScriptHashType::Type.into();
+    assert_eq!(index_state_type_script.hash_type(), hash_type);
+    assert_eq!(info_type_script.args(), index_state_type_script.args());
+    assert_eq!(info_type_script.args().raw_data().as_ref(), &args[..]);
+
+    let cell_dep = migration.oracle_cell_dep().unwrap();
+    let dep_type: Byte = DepType::DepGroup.into();
+    assert_eq!(cell_dep.dep_type(), dep_type);
+    assert_eq!(
+        cell_dep.out_point(),
+        OutPoint::new_builder()
+            .tx_hash(h256(MAINNET_DEP_GROUP_TX_HASH).pack())
+            .index(0u32.pack())
+            .build()
+    );
+    let cell_dep = migration.cell_dep(INFO_TYPE).unwrap();
+    let dep_type: Byte = DepType::Code.into();
+    assert_eq!(cell_dep.dep_type(), dep_type);
+}
+
+#[test]
+fn test_migration_errors() {
+    let migration = Migration::latest(migrations_dir(), Network::Mainnet).unwrap();
+    assert_eq!(
+        migration.cell_recipe("time-lock"),
+        Err(MigrationError::CellRecipeNotFound("time-lock".to_string()))
+    );
+
+    let mut migration = migration;
+    migration.cell_recipes[0].type_id = None;
+    assert_eq!(
+        migration.index_state_type_code_hash(),
+        Err(MigrationError::TypeIdNotFound(
+            "index-state-type".to_string()
+        ))
+    );
+    migration.dep_group_recipes.clear();
+    assert!(matches!(
+        migration.oracle_cell_dep(),
+        Err(MigrationError::DepGroupRecipeNotFound(_))
+    ));
+
+    // A network directory without any migration
+    let empty_dir = env::temp_dir().join("ckb-time-empty-migrations");
+    fs::create_dir_all(empty_dir.join(Network::Dev.dir_name())).unwrap();
+    assert_eq!(
+        Migration::latest(&empty_dir, Network::Dev),
+        Err(MigrationError::NoMigration(Network::Dev))
+    );
+    assert!(matches!(
+        Migration::latest(&empty_dir, Network::Mainnet),
+        Err(MigrationError::Io(_))
+    ));
+
+    assert_eq!("testnet".parse(), Ok(Network::Testnet));
+    assert!("devnet".parse::<Network>().is_err());
+}
+
+#[test]
+fn test_data_hash() {
+    // The ckb-blake2b hash of the empty data
+    assert_eq!(
+        data_hash(&[]),
+        h256("44f4c69744d5f8c55d642062949dcae49bc4e7ef43d388c5a12f42b5633d163e")
+    );
+}
+
+#[test]
+fn test_check_binaries() {
+    let binaries_dir = env::temp_dir().join("ckb-time-check-binaries");
+    fs::create_dir_all(&binaries_dir).unwrap();
+    for name in ORACLE_BINARIES.iter() {
+        fs::write(binaries_dir.join(name), name.as_bytes()).unwrap();
+    }
+
+    // Only the index-state-type binary is the recorded one
+    let mut migration = Migration::latest(migrations_dir(), Network::Mainnet).unwrap();
+    migration.cell_recipes[0].data_hash = data_hash(INDEX_STATE_TYPE.as_bytes());
+    let checks = migration.check_binaries(&binaries_dir).unwrap();
+    assert_eq!(checks.len(), 2);
+    assert_eq!(checks[0].name, INDEX_STATE_TYPE);
+    assert!(checks[0].is_matched());
+    assert_eq!(checks[1].name, INFO_TYPE);
+    assert!(!checks[1].is_matched());
+    assert_eq!(checks[1].data_hash, data_hash(INFO_TYPE.as_bytes()));
+    assert_eq!(
+        checks[1].recorded_data_hash,
+        migration.cell_recipes[1].data_hash
+    );
+
+    let result = migration.check_binaries(binaries_dir.join("missing"));
+    assert!(matches!(result, Err(MigrationError::Io(_))));
+}
+
+fn load_migration(network: Network, name: &str) -> Migration {
+    Migration::from_file(
+        migrations_dir()
+            .join(network.dir_name())
+            .join(format!("{}.json", name)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_diff_upgrade_with_type_ids() {
+    let old = load_migration(Network::Testnet, "2021-07-17-103929");
+    let new = load_migration(Network::Testnet, "2021-07-17-104039");
+    let diff = MigrationDiff::new(&old, &new);
+
+    assert_eq!(diff.cells.len(), 2);
+    assert_eq!(diff.cells[0].name, INDEX_STATE_TYPE);
+    assert!(diff.cells[0].is_data_changed());
+    assert_eq!(diff.cells[1].name, INFO_TYPE);
+    assert!(!diff.cells[1].is_data_changed());
+    assert!(diff.cells.iter().all(|cell| cell.is_type_id_stable()));
+    assert!(diff.cells.iter().all(|cell| cell.is_cell_moved()));
+    assert!(diff.dep_groups[0].is_rebuilt());
+    assert!(diff.keeps_type_ids());
+    assert!(!diff.is_dep_group_stale());
+}
+
+#[test]
+fn test_diff_redeployment() {
+    // The second mainnet deployment has new type ids and keeps the dep group of the first one
+    let old = load_migration(Network::Mainnet, "2021-07-19-135012");
+    let new = load_migration(Network::Mainnet, "2021-08-24-000000");
+    let diff = MigrationDiff::new(&old, &new);
+
+    assert!(diff.cells.iter().all(|cell| cell.is_data_changed()));
+    assert!(diff.cells.iter().all(|cell| !cell.is_type_id_stable()));
+    assert!(!diff.dep_groups[0].is_rebuilt());
+    assert!(!diff.keeps_type_ids());
+    assert!(diff.is_dep_group_stale());
+}
+
+#[test]
+fn test_diff_removed_recipe() {
+    let old = load_migration(Network::Mainnet, "2021-08-24-000000");
+    let mut new = old.clone();
+    new.cell_recipes.remove(0);
+    let diff = MigrationDiff::new(&old, &new);
+    assert_eq!(diff.cells[0].new, None);
+    assert!(!diff.keeps_type_ids());
+
+    // A new binary doesn't break the type ids of the old ones
+    let diff = MigrationDiff::new(&new, &old);
+    assert_eq!(diff.cells.len(), 2);
+    assert_eq!(diff.cells[1].old, None);
+    assert!(diff.keeps_type_ids());
+    assert!(!diff.is_dep_group_stale());
+}
@@ -0,0 +1,56 @@
+use common::script_error::{INDEX_STATE_TYPE_ERRORS, INFO_TYPE_ERRORS};
+use std::fs;
+use std::path::Path;
+
+// The exit codes and the names of the variants of the Error enum of a contract, which counts from
+// the last explicit discriminant like rustc does
+fn contract_errors(contract: &str) -> Vec<(i8, String)> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../contracts")
+        .join(contract)
+        .join("src/error.rs");
+    let source = fs::read_to_string(path).expect("read error.rs");
+    let start = source.find("pub enum Error {").expect("Error enum") + "pub enum Error {".len();
+    let end = start + source[start..].find('}').expect("end of Error enum");
+
+    let mut code = 0i8;
+    let mut errors = Vec::new();
+    for variant in source[start..end].split(',') {
+        let variant = variant.trim();
+        if variant.is_empty() {
+            continue;
+        }
+        let name = match variant.find('=') {
+            Some(pos) => {
+                code = variant[pos + 1..].trim().parse().expect("discriminant");
+                variant[..pos].trim()
+            }
+            None => {
+                code += 1;
+                variant
+            }
+        };
+        errors.push((code, name.to_string()));
+    }
+    errors
+}
+
+fn table(errors: &[(i8, &str)]) -> Vec<(i8, String)> {
+    errors
+        .iter()
+        .map(|(code, name)| (*code, name.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_index_state_type_errors_follow_contract() {
+    assert_eq!(
+        table(INDEX_STATE_TYPE_ERRORS),
+        contract_errors("index-state-type")
+    );
+}
+
+#[test]
+fn test_info_type_errors_follow_contract() {
+    assert_eq!(table(INFO_TYPE_ERRORS), contract_errors("info-type"));
+}
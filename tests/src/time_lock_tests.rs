@@ -0,0 +1,531 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::{BufMut, Bytes, BytesMut},
+    core::{TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use common::constants::*;
+
+const MAX_CYCLES: u64 = 10_000_000;
+
+const MAX_AGE: u64 = 3600;
+
+// error numbers
+const INVALID_ARGUMENT: i8 = 5;
+const OWNER_NOT_EXIST: i8 = 6;
+const INFO_NOT_EXIST: i8 = 8;
+const INVALID_ORACLE_DATA: i8 = 9;
+const INVALID_DATA_TYPE: i8 = 10;
+const TIMESTAMP_NOT_REACHED: i8 = 11;
+const INVALID_SINCE: i8 = 12;
+const ORACLE_NOT_FRESH: i8 = 13;
+
+fn build_time_lock_args(
+    owner_lock_hash: &Byte32,
+    index_state_type_hash: &Byte32,
+    info_type_code_hash: &Byte32,
+    unlock_timestamp: u64,
+) -> Bytes {
+    let mut args_buf = BytesMut::with_capacity(32 * 3 + 8 * 2);
+    args_buf.put_slice(owner_lock_hash.as_slice());
+    args_buf.put_slice(index_state_type_hash.as_slice());
+    args_buf.put_slice(info_type_code_hash.as_slice());
+    args_buf.put_u64(unlock_timestamp);
+    args_buf.put_u64(MAX_AGE);
+    Bytes::from(args_buf.to_vec())
+}
+
+fn create_test_context(
+    index_state_data: Bytes,
+    infos_data: &Vec<Bytes>,
+    unlock_timestamp: u64,
+    owner_exists: bool,
+    is_info_args_error: bool,
+) -> (Context, TransactionView) {
+    // deploy contract
+    let mut context = Context::default();
+    let time_lock_bin: Bytes = Loader::default().load_binary("time-lock");
+    let time_lock_out_point = context.deploy_cell(time_lock_bin);
+
+    // deploy always_success script
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    // prepare scripts
+    let lock_script = context
+        .build_script(&always_success_out_point, Default::default())
+        .expect("script");
+    let lock_script_dep = CellDep::new_builder()
+        .out_point(always_success_out_point.clone())
+        .build();
+    let owner_lock_script = context
+        .build_script(&always_success_out_point, Bytes::from(vec![1]))
+        .expect("script");
+
+    // prepare oracle cells which are cell deps of the transaction
+    let info_args = if is_info_args_error {
+        Some(Bytes::from(vec![3; 36]))
+    } else {
+        None
+    };
+    let oracle = create_oracle_cell_deps(
+        &mut context,
+        &lock_script,
+        index_state_data,
+        infos_data,
+        info_args,
+    );
+
+    let time_lock_script = context
+        .build_script(
+            &time_lock_out_point,
+            build_time_lock_args(
+                &owner_lock_script.calc_script_hash(),
+                &oracle.index_state_type_script.calc_script_hash(),
+                &oracle.info_type_script.code_hash(),
+                unlock_timestamp,
+            ),
+        )
+        .expect("script");
+    let time_lock_script_dep = CellDep::new_builder()
+        .out_point(time_lock_out_point)
+        .build();
+
+    // prepare cells
+    let time_lock_input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(time_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    // The time lock input is unlocked at the unlock timestamp
+    let mut inputs = vec![CellInput::new_builder()
+        .previous_output(time_lock_input_out_point)
+        .since((SINCE_TIMESTAMP_BASE + unlock_timestamp).pack())
+        .build()];
+    let mut witnesses = vec![Bytes::new()];
+    if owner_exists {
+        let owner_input_out_point = context.create_cell(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(owner_lock_script.clone())
+                .build(),
+            Bytes::new(),
+        );
+        inputs.push(
+            CellInput::new_builder()
+                .previous_output(owner_input_out_point)
+                .build(),
+        );
+        witnesses.push(Bytes::new());
+    }
+
+    let outputs = vec![CellOutput::new_builder()
+        .capacity(1500u64.pack())
+        .lock(owner_lock_script)
+        .build()];
+
+    // build transaction
+    let tx = TransactionBuilder::default()
+        .inputs(inputs)
+        .outputs(outputs)
+        .outputs_data(vec![Bytes::new()].pack())
+        .cell_dep(lock_script_dep)
+        .cell_dep(time_lock_script_dep)
+        .cell_deps(oracle.cell_deps)
+        .witnesses(witnesses.pack())
+        .build();
+    (context, tx)
+}
+
+#[test]
+fn test_unlock_time_lock_success() {
+    let infos_data = vec![
+        build_legacy_info_cell_data(2, DataType::Timestamp, 1614828683),
+        build_legacy_info_cell_data(3, DataType::Timestamp, 1614829080),
+    ];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_unlock_time_lock_with_timestamp_millis_success() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::TimestampMillis,
+        1614829080456,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_error_time_lock_timestamp_not_reached() {
+    // the info cell of index 3 is the latest one, though the other one has a bigger timestamp
+    let infos_data = vec![
+        build_legacy_info_cell_data(3, DataType::Timestamp, 1614829079),
+        build_legacy_info_cell_data(2, DataType::Timestamp, 1614829999),
+    ];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(TIMESTAMP_NOT_REACHED).input_lock_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_time_lock_owner_not_exist() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::Timestamp,
+        1614829080,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        false,
+        false,
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(OWNER_NOT_EXIST).input_lock_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_time_lock_info_of_another_oracle() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::Timestamp,
+        1614829080,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        true,
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INFO_NOT_EXIST).input_lock_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_time_lock_latest_info_not_exist() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        2,
+        DataType::Timestamp,
+        1614829080,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INFO_NOT_EXIST).input_lock_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_time_lock_block_number_oracle() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::BlockNumber,
+        1614829080,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INVALID_DATA_TYPE).input_lock_script(script_cell_index)
+    );
+}
+
+// The time lock input is the first input
+fn set_time_lock_input_since(tx: TransactionView, since: u64) -> TransactionView {
+    let mut inputs: Vec<CellInput> = tx.inputs().into_iter().collect();
+    inputs[0] = inputs[0].clone().as_builder().since(since.pack()).build();
+    tx.as_advanced_builder().set_inputs(inputs).build()
+}
+
+// The oracle has reached the unlock timestamp, but it stopped long before the since
+#[test]
+fn test_error_time_lock_stale_oracle() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::Timestamp,
+        1614829080,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+    let tx = set_time_lock_input_since(tx, SINCE_TIMESTAMP_BASE + 1614829080 + MAX_AGE + 1);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(ORACLE_NOT_FRESH).input_lock_script(script_cell_index)
+    );
+}
+
+// The since of the time lock input is the reference, and it must be an absolute timestamp
+#[test]
+fn test_error_time_lock_without_since() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::Timestamp,
+        1614829080,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+    let tx = set_time_lock_input_since(tx, 0);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INVALID_SINCE).input_lock_script(script_cell_index)
+    );
+}
+
+// Puts a copy of the oracle cell dep at the position in front of the genuine cell deps, with the
+// same type script and the data, like a forged oracle cell whose creation isn't checked
+fn prepend_forged_cell_dep(
+    context: &mut Context,
+    tx: TransactionView,
+    pos: usize,
+    data: Bytes,
+) -> TransactionView {
+    let cell_dep = tx.cell_deps().get(pos).expect("cell dep");
+    let (output, _) = context.get_cell(&cell_dep.out_point()).expect("get cell");
+    let forged_out_point = context.create_cell(output, data);
+    let mut cell_deps = vec![CellDep::new_builder().out_point(forged_out_point).build()];
+    cell_deps.extend(tx.cell_deps().into_iter());
+    tx.as_advanced_builder().set_cell_deps(cell_deps).build()
+}
+
+#[test]
+fn test_error_time_lock_forged_oracle() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::Timestamp,
+        1614829079,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+    // a second oracle with the same type scripts and a far future timestamp, in front of the genuine one
+    let tx = prepend_forged_cell_dep(
+        &mut context,
+        tx,
+        3,
+        build_legacy_info_cell_data(0, DataType::Timestamp, 1914829080),
+    );
+    let tx = prepend_forged_cell_dep(&mut context, tx, 3, build_legacy_index_state_cell_data(0));
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INVALID_ORACLE_DATA).input_lock_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_time_lock_forged_latest_info() {
+    let infos_data = vec![build_legacy_info_cell_data(
+        3,
+        DataType::Timestamp,
+        1614829079,
+    )];
+    let (mut context, tx) = create_test_context(
+        build_legacy_index_state_cell_data(3),
+        &infos_data,
+        1614829080,
+        true,
+        false,
+    );
+    let tx = prepend_forged_cell_dep(
+        &mut context,
+        tx,
+        3,
+        build_legacy_info_cell_data(3, DataType::Timestamp, 1914829080),
+    );
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INVALID_ORACLE_DATA).input_lock_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_error_time_lock_args() {
+    let mut context = Context::default();
+    let time_lock_bin: Bytes = Loader::default().load_binary("time-lock");
+    let time_lock_out_point = context.deploy_cell(time_lock_bin);
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    let lock_script = context
+        .build_script(&always_success_out_point, Default::default())
+        .expect("script");
+    let time_lock_script = context
+        .build_script(&time_lock_out_point, Bytes::from(vec![0; 32]))
+        .expect("script");
+
+    let time_lock_input_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(1000u64.pack())
+            .lock(time_lock_script)
+            .build(),
+        Bytes::new(),
+    );
+    let tx = TransactionBuilder::default()
+        .input(
+            CellInput::new_builder()
+                .previous_output(time_lock_input_out_point)
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .capacity(1000u64.pack())
+                .lock(lock_script)
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(always_success_out_point)
+                .build(),
+        )
+        .cell_dep(
+            CellDep::new_builder()
+                .out_point(time_lock_out_point)
+                .build(),
+        )
+        .witness(Bytes::new().pack())
+        .build();
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(INVALID_ARGUMENT).input_lock_script(script_cell_index)
+    );
+}
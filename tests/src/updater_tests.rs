@@ -0,0 +1,998 @@
+use super::Loader;
+use cli::genesis::{build_genesis_tx, FundingCell, GenesisOptions};
+use common::{
+    constants::*,
+    history::extend_history_root,
+    index_state::IndexState,
+    info::InfoLayout,
+    merkle::*,
+    script_error::{error_name, INDEX_STATE_TYPE_ERRORS, INFO_TYPE_ERRORS},
+};
+use migration::{network::Network, CellRecipe, DepGroupRecipe, Migration, ORACLE_DEP_GROUP};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use updater::{
+    config::{Config, InstanceConfig, MigrationConfig, RetryConfig},
+    error::UpdaterError,
+    funding::FundingCells,
+    mock::{MockChain, MockRpcServer},
+    plan::{plan_update, OracleKind},
+    scheduler::{Scheduler, StopSignal, UpdateResult},
+    tx::{build_update_tx, funding_capacity, LiveCell},
+    updater::Updater,
+    verify::{validation_failure_code, LocalVerifier, VerifyError},
+};
+
+use ckb_jsonrpc_types as json;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, ScriptHashType},
+    packed::*,
+    prelude::*,
+    H256,
+};
+
+// error numbers of info-type and index-state-type
+const INVALID_TIME_INFO_SINCE: i8 = 12;
+const INDEX_STATE_SEQUENCE_ERROR: i8 = 12;
+
+const FIRST_TIMESTAMP: u64 = 1614828000;
+const FEE: u64 = 10000;
+const FIRST_BLOCK_NUMBER: u64 = 80;
+
+fn build_legacy_ring(length: u16) -> Vec<Vec<u8>> {
+    (0..length)
+        .map(|index| {
+            let timestamp = FIRST_TIMESTAMP + index as u64 * 60;
+            InfoLayout::LEGACY.build(
+                index,
+                DataType::Timestamp as u8,
+                None,
+                &timestamp.to_be_bytes(),
+            )
+        })
+        .collect()
+}
+
+// The v3 info cells of a ring whose latest index is length - 1, so info cell i has the sequence i
+fn build_v3_ring(length: u16) -> Vec<Vec<u8>> {
+    (0..length)
+        .map(|index| {
+            let timestamp = FIRST_TIMESTAMP + index as u64 * 60;
+            InfoLayout::V3.build(
+                index,
+                DataType::Timestamp as u8,
+                Some(index as u64),
+                &timestamp.to_be_bytes(),
+            )
+        })
+        .collect()
+}
+
+// The v3 info cells of a block number ring whose latest value is below the tip of the mock chain
+fn build_block_number_ring(length: u16) -> Vec<Vec<u8>> {
+    (0..length)
+        .map(|index| {
+            InfoLayout::V3.build(
+                index,
+                DataType::BlockNumber as u8,
+                Some(index as u64),
+                &(FIRST_BLOCK_NUMBER + index as u64).to_be_bytes(),
+            )
+        })
+        .collect()
+}
+
+fn build_v4_index_state(ring: &[Vec<u8>]) -> IndexState {
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    IndexState {
+        version: INDEX_STATE_VERSION_INFO_ROOT,
+        index: ring.len() as u16 - 1,
+        length: ring.len() as u16,
+        history_root: Some([1u8; HASH_LEN]),
+        sequence: Some(ring.len() as u64 - 1),
+        info_root: Some(info_root(&infos_data)),
+    }
+}
+
+#[test]
+fn test_plan_legacy_update() {
+    let ring = build_legacy_ring(12);
+    let index_state_data = vec![3u8, 12];
+    let plan = plan_update(&index_state_data, &ring, OracleKind::Timestamp, 1614829080).unwrap();
+
+    assert_eq!(plan.info_position, 4);
+    assert_eq!(plan.index_state_data, vec![4u8, 12]);
+    assert_eq!(
+        plan.info_data,
+        InfoLayout::LEGACY.build(
+            4,
+            DataType::Timestamp as u8,
+            None,
+            &1614829080u64.to_be_bytes()
+        )
+    );
+    assert_eq!(plan.since, SINCE_TIMESTAMP_BASE + 1614829080);
+    assert_eq!(plan.info_proof, None);
+}
+
+#[test]
+fn test_plan_block_number_since() {
+    let ring: Vec<Vec<u8>> = (0..12u16)
+        .map(|index| {
+            let block_number = 1000 + index as u64;
+            InfoLayout::LEGACY.build(
+                index,
+                DataType::BlockNumber as u8,
+                None,
+                &block_number.to_be_bytes(),
+            )
+        })
+        .collect();
+    let plan = plan_update(&[11u8, 12], &ring, OracleKind::BlockNumber, 1020).unwrap();
+
+    assert_eq!(plan.info_position, 0);
+    assert_eq!(plan.index_state_data, vec![0u8, 12]);
+    assert_eq!(plan.since, 1020);
+}
+
+#[test]
+fn test_plan_v4_update() {
+    let ring = build_v3_ring(12);
+    let index_state = build_v4_index_state(&ring);
+    let value = FIRST_TIMESTAMP + 12 * 60;
+    let plan = plan_update(&index_state.to_vec(), &ring, OracleKind::Timestamp, value).unwrap();
+
+    let info_data =
+        InfoLayout::V3.build(0, DataType::Timestamp as u8, Some(12), &value.to_be_bytes());
+    assert_eq!(plan.info_position, 0);
+    assert_eq!(plan.info_data, info_data);
+
+    let next_index_state = IndexState::from_slice(&plan.index_state_data).unwrap();
+    assert_eq!(next_index_state.index, 0);
+    assert_eq!(next_index_state.sequence, Some(12));
+    assert_eq!(
+        next_index_state.history_root,
+        Some(extend_history_root(&[1u8; HASH_LEN], &info_data))
+    );
+
+    // The same proof leads to the input root with the input info data and to the output root
+    let proof = plan.info_proof.unwrap();
+    assert_eq!(
+        root_from_proof(&ring[0], 0, 12, &proof),
+        Ok(index_state.info_root.unwrap())
+    );
+    assert_eq!(
+        root_from_proof(&info_data, 0, 12, &proof),
+        Ok(next_index_state.info_root.unwrap())
+    );
+}
+
+#[test]
+fn test_plan_value_not_newer() {
+    let ring = build_v3_ring(12);
+    let index_state = build_v4_index_state(&ring);
+    let latest = FIRST_TIMESTAMP + 11 * 60;
+    assert_eq!(
+        plan_update(&index_state.to_vec(), &ring, OracleKind::Timestamp, latest),
+        Err(UpdaterError::ValueNotNewer)
+    );
+}
+
+#[test]
+fn test_plan_data_type_mismatch() {
+    let ring = build_legacy_ring(12);
+    assert_eq!(
+        plan_update(&[3u8, 12], &ring, OracleKind::BlockNumber, 1614829080),
+        Err(UpdaterError::DataTypeMismatch)
+    );
+}
+
+#[test]
+fn test_plan_next_info_not_found() {
+    let mut ring = build_legacy_ring(12);
+    ring.remove(4);
+    assert_eq!(
+        plan_update(&[3u8, 12], &ring, OracleKind::Timestamp, 1614829080),
+        Err(UpdaterError::InfoNotFound(4))
+    );
+}
+
+fn build_script(code_hash: [u8; 32], args: &[u8]) -> Script {
+    Script::new_builder()
+        .code_hash(code_hash.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(Bytes::copy_from_slice(args).pack())
+        .build()
+}
+
+// The lock of all the cells of the mock chain
+fn build_lock() -> Script {
+    build_script([4u8; 32], &[5u8; 20])
+}
+
+fn build_cell_output(type_script: &Script) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(Capacity::bytes(1000).unwrap().pack())
+        .lock(build_lock())
+        .type_(Some(type_script.clone()).pack())
+        .build()
+}
+
+// A funding cell which pays the fees of the updates, see updater::funding::FundingCells
+fn push_funding_cell(chain: &mut MockChain) -> OutPoint {
+    chain.push_cell(
+        CellOutput::new_builder()
+            .capacity(Capacity::bytes(1000).unwrap().pack())
+            .lock(build_lock())
+            .build(),
+        Bytes::new(),
+    )
+}
+
+// The args of a mock oracle pin the info type code hash of the migration, see index-state-type
+fn build_oracle_args(migration: &Migration, out_point: u8) -> Vec<u8> {
+    migration.oracle_args(&[out_point; OUT_POINT_LEN]).unwrap()
+}
+
+// The oracle scripts of a mock chain, whose binaries are deployed in a dep group
+struct MockOracle {
+    migration: Migration,
+    index_state_type_script: Script,
+    info_type_script: Script,
+    cell_dep: CellDep,
+}
+
+fn deploy_binary(chain: &mut MockChain, name: &str) -> CellRecipe {
+    // The type script works like the type id of the deployed cell
+    let type_script = build_script([9u8; 32], name.as_bytes());
+    let data = Loader::default().load_binary(name);
+    let out_point = chain.push_cell(build_cell_output(&type_script), data.clone());
+    CellRecipe {
+        name: name.to_string(),
+        tx_hash: out_point.tx_hash().unpack(),
+        index: out_point.index().unpack(),
+        occupied_capacity: 0,
+        data_hash: CellOutput::calc_data_hash(&data).unpack(),
+        type_id: Some(type_script.calc_script_hash().unpack()),
+    }
+}
+
+// A mock chain with a version 4 ring of 12 timestamp info cells, whose latest value is one minute
+// before its median time, and two funding cells
+fn build_mock_chain() -> (MockChain, MockOracle) {
+    let mut chain = MockChain::new(100, (FIRST_TIMESTAMP + 12 * 60) * 1000);
+    let index_state_recipe = deploy_binary(&mut chain, "index-state-type");
+    let info_recipe = deploy_binary(&mut chain, "info-type");
+    let dep_group_data = OutPointVec::new_builder()
+        .push(index_state_recipe.out_point())
+        .push(info_recipe.out_point())
+        .build();
+    let dep_group_out_point = chain.push_cell(
+        build_cell_output(&build_script([9u8; 32], b"dep-group")),
+        dep_group_data.as_bytes(),
+    );
+    let migration = Migration {
+        cell_recipes: vec![index_state_recipe, info_recipe],
+        dep_group_recipes: vec![DepGroupRecipe {
+            name: ORACLE_DEP_GROUP.to_string(),
+            tx_hash: dep_group_out_point.tx_hash().unpack(),
+            index: dep_group_out_point.index().unpack(),
+            occupied_capacity: 0,
+        }],
+    };
+
+    let args = build_oracle_args(&migration, 2);
+    let index_state_type_script = migration.index_state_type_script(&args).unwrap();
+    let info_type_script = migration.info_type_script(&args).unwrap();
+    let ring = build_v3_ring(12);
+    chain.push_cell(
+        build_cell_output(&index_state_type_script),
+        Bytes::from(build_v4_index_state(&ring).to_vec()),
+    );
+    for info_data in ring {
+        chain.push_cell(build_cell_output(&info_type_script), Bytes::from(info_data));
+    }
+    push_funding_cell(&mut chain);
+    push_funding_cell(&mut chain);
+
+    let cell_dep = migration.oracle_cell_dep().unwrap();
+    (
+        chain,
+        MockOracle {
+            migration,
+            index_state_type_script,
+            info_type_script,
+            cell_dep,
+        },
+    )
+}
+
+// Another oracle of the mock oracle scripts, a version 4 ring of 12 block number info cells
+fn push_block_number_oracle(chain: &mut MockChain, oracle: &MockOracle) -> (Script, Script) {
+    let args = build_oracle_args(&oracle.migration, 3);
+    let index_state_type_script = oracle.migration.index_state_type_script(&args).unwrap();
+    let info_type_script = oracle.migration.info_type_script(&args).unwrap();
+    let ring = build_block_number_ring(12);
+    chain.push_cell(
+        build_cell_output(&index_state_type_script),
+        Bytes::from(build_v4_index_state(&ring).to_vec()),
+    );
+    for info_data in ring {
+        chain.push_cell(build_cell_output(&info_type_script), Bytes::from(info_data));
+    }
+    (index_state_type_script, info_type_script)
+}
+
+// The migration of the mock oracle is the only one of the dev network in the migrations dir
+fn write_migration(name: &str, migration: &Migration) -> PathBuf {
+    let migrations_dir = env::temp_dir().join(format!("ckb-time-updater-{}-migrations", name));
+    let dir = migrations_dir.join(Network::Dev.dir_name());
+    fs::create_dir_all(&dir).expect("create migrations dir");
+    fs::write(
+        dir.join("2021-07-14-000000.json"),
+        serde_json::to_string_pretty(migration).unwrap(),
+    )
+    .expect("write migration");
+    migrations_dir
+}
+
+fn build_instance(
+    name: &str,
+    args: Vec<u8>,
+    kind: OracleKind,
+    interval_secs: u64,
+) -> InstanceConfig {
+    let private_key_path = env::temp_dir().join(format!("ckb-time-updater-{}.key", name));
+    fs::write(&private_key_path, hex::encode([1u8; 32])).expect("write private key");
+    InstanceConfig {
+        name: name.to_string(),
+        args: json::JsonBytes::from_vec(args),
+        kind,
+        private_key_path,
+        fee: FEE,
+        interval_secs,
+    }
+}
+
+fn build_config(name: &str, url: &str, oracle: &MockOracle) -> Config {
+    build_instances_config(
+        name,
+        url,
+        oracle,
+        vec![build_instance(
+            name,
+            build_oracle_args(&oracle.migration, 2),
+            OracleKind::Timestamp,
+            60,
+        )],
+    )
+}
+
+fn build_instances_config(
+    name: &str,
+    url: &str,
+    oracle: &MockOracle,
+    instances: Vec<InstanceConfig>,
+) -> Config {
+    Config {
+        ckb_rpc_url: url.to_string(),
+        indexer_rpc_url: url.to_string(),
+        migration: MigrationConfig {
+            dir: write_migration(name, &oracle.migration),
+            network: Network::Dev,
+        },
+        cell_deps: vec![],
+        retry: RetryConfig {
+            max_retries: 2,
+            backoff_millis: 10,
+        },
+        instances,
+    }
+}
+
+fn live_data(chain: &MockChain, type_script: &Script) -> Vec<Vec<u8>> {
+    let type_script: json::Script = type_script.clone().into();
+    chain
+        .cells
+        .iter()
+        .filter(|cell| cell.output.type_.as_ref() == Some(&type_script))
+        .map(|cell| cell.output_data.as_bytes().to_vec())
+        .collect()
+}
+
+// Commits the genesis transaction of a new version 4 oracle of 12 timestamp info cells to the mock
+// chain, and returns the args of its type scripts
+fn commit_genesis(chain: &mut MockChain, oracle: &MockOracle) -> Vec<u8> {
+    let funding_output = CellOutput::new_builder()
+        .capacity(Capacity::bytes(10_000).unwrap().pack())
+        .lock(build_lock())
+        .build();
+    let funding_out_point = chain.push_cell(funding_output.clone(), Bytes::new());
+    let funding = FundingCell {
+        out_point: funding_out_point.into(),
+        output: funding_output.into(),
+        cell_deps: vec![],
+    };
+    let options = GenesisOptions {
+        version: INDEX_STATE_VERSION_INFO_ROOT,
+        length: 12,
+        data_type: DataType::Timestamp,
+        content: FIRST_TIMESTAMP.to_be_bytes().to_vec(),
+        fee: 100_000,
+    };
+    let tx = build_genesis_tx(&oracle.migration, &funding, &options).unwrap();
+    chain.commit(tx.data().into()).unwrap();
+    tx.output(0)
+        .unwrap()
+        .type_()
+        .to_opt()
+        .unwrap()
+        .args()
+        .raw_data()
+        .to_vec()
+}
+
+// The fee is paid by a funding cell, so a new ring is updated without any capacity over the occupied
+// capacity of its info cells
+#[test]
+fn test_update_after_genesis() {
+    let (mut chain, oracle) = build_mock_chain();
+    let args = commit_genesis(&mut chain, &oracle);
+    let server = MockRpcServer::start(chain).unwrap();
+    let config = build_instances_config(
+        "genesis",
+        server.url(),
+        &oracle,
+        vec![build_instance("genesis", args, OracleKind::Timestamp, 60)],
+    );
+
+    let updater = Updater::new(&config, config.instances[0].clone()).unwrap();
+    updater.update_once().unwrap().expect("update transaction");
+    let chain = server.chain();
+    let tx = Transaction::from(chain.sent_transactions.last().unwrap().clone()).into_view();
+    let info_output = tx.outputs().get(1).unwrap();
+    let info_capacity: Capacity = info_output.capacity().unpack();
+    assert_eq!(
+        info_capacity,
+        info_output
+            .occupied_capacity(Capacity::bytes(tx.outputs_data().get(1).unwrap().len()).unwrap())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_update_with_mock_rpc_server() {
+    let (chain, oracle) = build_mock_chain();
+    let server = MockRpcServer::start(chain).unwrap();
+    let config = build_config("update", server.url(), &oracle);
+    let updater = Updater::new(&config, config.instances[0].clone()).unwrap();
+
+    let tx_hash = updater.update_once().unwrap().expect("update transaction");
+    {
+        let chain = server.chain();
+        assert_eq!(chain.sent_transactions.len(), 1);
+        let tx = &chain.sent_transactions[0];
+        let tx_view = Transaction::from(tx.clone()).into_view();
+        let expected_hash: H256 = tx_view.hash().unpack();
+        assert_eq!(tx_hash, expected_hash);
+        let since: u64 = tx_view.inputs().get(1).unwrap().since().unpack();
+        assert_eq!(since, SINCE_TIMESTAMP_BASE + FIRST_TIMESTAMP + 12 * 60);
+
+        // The signature is in the lock of the first witness and the proof in the second one, and
+        // the funding cell is signed with them
+        assert_eq!(tx_view.witnesses().len(), 3);
+        let lock_witness =
+            WitnessArgs::from_slice(&tx_view.witnesses().get(0).unwrap().raw_data()).unwrap();
+        assert_eq!(lock_witness.lock().to_opt().unwrap().raw_data().len(), 65);
+        let info_witness =
+            WitnessArgs::from_slice(&tx_view.witnesses().get(1).unwrap().raw_data()).unwrap();
+        assert_eq!(
+            info_witness.input_type().to_opt().unwrap().raw_data().len(),
+            HASH_LEN * merkle_depth(12)
+        );
+
+        // The info cell keeps its capacity, and the funding cell pays the fee
+        let info_output = tx_view.outputs().get(1).unwrap();
+        let info_capacity: Capacity = info_output.capacity().unpack();
+        assert_eq!(info_capacity, Capacity::bytes(1000).unwrap());
+        let change_output = tx_view.outputs().get(2).unwrap();
+        assert_eq!(change_output.lock(), build_lock());
+        assert!(change_output.type_().is_none());
+        let change_capacity: Capacity = change_output.capacity().unpack();
+        assert_eq!(
+            change_capacity.as_u64(),
+            Capacity::bytes(1000).unwrap().as_u64() - FEE
+        );
+
+        let index_state_data = live_data(&chain, &oracle.index_state_type_script);
+        let index_state = IndexState::from_slice(&index_state_data[0]).unwrap();
+        assert_eq!(index_state.index, 0);
+        assert_eq!(index_state.sequence, Some(12));
+    }
+
+    // The median time hasn't changed, so there is nothing to update
+    assert_eq!(updater.update_once(), Ok(None));
+
+    server.chain().median_time += 60 * 1000;
+    updater.update_once().unwrap().expect("update transaction");
+    let chain = server.chain();
+    assert_eq!(chain.sent_transactions.len(), 2);
+
+    let index_state_data = live_data(&chain, &oracle.index_state_type_script);
+    let index_state = IndexState::from_slice(&index_state_data[0]).unwrap();
+    assert_eq!(index_state.index, 1);
+    assert_eq!(index_state.sequence, Some(13));
+
+    let mut ring = live_data(&chain, &oracle.info_type_script);
+    ring.sort_by_key(|info_data| InfoLayout::V3.index(info_data));
+    let infos_data: Vec<&[u8]> = ring.iter().map(|data| data.as_slice()).collect();
+    assert_eq!(index_state.info_root, Some(info_root(&infos_data)));
+    assert_eq!(
+        InfoLayout::V3.content(&ring[1]),
+        &(FIRST_TIMESTAMP + 13 * 60).to_be_bytes()[..]
+    );
+}
+
+#[test]
+fn test_update_without_index_state_cell() {
+    let (_, oracle) = build_mock_chain();
+    let server = MockRpcServer::start(MockChain::new(100, FIRST_TIMESTAMP * 1000)).unwrap();
+    let config = build_config("no-index-state", server.url(), &oracle);
+    let updater = Updater::new(&config, config.instances[0].clone()).unwrap();
+
+    assert_eq!(updater.update_once(), Err(UpdaterError::IndexStateNotFound));
+    assert!(server.chain().sent_transactions.is_empty());
+}
+
+// The results of the instances until the one of the name is an update transaction
+fn recv_until_update(results: &Receiver<UpdateResult>, name: &str) -> Vec<UpdateResult> {
+    let mut received = Vec::new();
+    loop {
+        let result = results
+            .recv_timeout(Duration::from_secs(10))
+            .expect("update result");
+        let is_update = result.0 == name && matches!(result.1, Ok(Some(_)));
+        received.push(result);
+        if is_update {
+            return received;
+        }
+    }
+}
+
+#[test]
+fn test_update_instances() {
+    let (mut chain, oracle) = build_mock_chain();
+    let (index_state_type_script, info_type_script) = push_block_number_oracle(&mut chain, &oracle);
+    let server = MockRpcServer::start(chain).unwrap();
+    let config = build_instances_config(
+        "instances",
+        server.url(),
+        &oracle,
+        vec![
+            build_instance(
+                "instances-timestamp",
+                build_oracle_args(&oracle.migration, 2),
+                OracleKind::Timestamp,
+                1,
+            ),
+            build_instance(
+                "instances-block",
+                build_oracle_args(&oracle.migration, 3),
+                OracleKind::BlockNumber,
+                600,
+            ),
+        ],
+    );
+    let stop = StopSignal::default();
+    let results = Scheduler::new(&config).unwrap().spawn(&stop);
+
+    // Every instance is updated at once, and spends the cells of its own oracle only
+    let mut first_results = vec![
+        results.recv_timeout(Duration::from_secs(10)).unwrap(),
+        results.recv_timeout(Duration::from_secs(10)).unwrap(),
+    ];
+    first_results.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(first_results[0].0, "instances-block");
+    assert_eq!(first_results[1].0, "instances-timestamp");
+    assert!(first_results
+        .iter()
+        .all(|(_, result)| matches!(result, Ok(Some(_)))));
+    {
+        let chain = server.chain();
+        assert_eq!(chain.sent_transactions.len(), 2);
+        // The oracles have the same lock, and their updates spend different funding cells
+        let funding_out_points: Vec<OutPoint> = chain
+            .sent_transactions
+            .iter()
+            .map(|tx| {
+                let tx = Transaction::from(tx.clone()).into_view();
+                tx.inputs().get(2).unwrap().previous_output()
+            })
+            .collect();
+        assert_ne!(funding_out_points[0], funding_out_points[1]);
+
+        let index_state_data = live_data(&chain, &oracle.index_state_type_script);
+        let index_state = IndexState::from_slice(&index_state_data[0]).unwrap();
+        assert_eq!(index_state.index, 0);
+        assert_eq!(index_state.sequence, Some(12));
+
+        let index_state_data = live_data(&chain, &index_state_type_script);
+        let index_state = IndexState::from_slice(&index_state_data[0]).unwrap();
+        assert_eq!(index_state.index, 0);
+        assert_eq!(index_state.sequence, Some(12));
+        let mut ring = live_data(&chain, &info_type_script);
+        ring.sort_by_key(|info_data| InfoLayout::V3.index(info_data));
+        assert_eq!(
+            InfoLayout::V3.data_type(&ring[0]),
+            DataType::BlockNumber as u8
+        );
+        // The instances are updated on their own threads, so the tip is read before or after the
+        // transaction of the timestamp instance is committed
+        let content = InfoLayout::V3.content(&ring[0]);
+        assert!(content == &100u64.to_be_bytes()[..] || content == &101u64.to_be_bytes()[..]);
+    }
+
+    // The timestamp instance loops every second, and only sends an update after the median time
+    // moves, while the block number instance waits for its interval
+    server.chain().median_time += 60 * 1000;
+    let received = recv_until_update(&results, "instances-timestamp");
+    assert!(received
+        .iter()
+        .all(|(name, result)| name == "instances-timestamp" && matches!(result, Ok(_))));
+    assert_eq!(server.chain().sent_transactions.len(), 3);
+
+    // The threads stop in the middle of their intervals, and the results are closed then
+    stop.stop();
+    for (name, result) in results.iter() {
+        assert_eq!(name, "instances-timestamp");
+        assert_eq!(result, Ok(None));
+    }
+    assert_eq!(server.chain().sent_transactions.len(), 3);
+}
+
+#[test]
+fn test_funding_cells_reservations() {
+    let (chain, _) = build_mock_chain();
+    let cells: Vec<LiveCell> = chain.cells.iter().cloned().map(LiveCell::from).collect();
+    let lock = build_lock();
+    let capacity = funding_capacity(&lock, FEE).unwrap();
+    let funding = FundingCells::default();
+
+    // A reserved funding cell isn't handed to another update, even by a clone of the reservations
+    let first = funding.reserve(&lock, &cells, capacity).unwrap();
+    assert!(FundingCells::is_funding_cell(&first, &lock));
+    let second = funding.clone().reserve(&lock, &cells, capacity).unwrap();
+    assert_ne!(first.out_point, second.out_point);
+    assert_eq!(
+        funding.reserve(&lock, &cells, capacity),
+        Err(UpdaterError::FundingNotFound)
+    );
+
+    // The funding cell of a transaction which isn't sent is free again
+    funding.release(&first.out_point);
+    assert_eq!(funding.reserve(&lock, &cells, capacity), Ok(first.clone()));
+
+    // The reservation of a funding cell which is no longer live is dropped
+    let live_cells: Vec<LiveCell> = cells
+        .iter()
+        .filter(|cell| cell.out_point != first.out_point)
+        .cloned()
+        .collect();
+    assert_eq!(
+        funding.reserve(&lock, &live_cells, capacity),
+        Err(UpdaterError::FundingNotFound)
+    );
+    assert_eq!(funding.reserve(&lock, &cells, capacity), Ok(first));
+
+    // A funding cell must pay the fee and keep the change cell
+    let funding = FundingCells::default();
+    assert_eq!(
+        funding.reserve(&lock, &cells, Capacity::bytes(1001).unwrap()),
+        Err(UpdaterError::FundingNotFound)
+    );
+}
+
+#[test]
+fn test_instances_of_the_same_oracle() {
+    let (chain, oracle) = build_mock_chain();
+    let server = MockRpcServer::start(chain).unwrap();
+    let config = build_instances_config(
+        "same-oracle",
+        server.url(),
+        &oracle,
+        vec![
+            build_instance(
+                "same-oracle-a",
+                build_oracle_args(&oracle.migration, 2),
+                OracleKind::Timestamp,
+                60,
+            ),
+            build_instance(
+                "same-oracle-b",
+                build_oracle_args(&oracle.migration, 2),
+                OracleKind::Timestamp,
+                600,
+            ),
+        ],
+    );
+    assert_eq!(
+        config.validate(),
+        Err(UpdaterError::Config(
+            "the instances same-oracle-a and same-oracle-b update the same oracle".to_string()
+        ))
+    );
+    assert!(Scheduler::new(&config).is_err());
+
+    let mut config = config;
+    config.instances[1].args = json::JsonBytes::from_vec(build_oracle_args(&oracle.migration, 3));
+    config.instances[1].name = "same-oracle-a".to_string();
+    assert_eq!(
+        config.validate(),
+        Err(UpdaterError::Config(
+            "the instance name same-oracle-a is duplicated".to_string()
+        ))
+    );
+}
+
+// The update of the timestamp oracle by another updater, which is built from the live cells
+fn build_competing_tx(chain: &MockChain, oracle: &MockOracle, timestamp: u64) -> json::Transaction {
+    let cells: Vec<LiveCell> = chain.cells.iter().cloned().map(LiveCell::from).collect();
+    let index_state_cell = &find_cells(&cells, &oracle.index_state_type_script)[0];
+    let info_cells = find_cells(&cells, &oracle.info_type_script);
+    let infos_data: Vec<Vec<u8>> = info_cells.iter().map(|cell| cell.data.to_vec()).collect();
+    let plan = plan_update(
+        &index_state_cell.data,
+        &infos_data,
+        OracleKind::Timestamp,
+        timestamp,
+    )
+    .unwrap();
+    // The updater takes the first free funding cell, and the other one takes the last one
+    let funding_cell = find_funding_cells(&cells).pop().unwrap();
+    let tx = build_update_tx(
+        index_state_cell,
+        &info_cells[plan.info_position],
+        &funding_cell,
+        &plan,
+        vec![oracle.cell_dep.clone()],
+        FEE,
+    )
+    .unwrap();
+    tx.data().into()
+}
+
+fn live_ring(chain: &MockChain, oracle: &MockOracle) -> (IndexState, Vec<Vec<u8>>) {
+    let index_state_data = live_data(chain, &oracle.index_state_type_script);
+    assert_eq!(index_state_data.len(), 1);
+    let mut ring = live_data(chain, &oracle.info_type_script);
+    ring.sort_by_key(|info_data| InfoLayout::V3.index(info_data));
+    (IndexState::from_slice(&index_state_data[0]).unwrap(), ring)
+}
+
+#[test]
+fn test_update_after_competing_update() {
+    let (chain, oracle) = build_mock_chain();
+    let server = MockRpcServer::start(chain).unwrap();
+    let config = build_config("competing", server.url(), &oracle);
+    let updater = Updater::new(&config, config.instances[0].clone()).unwrap();
+
+    // Another updater writes the slot of index 0 with an older value right before the update, so
+    // the update is rebuilt for index 1
+    {
+        let mut chain = server.chain();
+        let competing_tx = build_competing_tx(&chain, &oracle, FIRST_TIMESTAMP + 11 * 60 + 30);
+        chain.competing_transactions.push(competing_tx);
+    }
+    let tx_hash = updater.update_once().unwrap().expect("update transaction");
+    {
+        let chain = server.chain();
+        assert_eq!(chain.sent_transactions.len(), 2);
+        let tx_view = Transaction::from(chain.sent_transactions[1].clone()).into_view();
+        let expected_hash: H256 = tx_view.hash().unpack();
+        assert_eq!(tx_hash, expected_hash);
+
+        let (index_state, ring) = live_ring(&chain, &oracle);
+        assert_eq!(ring.len(), 12);
+        assert_eq!(index_state.index, 1);
+        assert_eq!(index_state.sequence, Some(13));
+        assert_eq!(
+            InfoLayout::V3.content(&ring[0]),
+            &(FIRST_TIMESTAMP + 11 * 60 + 30).to_be_bytes()[..]
+        );
+        assert_eq!(InfoLayout::V3.sequence(&ring[1]), Some(13));
+        assert_eq!(
+            InfoLayout::V3.content(&ring[1]),
+            &(FIRST_TIMESTAMP + 12 * 60).to_be_bytes()[..]
+        );
+    }
+
+    // The other updater writes the same value first, so the slot isn't written twice
+    server.chain().median_time += 60 * 1000;
+    {
+        let mut chain = server.chain();
+        let competing_tx = build_competing_tx(&chain, &oracle, FIRST_TIMESTAMP + 13 * 60);
+        chain.competing_transactions.push(competing_tx);
+    }
+    assert_eq!(updater.update_once(), Ok(None));
+    let chain = server.chain();
+    assert_eq!(chain.sent_transactions.len(), 3);
+    let (index_state, ring) = live_ring(&chain, &oracle);
+    assert_eq!(index_state.index, 2);
+    assert_eq!(index_state.sequence, Some(14));
+    assert_eq!(InfoLayout::V3.sequence(&ring[2]), Some(14));
+}
+
+#[test]
+fn test_update_with_stale_indexer() {
+    let (mut chain, oracle) = build_mock_chain();
+    // The indexer hasn't indexed the update of another updater yet
+    let stale_cells = chain.cells.clone();
+    let competing_tx = build_competing_tx(&chain, &oracle, FIRST_TIMESTAMP + 11 * 60 + 30);
+    chain.commit(competing_tx).unwrap();
+    chain.indexed_cells = Some(stale_cells);
+    let server = MockRpcServer::start(chain).unwrap();
+    let config = build_config("stale-indexer", server.url(), &oracle);
+    let updater = Updater::new(&config, config.instances[0].clone()).unwrap();
+
+    // Nothing is sent with the spent cells, and the update gives up after the retries
+    match updater.update_once() {
+        Err(UpdaterError::InputDead(_)) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(server.chain().sent_transactions.len(), 1);
+
+    server.chain().indexed_cells = None;
+    updater.update_once().unwrap().expect("update transaction");
+    let chain = server.chain();
+    assert_eq!(chain.sent_transactions.len(), 2);
+    let (index_state, _) = live_ring(&chain, &oracle);
+    assert_eq!(index_state.index, 1);
+    assert_eq!(index_state.sequence, Some(13));
+}
+
+fn find_cells(cells: &[LiveCell], type_script: &Script) -> Vec<LiveCell> {
+    cells
+        .iter()
+        .filter(|cell| cell.output.type_().to_opt().as_ref() == Some(type_script))
+        .cloned()
+        .collect()
+}
+
+fn find_funding_cells(cells: &[LiveCell]) -> Vec<LiveCell> {
+    cells
+        .iter()
+        .filter(|cell| FundingCells::is_funding_cell(cell, &build_lock()))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_verify_update_tx() {
+    let (chain, oracle) = build_mock_chain();
+    let cells: Vec<LiveCell> = chain.cells.iter().cloned().map(LiveCell::from).collect();
+    let index_state_cell = &find_cells(&cells, &oracle.index_state_type_script)[0];
+    let info_cells = find_cells(&cells, &oracle.info_type_script);
+    let infos_data: Vec<Vec<u8>> = info_cells.iter().map(|cell| cell.data.to_vec()).collect();
+    let mut plan = plan_update(
+        &index_state_cell.data,
+        &infos_data,
+        OracleKind::Timestamp,
+        FIRST_TIMESTAMP + 12 * 60,
+    )
+    .unwrap();
+    let funding_cell = find_funding_cells(&cells).remove(0);
+    let verifier = LocalVerifier::new(cells.clone());
+
+    let tx = build_update_tx(
+        index_state_cell,
+        &info_cells[plan.info_position],
+        &funding_cell,
+        &plan,
+        vec![oracle.cell_dep.clone()],
+        FEE,
+    )
+    .unwrap();
+    let cycles = verifier
+        .verify(
+            &tx,
+            &oracle.index_state_type_script,
+            &oracle.info_type_script,
+        )
+        .unwrap();
+    let names: Vec<&str> = cycles.iter().map(|script| script.name).collect();
+    assert_eq!(names, vec!["index-state-type", "info-type"]);
+
+    // The error code of the script is reported with the name of its Error variant
+    plan.since += 1;
+    let tx = build_update_tx(
+        index_state_cell,
+        &info_cells[plan.info_position],
+        &funding_cell,
+        &plan,
+        vec![oracle.cell_dep.clone()],
+        FEE,
+    )
+    .unwrap();
+    match verifier.verify(
+        &tx,
+        &oracle.index_state_type_script,
+        &oracle.info_type_script,
+    ) {
+        Err(VerifyError::Script {
+            name, code, error, ..
+        }) => {
+            assert_eq!(name, "info-type");
+            assert_eq!(code, Some(INVALID_TIME_INFO_SINCE));
+            assert_eq!(error, Some("InvalidTimeInfoSince"));
+        }
+        result => panic!("unexpected verify result {:?}", result),
+    }
+}
+
+#[test]
+fn test_verify_without_cell_dep() {
+    let (chain, oracle) = build_mock_chain();
+    let cells: Vec<LiveCell> = chain.cells.iter().cloned().map(LiveCell::from).collect();
+    let index_state_cell = &find_cells(&cells, &oracle.index_state_type_script)[0];
+    let info_cells = find_cells(&cells, &oracle.info_type_script);
+    let infos_data: Vec<Vec<u8>> = info_cells.iter().map(|cell| cell.data.to_vec()).collect();
+    let plan = plan_update(
+        &index_state_cell.data,
+        &infos_data,
+        OracleKind::Timestamp,
+        FIRST_TIMESTAMP + 12 * 60,
+    )
+    .unwrap();
+    let funding_cell = find_funding_cells(&cells).remove(0);
+    let tx = build_update_tx(
+        index_state_cell,
+        &info_cells[plan.info_position],
+        &funding_cell,
+        &plan,
+        vec![oracle.cell_dep.clone()],
+        FEE,
+    )
+    .unwrap();
+
+    let inputs = vec![
+        index_state_cell.clone(),
+        info_cells[plan.info_position].clone(),
+        funding_cell,
+    ];
+    assert_eq!(
+        LocalVerifier::new(inputs).verify(
+            &tx,
+            &oracle.index_state_type_script,
+            &oracle.info_type_script
+        ),
+        Err(VerifyError::CellNotResolved(oracle.cell_dep.out_point()))
+    );
+}
+
+#[test]
+fn test_script_error_names() {
+    assert_eq!(
+        validation_failure_code(&ScriptError::ValidationFailure(INVALID_TIME_INFO_SINCE)),
+        Some(INVALID_TIME_INFO_SINCE)
+    );
+    assert_eq!(
+        validation_failure_code(&ScriptError::ValidationFailure(-1)),
+        Some(-1)
+    );
+    assert_eq!(validation_failure_code(&ScriptError::MultipleMatches), None);
+
+    assert_eq!(
+        error_name(INFO_TYPE_ERRORS, INVALID_TIME_INFO_SINCE),
+        Some("InvalidTimeInfoSince")
+    );
+    assert_eq!(
+        error_name(INDEX_STATE_TYPE_ERRORS, INDEX_STATE_SEQUENCE_ERROR),
+        Some("IndexStateSequenceError")
+    );
+    assert_eq!(error_name(INDEX_STATE_TYPE_ERRORS, 100), None);
+}
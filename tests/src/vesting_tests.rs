@@ -0,0 +1,327 @@
+use super::*;
+use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
+use ckb_tool::ckb_error::assert_error_eq;
+use ckb_tool::ckb_script::ScriptError;
+use ckb_tool::ckb_types::{
+    bytes::{BufMut, Bytes, BytesMut},
+    core::{TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use common::constants::*;
+
+const MAX_CYCLES: u64 = 10_000_000;
+
+const TOTAL_CAPACITY: u64 = 10000;
+const START_TIMESTAMP: u64 = 1614829000;
+const END_TIMESTAMP: u64 = 1614830000;
+const MAX_AGE: u64 = 3600;
+
+// error numbers
+const INVALID_ARGUMENT: i8 = 5;
+const OWNER_NOT_EXIST: i8 = 6;
+const MULTIPLE_VESTING_INPUTS: i8 = 11;
+const LOCKED_CAPACITY_NOT_ENOUGH: i8 = 12;
+const ORACLE_NOT_FRESH: i8 = 14;
+
+fn build_vesting_args(
+    owner_lock_hash: &Byte32,
+    index_state_type_hash: &Byte32,
+    info_type_code_hash: &Byte32,
+    end_timestamp: u64,
+) -> Bytes {
+    let mut args_buf = BytesMut::with_capacity(32 * 3 + 8 * 4);
+    args_buf.put_slice(owner_lock_hash.as_slice());
+    args_buf.put_slice(index_state_type_hash.as_slice());
+    args_buf.put_slice(info_type_code_hash.as_slice());
+    args_buf.put_u64(TOTAL_CAPACITY);
+    args_buf.put_u64(START_TIMESTAMP);
+    args_buf.put_u64(end_timestamp);
+    args_buf.put_u64(MAX_AGE);
+    Bytes::from(args_buf.to_vec())
+}
+
+struct VestingCase {
+    latest_timestamp: u64,
+    vesting_inputs_capacity: Vec<u64>,
+    change_capacity: Option<u64>,
+    owner_exists: bool,
+    end_timestamp: u64,
+    // The since of the vesting inputs, which is the latest timestamp by default
+    since_timestamp: Option<u64>,
+}
+
+impl Default for VestingCase {
+    fn default() -> Self {
+        VestingCase {
+            latest_timestamp: START_TIMESTAMP,
+            vesting_inputs_capacity: vec![TOTAL_CAPACITY],
+            change_capacity: None,
+            owner_exists: true,
+            end_timestamp: END_TIMESTAMP,
+            since_timestamp: None,
+        }
+    }
+}
+
+fn create_test_context(case: VestingCase) -> (Context, TransactionView) {
+    // deploy contract
+    let mut context = Context::default();
+    let vesting_bin: Bytes = Loader::default().load_binary("vesting-lock");
+    let vesting_out_point = context.deploy_cell(vesting_bin);
+
+    // deploy always_success script
+    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+    // prepare scripts
+    let lock_script = context
+        .build_script(&always_success_out_point, Default::default())
+        .expect("script");
+    let lock_script_dep = CellDep::new_builder()
+        .out_point(always_success_out_point.clone())
+        .build();
+    let owner_lock_script = context
+        .build_script(&always_success_out_point, Bytes::from(vec![1]))
+        .expect("script");
+
+    // prepare oracle cells which are cell deps of the transaction
+    let oracle = create_oracle_cell_deps(
+        &mut context,
+        &lock_script,
+        build_legacy_index_state_cell_data(3),
+        &[build_legacy_info_cell_data(
+            3,
+            DataType::Timestamp,
+            case.latest_timestamp,
+        )],
+        None,
+    );
+
+    let vesting_lock_script = context
+        .build_script(
+            &vesting_out_point,
+            build_vesting_args(
+                &owner_lock_script.calc_script_hash(),
+                &oracle.index_state_type_script.calc_script_hash(),
+                &oracle.info_type_script.code_hash(),
+                case.end_timestamp,
+            ),
+        )
+        .expect("script");
+    let vesting_lock_script_dep = CellDep::new_builder().out_point(vesting_out_point).build();
+
+    // prepare cells
+    let mut inputs = vec![];
+    for capacity in case.vesting_inputs_capacity.iter() {
+        let vesting_input_out_point = context.create_cell(
+            CellOutput::new_builder()
+                .capacity(capacity.pack())
+                .lock(vesting_lock_script.clone())
+                .build(),
+            Bytes::new(),
+        );
+        let since_timestamp = case.since_timestamp.unwrap_or(case.latest_timestamp);
+        inputs.push(
+            CellInput::new_builder()
+                .previous_output(vesting_input_out_point)
+                .since((SINCE_TIMESTAMP_BASE + since_timestamp).pack())
+                .build(),
+        );
+    }
+    if case.owner_exists {
+        let owner_input_out_point = context.create_cell(
+            CellOutput::new_builder()
+                .capacity(500u64.pack())
+                .lock(owner_lock_script.clone())
+                .build(),
+            Bytes::new(),
+        );
+        inputs.push(
+            CellInput::new_builder()
+                .previous_output(owner_input_out_point)
+                .build(),
+        );
+    }
+
+    let input_capacity: u64 = case.vesting_inputs_capacity.iter().sum();
+    let change_capacity = case.change_capacity.unwrap_or(0);
+    let mut outputs = vec![CellOutput::new_builder()
+        .capacity((input_capacity - change_capacity).pack())
+        .lock(owner_lock_script)
+        .build()];
+    let mut outputs_data = vec![Bytes::new()];
+    if let Some(change_capacity) = case.change_capacity {
+        outputs.push(
+            CellOutput::new_builder()
+                .capacity(change_capacity.pack())
+                .lock(vesting_lock_script)
+                .build(),
+        );
+        outputs_data.push(Bytes::new());
+    }
+
+    let witnesses: Vec<Bytes> = inputs.iter().map(|_| Bytes::new()).collect();
+
+    // build transaction
+    let tx = TransactionBuilder::default()
+        .inputs(inputs)
+        .outputs(outputs)
+        .outputs_data(outputs_data.pack())
+        .cell_dep(lock_script_dep)
+        .cell_dep(vesting_lock_script_dep)
+        .cell_deps(oracle.cell_deps)
+        .witnesses(witnesses.pack())
+        .build();
+    (context, tx)
+}
+
+fn verify_success(case: VestingCase) {
+    let (mut context, tx) = create_test_context(case);
+
+    let tx = context.complete_tx(tx);
+    // run
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn verify_error(case: VestingCase, error_code: i8) {
+    let (mut context, tx) = create_test_context(case);
+
+    let tx = context.complete_tx(tx);
+
+    // run
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+
+    let script_cell_index = 0;
+    assert_error_eq!(
+        err,
+        ScriptError::ValidationFailure(error_code).input_lock_script(script_cell_index)
+    );
+}
+
+#[test]
+fn test_withdraw_vested_capacity_success() {
+    // half of the capacity is vested
+    verify_success(VestingCase {
+        latest_timestamp: START_TIMESTAMP + 500,
+        change_capacity: Some(5000),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_withdraw_after_partial_withdrawal_success() {
+    // 4000 was withdrawn before, and 7000 is vested now
+    verify_success(VestingCase {
+        latest_timestamp: START_TIMESTAMP + 700,
+        vesting_inputs_capacity: vec![6000],
+        change_capacity: Some(3000),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_withdraw_all_after_end_success() {
+    verify_success(VestingCase {
+        latest_timestamp: END_TIMESTAMP,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_keep_all_before_start_success() {
+    verify_success(VestingCase {
+        latest_timestamp: START_TIMESTAMP - 100,
+        change_capacity: Some(TOTAL_CAPACITY),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_error_withdraw_before_start() {
+    verify_error(
+        VestingCase {
+            latest_timestamp: START_TIMESTAMP,
+            change_capacity: Some(TOTAL_CAPACITY - 1),
+            ..Default::default()
+        },
+        LOCKED_CAPACITY_NOT_ENOUGH,
+    );
+}
+
+#[test]
+fn test_error_withdraw_more_than_vested() {
+    verify_error(
+        VestingCase {
+            latest_timestamp: START_TIMESTAMP + 500,
+            change_capacity: Some(4999),
+            ..Default::default()
+        },
+        LOCKED_CAPACITY_NOT_ENOUGH,
+    );
+}
+
+#[test]
+fn test_error_withdraw_more_than_vested_after_partial_withdrawal() {
+    verify_error(
+        VestingCase {
+            latest_timestamp: START_TIMESTAMP + 700,
+            vesting_inputs_capacity: vec![6000],
+            change_capacity: Some(2999),
+            ..Default::default()
+        },
+        LOCKED_CAPACITY_NOT_ENOUGH,
+    );
+}
+
+#[test]
+fn test_error_vesting_owner_not_exist() {
+    verify_error(
+        VestingCase {
+            latest_timestamp: END_TIMESTAMP,
+            owner_exists: false,
+            ..Default::default()
+        },
+        OWNER_NOT_EXIST,
+    );
+}
+
+#[test]
+fn test_error_multiple_vesting_inputs() {
+    verify_error(
+        VestingCase {
+            latest_timestamp: START_TIMESTAMP + 500,
+            vesting_inputs_capacity: vec![5000, 5000],
+            change_capacity: Some(5000),
+            ..Default::default()
+        },
+        MULTIPLE_VESTING_INPUTS,
+    );
+}
+
+#[test]
+fn test_error_vesting_args_period() {
+    verify_error(
+        VestingCase {
+            end_timestamp: START_TIMESTAMP,
+            ..Default::default()
+        },
+        INVALID_ARGUMENT,
+    );
+}
+
+// Half of the capacity is vested at the latest timestamp, but the oracle stopped long before the since
+#[test]
+fn test_error_withdraw_with_stale_oracle() {
+    verify_error(
+        VestingCase {
+            latest_timestamp: START_TIMESTAMP + 500,
+            change_capacity: Some(5000),
+            since_timestamp: Some(START_TIMESTAMP + 500 + MAX_AGE + 1),
+            ..Default::default()
+        },
+        ORACLE_NOT_FRESH,
+    );
+}
@@ -0,0 +1,55 @@
+use crate::error::CliError;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// The positional arguments and the `--name value` options of a command
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    pub positionals: Vec<String>,
+    pub options: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn parse(args: &[String]) -> Result<Args, CliError> {
+        let mut parsed = Args::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = iter.next().ok_or_else(|| {
+                        CliError::InvalidArgument(format!("--{} needs a value", name))
+                    })?;
+                    parsed.options.insert(name.to_string(), value.clone());
+                }
+                None => parsed.positionals.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn positional(&self, position: usize, name: &str) -> Result<&str, CliError> {
+        self.positionals
+            .get(position)
+            .map(|arg| arg.as_str())
+            .ok_or_else(|| CliError::InvalidArgument(format!("<{}> is required", name)))
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|value| value.as_str())
+    }
+
+    pub fn required(&self, name: &str) -> Result<&str, CliError> {
+        self.option(name)
+            .ok_or_else(|| CliError::InvalidArgument(format!("--{} is required", name)))
+    }
+
+    pub fn parse_option<T: FromStr>(&self, name: &str) -> Result<Option<T>, CliError> {
+        match self.option(name) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| CliError::InvalidArgument(format!("--{} {} is invalid", name, value))),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::args::Args;
+use crate::data;
+use crate::error::CliError;
+use crate::genesis::{build_genesis_tx, FundingCell, GenesisOptions};
+use crate::verify::{verify_mock_tx, MockTx};
+use ckb_jsonrpc_types as json;
+use ckb_types::{prelude::*, H256};
+use common::constants::*;
+use migration::{
+    diff::{CellRecipeDiff, DepGroupDiff, MigrationDiff},
+    network::Network,
+    Migration,
+};
+use std::fs;
+use std::path::Path;
+
+// The fee in shannons of the genesis transaction
+pub const DEFAULT_GENESIS_FEE: u64 = 100_000;
+pub const DEFAULT_BINARIES_DIR: &str = "build/release";
+pub const DEFAULT_MIGRATIONS_DIR: &str = "migrations";
+
+pub const USAGE: &str = "ckb-time <command> [args]
+
+Commands:
+    decode-info <hex> [--version <index state version>]
+    decode-index <hex>
+    encode-info --type <data type> --index <index> --value <value>
+                [--version <index state version>] [--sequence <sequence>] [--block-hash <hex>]
+    since <value> [--type <data type>]
+    genesis (--migration <migration json> | --network <dev|testnet|mainnet> [--migrations <dir>])
+            --funding <funding cell json> --type <data type>
+            [--value <value>] [--block-hash <hex>] [--version <index state version>]
+            [--length <info cells>] [--fee <shannons>]
+            [--output <tx json>]
+    verify <mock tx json> [--binaries <dir of index-state-type and info-type>]
+    check-binaries [--binaries <dir>] [--migration <migration json> | --network <network>]
+                   [--migrations <dir>]
+    diff-migrations <old migration json> <new migration json>
+
+Data types: arbitrage, timestamp, block_number, timestamp_millis, median_timestamp, block_hash,
+uint128, hash, payload";
+
+// Runs the command of the arguments, which don't include the program name, and returns the output
+pub fn run(args: &[String]) -> Result<String, CliError> {
+    let command = match args.first() {
+        Some(command) => command.as_str(),
+        None => return Err(CliError::Usage(USAGE.to_string())),
+    };
+    let args = Args::parse(&args[1..])?;
+    match command {
+        "decode-info" => data::decode_info(
+            &data::parse_hex(args.positional(0, "hex")?)?,
+            args.parse_option("version")?,
+        ),
+        "decode-index" => data::decode_index_state(&data::parse_hex(args.positional(0, "hex")?)?),
+        "encode-info" => {
+            let data_type = data::parse_data_type(args.required("type")?)?;
+            let content = data::encode_content(
+                data_type,
+                args.required("value")?,
+                args.option("block-hash"),
+            )?;
+            let index = args
+                .parse_option("index")?
+                .ok_or_else(|| CliError::InvalidArgument("--index is required".to_string()))?;
+            let version = args
+                .parse_option("version")?
+                .unwrap_or(INDEX_STATE_VERSION_LEGACY);
+            let info_data = data::encode_info(
+                version,
+                index,
+                data_type,
+                args.parse_option("sequence")?,
+                &content,
+            )?;
+            Ok(data::to_hex(&info_data))
+        }
+        "since" => {
+            let data_type = data::parse_data_type(args.option("type").unwrap_or("timestamp"))?;
+            let value = args
+                .positional(0, "value")?
+                .parse()
+                .map_err(|_| CliError::InvalidArgument("<value> is not a u64".to_string()))?;
+            let since = data::info_since(data_type, value)?;
+            Ok(format!("{} (0x{:016x})", since, since))
+        }
+        "genesis" => genesis(&args),
+        "check-binaries" => check_binaries(&args),
+        "diff-migrations" => {
+            let old = Migration::from_file(args.positional(0, "old migration json")?)
+                .map_err(CliError::Migration)?;
+            let new = Migration::from_file(args.positional(1, "new migration json")?)
+                .map_err(CliError::Migration)?;
+            diff_migrations(&MigrationDiff::new(&old, &new))
+        }
+        "verify" => {
+            let mock_tx = MockTx::from_file(args.positional(0, "mock tx json")?)?;
+            let binaries = args.option("binaries").unwrap_or(DEFAULT_BINARIES_DIR);
+            let results = verify_mock_tx(mock_tx, binaries)?;
+            let failed = results.iter().any(|result| result.is_err());
+            let report = results
+                .into_iter()
+                .map(|result| result.unwrap_or_else(|err| err))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if failed {
+                Err(CliError::VerifyFailed(report))
+            } else {
+                Ok(report)
+            }
+        }
+        _ => Err(CliError::Usage(USAGE.to_string())),
+    }
+}
+
+// The migration of the file, or the latest migration of the network
+fn load_migration(args: &Args) -> Result<Migration, CliError> {
+    let migration = match (args.option("migration"), args.option("network")) {
+        (Some(path), _) => Migration::from_file(path),
+        (None, Some(network)) => {
+            let network: Network = network
+                .parse()
+                .map_err(|_| CliError::InvalidArgument(format!("unknown network {}", network)))?;
+            let dir = args.option("migrations").unwrap_or(DEFAULT_MIGRATIONS_DIR);
+            Migration::latest(dir, network)
+        }
+        (None, None) => {
+            return Err(CliError::InvalidArgument(
+                "--migration or --network is required".to_string(),
+            ))
+        }
+    };
+    migration.map_err(CliError::Migration)
+}
+
+fn genesis(args: &Args) -> Result<String, CliError> {
+    let migration = load_migration(args)?;
+    let funding = FundingCell::from_file(args.required("funding")?)?;
+    let data_type = data::parse_data_type(args.required("type")?)?;
+    let content = data::encode_content(
+        data_type,
+        args.option("value").unwrap_or("0"),
+        args.option("block-hash"),
+    )?;
+    // A new oracle is a version 5 one unless the version is given, the older versions only match
+    // the deployed oracles and let anyone forge their cells
+    let options = GenesisOptions {
+        version: args
+            .parse_option("version")?
+            .unwrap_or(INDEX_STATE_VERSION_UNIQUE),
+        length: args
+            .parse_option("length")?
+            .unwrap_or(SUM_OF_INFO_CELLS as u16),
+        data_type,
+        content,
+        fee: args.parse_option("fee")?.unwrap_or(DEFAULT_GENESIS_FEE),
+    };
+    let tx = build_genesis_tx(&migration, &funding, &options)?;
+    let tx_json = serde_json::to_string_pretty(&json::Transaction::from(tx.data()))
+        .map_err(|err| CliError::InvalidData(err.to_string()))?;
+
+    match args.option("output") {
+        Some(path) => {
+            fs::write(path, tx_json).map_err(|err| CliError::InvalidFile(err.to_string()))?;
+            // The args of the type scripts of the new oracle, which its consumers look up by
+            let args = tx
+                .output(0)
+                .and_then(|output| output.type_().to_opt())
+                .map(|script| script.args().raw_data())
+                .unwrap_or_default();
+            let tx_hash: H256 = tx.hash().unpack();
+            Ok(format!(
+                "tx hash: {:#x}\nargs: {}",
+                tx_hash,
+                data::to_hex(&args)
+            ))
+        }
+        None => Ok(tx_json),
+    }
+}
+
+// Compares the data hashes of the binaries with the given migration, or with the latest migration
+// of the given network or of every network
+fn check_binaries(args: &Args) -> Result<String, CliError> {
+    let binaries = args.option("binaries").unwrap_or(DEFAULT_BINARIES_DIR);
+    let migrations = match args.option("migration") {
+        Some(path) => vec![(path.to_string(), Migration::from_file(path))],
+        None => {
+            let networks = match args.option("network") {
+                Some(network) => vec![network.parse().map_err(|_| {
+                    CliError::InvalidArgument(format!("unknown network {}", network))
+                })?],
+                None => Network::ALL.to_vec(),
+            };
+            let dir = args.option("migrations").unwrap_or(DEFAULT_MIGRATIONS_DIR);
+            let mut migrations = Vec::new();
+            for network in networks {
+                let path = Migration::latest_path(dir, network).map_err(CliError::Migration)?;
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let label = format!("{} {}", network.dir_name(), file_name);
+                migrations.push((label, Migration::from_file(&path)));
+            }
+            migrations
+        }
+    };
+
+    let mut lines = Vec::new();
+    let mut mismatched = false;
+    for (label, migration) in migrations {
+        let checks = migration
+            .and_then(|migration| migration.check_binaries(binaries))
+            .map_err(CliError::Migration)?;
+        lines.push(label);
+        for check in checks {
+            if check.is_matched() {
+                lines.push(format!(
+                    "    {}: {:#x} matched",
+                    check.name, check.data_hash
+                ));
+            } else {
+                mismatched = true;
+                lines.push(format!(
+                    "    {}: {:#x} mismatched, the migration has {:#x}",
+                    check.name, check.data_hash, check.recorded_data_hash
+                ));
+            }
+        }
+    }
+    let report = lines.join("\n");
+    if mismatched {
+        Err(CliError::BinaryMismatch(report))
+    } else {
+        Ok(report)
+    }
+}
+
+fn cell_recipe_line(cell: &CellRecipeDiff) -> String {
+    let (old, new) = match (&cell.old, &cell.new) {
+        (Some(old), Some(new)) => (old, new),
+        (None, _) => return format!("{}: added", cell.name),
+        (_, None) => return format!("{}: removed", cell.name),
+    };
+    let binary = if cell.is_data_changed() {
+        format!(
+            "binary changed {:#x} -> {:#x}",
+            old.data_hash, new.data_hash
+        )
+    } else {
+        format!("binary unchanged {:#x}", new.data_hash)
+    };
+    let type_id = match (&old.type_id, &new.type_id) {
+        (Some(type_id), _) if cell.is_type_id_stable() => format!("type id stable {:#x}", type_id),
+        (Some(old_type_id), Some(new_type_id)) => {
+            format!("type id changed {:#x} -> {:#x}", old_type_id, new_type_id)
+        }
+        _ => "no type id".to_string(),
+    };
+    format!("{}: {}, {}", cell.name, binary, type_id)
+}
+
+fn dep_group_line(dep_group: &DepGroupDiff) -> String {
+    match (&dep_group.old, &dep_group.new) {
+        (Some(old), Some(new)) if dep_group.is_rebuilt() => format!(
+            "{}: rebuilt {:#x}:{} -> {:#x}:{}",
+            dep_group.name, old.tx_hash, old.index, new.tx_hash, new.index
+        ),
+        (Some(old), Some(_)) => format!(
+            "{}: not rebuilt {:#x}:{}",
+            dep_group.name, old.tx_hash, old.index
+        ),
+        (None, _) => format!("{}: added", dep_group.name),
+        (_, None) => format!("{}: removed", dep_group.name),
+    }
+}
+
+// Reports the changes of the binaries and the dep groups, and fails if the upgrade changes a type
+// id which the consumers refer to, or moves a binary out of its dep group
+fn diff_migrations(diff: &MigrationDiff) -> Result<String, CliError> {
+    let mut lines: Vec<String> = diff.cells.iter().map(cell_recipe_line).collect();
+    lines.extend(diff.dep_groups.iter().map(dep_group_line));
+    if diff.is_dep_group_stale() {
+        lines.push("a binary is moved, but the dep group is not rebuilt".to_string());
+    }
+    let report = lines.join("\n");
+    if diff.keeps_type_ids() && !diff.is_dep_group_stale() {
+        Ok(report)
+    } else {
+        Err(CliError::UpgradeBroken(report))
+    }
+}
@@ -0,0 +1,269 @@
+use crate::error::CliError;
+use common::{constants::*, index_state::IndexState, info::InfoLayout};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const BLOCK_HASH_LEN: usize = 32;
+
+// The names of the data types in the commands, e.g. `--type block_number`
+pub const DATA_TYPES: &[(DataType, &str)] = &[
+    (DataType::Arbitrage, "arbitrage"),
+    (DataType::Timestamp, "timestamp"),
+    (DataType::BlockNumber, "block_number"),
+    (DataType::TimestampMillis, "timestamp_millis"),
+    (DataType::MedianTimestamp, "median_timestamp"),
+    (DataType::BlockHash, "block_hash"),
+    (DataType::Uint128, "uint128"),
+    (DataType::Hash, "hash"),
+    (DataType::Payload, "payload"),
+];
+
+pub fn parse_data_type(name: &str) -> Result<DataType, CliError> {
+    DATA_TYPES
+        .iter()
+        .find(|(_, type_name)| *type_name == name)
+        .map(|(data_type, _)| *data_type)
+        .ok_or_else(|| CliError::InvalidArgument(format!("unknown data type {}", name)))
+}
+
+pub fn data_type_of(data_type: u8) -> Option<DataType> {
+    DATA_TYPES
+        .iter()
+        .map(|(data_type, _)| *data_type)
+        .find(|known| *known as u8 == data_type)
+}
+
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>, CliError> {
+    let hex = hex.trim();
+    hex::decode(hex.strip_prefix("0x").unwrap_or(hex))
+        .map_err(|err| CliError::InvalidHex(err.to_string()))
+}
+
+pub fn to_hex(data: &[u8]) -> String {
+    format!("0x{}", hex::encode(data))
+}
+
+fn u64_of(content: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&content[..8]);
+    u64::from_be_bytes(buf)
+}
+
+// Seconds since the unix epoch as 2021-03-04T03:38:00Z, by the days from civil algorithm
+pub fn format_utc(seconds: u64) -> String {
+    let days = seconds / SECONDS_PER_DAY;
+    let seconds_of_day = seconds % SECONDS_PER_DAY;
+
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+pub fn format_utc_millis(millis: u64) -> String {
+    let utc = format_utc(millis / MILLIS_PER_SECOND);
+    format!(
+        "{}.{:03}Z",
+        utc.trim_end_matches('Z'),
+        millis % MILLIS_PER_SECOND
+    )
+}
+
+// The content must have the length of the data type, see content_len_rule
+pub fn format_content(data_type: u8, content: &[u8]) -> String {
+    match data_type_of(data_type) {
+        Some(DataType::Timestamp) => {
+            let timestamp = u64_of(content);
+            format!("{} ({})", timestamp, format_utc(timestamp))
+        }
+        Some(DataType::TimestampMillis) | Some(DataType::MedianTimestamp) => {
+            let timestamp = u64_of(content);
+            format!("{} ({})", timestamp, format_utc_millis(timestamp))
+        }
+        Some(DataType::BlockHash) => format!(
+            "block number {}, block hash {}",
+            u64_of(content),
+            to_hex(&content[8..])
+        ),
+        Some(DataType::Uint128) => {
+            let mut buf = [0u8; 16];
+            buf.copy_from_slice(content);
+            u128::from_be_bytes(buf).to_string()
+        }
+        Some(DataType::Hash) | Some(DataType::Payload) => to_hex(content),
+        _ => u64_of(content).to_string(),
+    }
+}
+
+fn data_type_name(data_type: u8) -> String {
+    match data_type_of(data_type) {
+        Some(known) => format!("{:?}({})", known, data_type),
+        None => format!("Unknown({})", data_type),
+    }
+}
+
+// The layout of the info cell data is decided by the version of its index state, and it is inferred
+// from the length of the data if the version is not given
+pub fn decode_info(data: &[u8], version: Option<u8>) -> Result<String, CliError> {
+    let layout = match version {
+        Some(version) => InfoLayout::of_version(version),
+        None => {
+            let layouts: Vec<InfoLayout> = [InfoLayout::LEGACY, InfoLayout::V1, InfoLayout::V3]
+                .iter()
+                .copied()
+                .filter(|layout| layout.is_data_len_valid(data))
+                .collect();
+            match layouts.len() {
+                0 => return Err(CliError::InvalidData("info cell data length".to_string())),
+                1 => layouts[0],
+                _ => {
+                    return Err(CliError::InvalidData(
+                        "the layout is ambiguous, set the index state --version".to_string(),
+                    ))
+                }
+            }
+        }
+    };
+    if !layout.is_data_len_valid(data) {
+        return Err(CliError::InvalidData("info cell data length".to_string()));
+    }
+
+    let data_type = layout.data_type(data);
+    let mut lines = vec![
+        format!("index: {}", layout.index(data)),
+        format!("type: {}", data_type_name(data_type)),
+    ];
+    if let Some(sequence) = layout.sequence(data) {
+        lines.push(format!("sequence: {}", sequence));
+    }
+    lines.push(format!(
+        "value: {}",
+        format_content(data_type, layout.content(data))
+    ));
+    Ok(lines.join("\n"))
+}
+
+pub fn decode_index_state(data: &[u8]) -> Result<String, CliError> {
+    let index_state =
+        IndexState::from_slice(data).map_err(|err| CliError::InvalidData(format!("{:?}", err)))?;
+    let mut lines = vec![
+        format!("version: {}", index_state.version),
+        format!("index: {}", index_state.index),
+        format!("length: {}", index_state.length),
+    ];
+    if let Some(history_root) = index_state.history_root {
+        lines.push(format!("history_root: {}", to_hex(&history_root)));
+    }
+    if let Some(sequence) = index_state.sequence {
+        lines.push(format!("sequence: {}", sequence));
+    }
+    if let Some(info_root) = index_state.info_root {
+        lines.push(format!("info_root: {}", to_hex(&info_root)));
+    }
+    Ok(lines.join("\n"))
+}
+
+fn parse_u64(value: &str) -> Result<u64, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError::InvalidArgument(format!("{} is not a u64", value)))
+}
+
+// The content of the value: a decimal number for the number types, hex for Hash and Payload,
+// and the block number for BlockHash whose block hash is given separately
+pub fn encode_content(
+    data_type: DataType,
+    value: &str,
+    block_hash: Option<&str>,
+) -> Result<Vec<u8>, CliError> {
+    match data_type {
+        DataType::BlockHash => {
+            let hash = parse_hex(block_hash.ok_or_else(|| {
+                CliError::InvalidArgument("--block-hash is required".to_string())
+            })?)?;
+            if hash.len() != BLOCK_HASH_LEN {
+                return Err(CliError::InvalidArgument(
+                    "the block hash must be 32 bytes".to_string(),
+                ));
+            }
+            let mut content = parse_u64(value)?.to_be_bytes().to_vec();
+            content.extend_from_slice(&hash);
+            Ok(content)
+        }
+        DataType::Uint128 => value
+            .parse::<u128>()
+            .map(|value| value.to_be_bytes().to_vec())
+            .map_err(|_| CliError::InvalidArgument(format!("{} is not a u128", value))),
+        DataType::Hash | DataType::Payload => parse_hex(value),
+        _ => Ok(parse_u64(value)?.to_be_bytes().to_vec()),
+    }
+}
+
+pub fn encode_info(
+    version: u8,
+    index: u16,
+    data_type: DataType,
+    sequence: Option<u64>,
+    content: &[u8],
+) -> Result<Vec<u8>, CliError> {
+    let layout = InfoLayout::of_version(version);
+    if layout.index_len == 1 && index > u8::MAX as u16 {
+        return Err(CliError::InvalidArgument(
+            "the legacy index must be less than 256".to_string(),
+        ));
+    }
+    if layout.sequence_len > 0 && sequence.is_none() {
+        return Err(CliError::InvalidArgument(
+            "--sequence is required since version 3".to_string(),
+        ));
+    }
+    let data = layout.build(index, data_type as u8, sequence, content);
+    if !layout.is_data_len_valid(&data) {
+        return Err(CliError::InvalidData(format!(
+            "the content of {:?} can't be {} bytes",
+            data_type,
+            content.len()
+        )));
+    }
+    Ok(data)
+}
+
+// The since of the info cell input which info-type expects for the value of the data type
+pub fn info_since(data_type: DataType, value: u64) -> Result<u64, CliError> {
+    let since = match data_type {
+        DataType::Timestamp => value,
+        DataType::TimestampMillis | DataType::MedianTimestamp => value / MILLIS_PER_SECOND,
+        DataType::BlockNumber | DataType::BlockHash => value,
+        _ => {
+            return Err(CliError::InvalidArgument(format!(
+                "{:?} has no since",
+                data_type
+            )))
+        }
+    };
+    if since & !SINCE_VALUE_MASK != 0 {
+        return Err(CliError::InvalidArgument(format!(
+            "{} is too big for the since",
+            value
+        )));
+    }
+    match data_type {
+        DataType::BlockNumber | DataType::BlockHash => Ok(since),
+        _ => Ok(SINCE_TIMESTAMP_BASE + since),
+    }
+}
@@ -0,0 +1,41 @@
+use migration::error::MigrationError;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliError {
+    Usage(String),
+    InvalidArgument(String),
+    InvalidHex(String),
+    InvalidData(String),
+    InvalidFile(String),
+    Migration(MigrationError),
+    // The capacity in shannons which the funding cell lacks
+    CapacityNotEnough(u64),
+    // The report of the scripts of which at least one failed
+    VerifyFailed(String),
+    // The report of the binaries of which at least one doesn't match its migration
+    BinaryMismatch(String),
+    // The report of an upgrade which changes a type id or keeps a stale dep group
+    UpgradeBroken(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(usage) => write!(f, "Usage: {}", usage),
+            CliError::InvalidArgument(err) => write!(f, "invalid argument: {}", err),
+            CliError::InvalidHex(err) => write!(f, "invalid hex: {}", err),
+            CliError::InvalidData(err) => write!(f, "invalid data: {}", err),
+            CliError::InvalidFile(err) => write!(f, "invalid file: {}", err),
+            CliError::Migration(err) => write!(f, "{}", err),
+            CliError::CapacityNotEnough(shannons) => {
+                write!(f, "the funding cell needs {} more shannons", shannons)
+            }
+            CliError::VerifyFailed(report) => write!(f, "{}", report),
+            CliError::BinaryMismatch(report) => write!(f, "{}", report),
+            CliError::UpgradeBroken(report) => write!(f, "{}", report),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
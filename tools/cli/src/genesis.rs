@@ -0,0 +1,203 @@
+use crate::error::CliError;
+use ckb_jsonrpc_types as json;
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionBuilder, TransactionView},
+    packed::{CellDep, CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use common::{constants::*, index_state::IndexState, info::InfoLayout, merkle::info_root};
+use migration::Migration;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// The cell which pays the capacity of the new oracle cells, whose lock also locks them
+#[derive(Debug, Clone, Deserialize)]
+pub struct FundingCell {
+    pub out_point: json::OutPoint,
+    pub output: json::CellOutput,
+    // The cell deps of the lock, e.g. the secp256k1 dep group
+    #[serde(default)]
+    pub cell_deps: Vec<json::CellDep>,
+}
+
+impl FundingCell {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<FundingCell, CliError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| CliError::InvalidFile(err.to_string()))?;
+        serde_json::from_str(&content).map_err(|err| CliError::InvalidFile(err.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenesisOptions {
+    pub version: u8,
+    pub length: u16,
+    pub data_type: DataType,
+    // The content of every info cell until it is updated
+    pub content: Vec<u8>,
+    pub fee: u64,
+}
+
+// The index state is at the last index, so the first update writes the info cell of the index 0
+pub fn genesis_data(options: &GenesisOptions) -> Result<(Vec<u8>, Vec<Vec<u8>>), CliError> {
+    if options.version > INDEX_STATE_VERSION_UNIQUE {
+        return Err(CliError::InvalidArgument(format!(
+            "unknown version {}",
+            options.version
+        )));
+    }
+    if options.version == INDEX_STATE_VERSION_LEGACY && options.length != SUM_OF_INFO_CELLS as u16 {
+        return Err(CliError::InvalidArgument(format!(
+            "the legacy ring has {} info cells",
+            SUM_OF_INFO_CELLS
+        )));
+    }
+    if options.length == 0 {
+        return Err(CliError::InvalidArgument(
+            "the ring needs info cells".to_string(),
+        ));
+    }
+
+    let layout = InfoLayout::of_version(options.version);
+    let sequence = if options.version >= INDEX_STATE_VERSION_SEQUENCE {
+        Some(0)
+    } else {
+        None
+    };
+    let infos_data: Vec<Vec<u8>> = (0..options.length)
+        .map(|index| layout.build(index, options.data_type as u8, sequence, &options.content))
+        .collect();
+    if !layout.is_data_len_valid(&infos_data[0]) {
+        return Err(CliError::InvalidData(format!(
+            "the content of {:?} can't be {} bytes",
+            options.data_type,
+            options.content.len()
+        )));
+    }
+
+    let index_state = IndexState {
+        version: options.version,
+        index: options.length - 1,
+        length: options.length,
+        history_root: if options.version >= INDEX_STATE_VERSION_HISTORY_ROOT {
+            Some([0u8; HASH_LEN])
+        } else {
+            None
+        },
+        sequence,
+        info_root: if options.version >= INDEX_STATE_VERSION_INFO_ROOT {
+            let leaves: Vec<&[u8]> = infos_data.iter().map(|data| data.as_slice()).collect();
+            Some(info_root(&leaves))
+        } else {
+            None
+        },
+    };
+    Ok((index_state.to_vec(), infos_data))
+}
+
+// The output has its occupied capacity only, the fees of the updates are paid by the funding cells
+// of the updater, see updater::funding::FundingCells
+fn build_output(lock: &Script, type_script: Option<Script>, data: &[u8]) -> CellOutput {
+    let output = CellOutput::new_builder()
+        .lock(lock.clone())
+        .type_(type_script.pack())
+        .build();
+    let capacity = output
+        .occupied_capacity(Capacity::bytes(data.len()).expect("data capacity"))
+        .expect("occupied capacity");
+    output.as_builder().capacity(capacity.pack()).build()
+}
+
+// Inputs: [funding cell]
+// Outputs: [index state cell, info cells of the indexes 0..length, change cell]
+// The args of the type scripts are the out point of the first input, so the ring is unique, and
+// since version 2 the info type code hash, see Migration::oracle_args.
+pub fn build_genesis_tx(
+    migration: &Migration,
+    funding: &FundingCell,
+    options: &GenesisOptions,
+) -> Result<TransactionView, CliError> {
+    let (index_state_data, infos_data) = genesis_data(options)?;
+
+    let funding_out_point: OutPoint = funding.out_point.clone().into();
+    let funding_output: CellOutput = funding.output.clone().into();
+    let lock = funding_output.lock();
+    let args = if options.version >= INDEX_STATE_VERSION_HISTORY_ROOT {
+        migration
+            .oracle_args(funding_out_point.as_slice())
+            .map_err(CliError::Migration)?
+    } else {
+        funding_out_point.as_slice().to_vec()
+    };
+    let index_state_type_script = migration
+        .index_state_type_script(&args)
+        .map_err(CliError::Migration)?;
+    let info_type_script = migration
+        .info_type_script(&args)
+        .map_err(CliError::Migration)?;
+
+    let mut outputs = vec![build_output(
+        &lock,
+        Some(index_state_type_script),
+        &index_state_data,
+    )];
+    let mut outputs_data = vec![Bytes::from(index_state_data).pack()];
+    for info_data in infos_data {
+        outputs.push(build_output(
+            &lock,
+            Some(info_type_script.clone()),
+            &info_data,
+        ));
+        outputs_data.push(Bytes::from(info_data).pack());
+    }
+
+    let mut needed = Capacity::shannons(options.fee);
+    for output in &outputs {
+        let capacity: Capacity = output.capacity().unpack();
+        needed = needed
+            .safe_add(capacity)
+            .map_err(|err| CliError::InvalidData(err.to_string()))?;
+    }
+    let change_output = build_output(&lock, None, &[]);
+    let change_occupied: Capacity = change_output.capacity().unpack();
+    let funding_capacity: Capacity = funding_output.capacity().unpack();
+    let required = needed
+        .safe_add(change_occupied)
+        .map_err(|err| CliError::InvalidData(err.to_string()))?;
+    if funding_capacity < required {
+        return Err(CliError::CapacityNotEnough(
+            required.as_u64() - funding_capacity.as_u64(),
+        ));
+    }
+    let change_capacity = funding_capacity
+        .safe_sub(needed)
+        .map_err(|err| CliError::InvalidData(err.to_string()))?;
+    outputs.push(
+        change_output
+            .as_builder()
+            .capacity(change_capacity.pack())
+            .build(),
+    );
+    outputs_data.push(Bytes::new().pack());
+
+    let cell_dep = migration.oracle_cell_dep().map_err(CliError::Migration)?;
+
+    let lock_cell_deps: Vec<CellDep> = funding
+        .cell_deps
+        .iter()
+        .cloned()
+        .map(CellDep::from)
+        .collect();
+
+    // The witness of the funding cell is left empty to be signed
+    Ok(TransactionBuilder::default()
+        .input(CellInput::new(funding_out_point, 0))
+        .outputs(outputs)
+        .outputs_data(outputs_data)
+        .cell_dep(cell_dep)
+        .cell_deps(lock_cell_deps)
+        .witness(Bytes::new().pack())
+        .build())
+}
@@ -0,0 +1,6 @@
+pub mod args;
+pub mod command;
+pub mod data;
+pub mod error;
+pub mod genesis;
+pub mod verify;
@@ -0,0 +1,206 @@
+use crate::error::CliError;
+use ckb_standalone_debugger::transaction::{MockTransaction, ReprMockTransaction};
+use ckb_types::{
+    bytes::Bytes,
+    core::{HeaderView, ScriptHashType, TransactionView},
+    packed::{CellOutput, Script},
+    prelude::*,
+};
+use common::{
+    constants::{ORACLE_ARGS_LEN, OUT_POINT_LEN},
+    script_error::{INDEX_STATE_TYPE_ERRORS, INFO_TYPE_ERRORS},
+};
+use std::fs;
+use std::path::Path;
+use updater::{
+    tx::LiveCell,
+    verify::{LocalVerifier, VerifyError},
+};
+
+// The transaction and the resolved cells of a mock transaction JSON, like the tx.json of
+// write_native_setup or a transaction captured from a chain
+#[derive(Debug, Clone)]
+pub struct MockTx {
+    pub tx: TransactionView,
+    pub inputs: Vec<LiveCell>,
+    pub cell_deps: Vec<LiveCell>,
+    pub headers: Vec<HeaderView>,
+}
+
+impl MockTx {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<MockTx, CliError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| CliError::InvalidFile(err.to_string()))?;
+        let repr_tx: ReprMockTransaction =
+            serde_json::from_str(&content).map_err(|err| CliError::InvalidFile(err.to_string()))?;
+        Ok(MockTx::from(MockTransaction::from(repr_tx)))
+    }
+
+    fn type_scripts(&self) -> Vec<Script> {
+        self.inputs
+            .iter()
+            .map(|cell| cell.output.clone())
+            .chain(self.tx.outputs().into_iter())
+            .filter_map(|output| output.type_().to_opt())
+            .collect()
+    }
+
+    // The index state cell is the first output, or the first input when the oracle cells are
+    // recycled, see info-type
+    pub fn index_state_type_script(&self) -> Option<Script> {
+        match self.tx.output(0) {
+            Some(output) => output.type_().to_opt(),
+            None => self
+                .inputs
+                .first()
+                .and_then(|cell| cell.output.type_().to_opt()),
+        }
+    }
+
+    // The info type script has the args of the index state type script
+    pub fn info_type_script(&self, index_state_type_script: &Script) -> Option<Script> {
+        self.type_scripts().into_iter().find(|script| {
+            script.args() == index_state_type_script.args()
+                && script.code_hash() != index_state_type_script.code_hash()
+        })
+    }
+
+    // Replaces the binary which the script refers to with another one, and returns the script
+    // which refers to the new binary. A script referring to the data hash gets the new data hash,
+    // in every cell of the transaction, and so do the oracle args which pin the code hash.
+    pub fn replace_binary(&mut self, script: &Script, binary: Bytes) -> Result<Script, CliError> {
+        let by_data_hash = script.hash_type() == ScriptHashType::Data.into();
+        let code_hash = script.code_hash();
+        let cell_dep = self
+            .cell_deps
+            .iter_mut()
+            .find(|cell| {
+                if by_data_hash {
+                    CellOutput::calc_data_hash(&cell.data) == code_hash
+                } else {
+                    cell.output
+                        .type_()
+                        .to_opt()
+                        .map(|type_script| type_script.calc_script_hash())
+                        == Some(code_hash.clone())
+                }
+            })
+            .ok_or_else(|| {
+                CliError::InvalidData(format!("no cell dep of the code hash {}", code_hash))
+            })?;
+        let new_code_hash = CellOutput::calc_data_hash(&binary);
+        cell_dep.data = binary;
+        if !by_data_hash {
+            return Ok(script.clone());
+        }
+
+        let replace_script = |type_script: Script| -> Script {
+            let mut builder = type_script.clone().as_builder();
+            if type_script.code_hash() == code_hash && type_script.hash_type() == script.hash_type()
+            {
+                builder = builder.code_hash(new_code_hash.clone());
+            }
+            let args = type_script.args().raw_data();
+            if args.len() == ORACLE_ARGS_LEN && args[OUT_POINT_LEN..] == code_hash.raw_data()[..] {
+                let mut new_args = args[..OUT_POINT_LEN].to_vec();
+                new_args.extend_from_slice(&new_code_hash.raw_data());
+                builder = builder.args(Bytes::from(new_args).pack());
+            }
+            builder.build()
+        };
+        let replace = |output: CellOutput| -> CellOutput {
+            match output.type_().to_opt() {
+                Some(type_script) => {
+                    let type_script = replace_script(type_script);
+                    output.as_builder().type_(Some(type_script).pack()).build()
+                }
+                None => output,
+            }
+        };
+        for cell in self.inputs.iter_mut() {
+            cell.output = replace(cell.output.clone());
+        }
+        let outputs: Vec<CellOutput> = self.tx.outputs().into_iter().map(replace).collect();
+        self.tx = self.tx.as_advanced_builder().set_outputs(outputs).build();
+        Ok(replace_script(script.clone()))
+    }
+
+    pub fn verifier(&self) -> LocalVerifier {
+        let cells = self.inputs.iter().chain(self.cell_deps.iter()).cloned();
+        LocalVerifier::new(cells.collect()).headers(self.headers.clone())
+    }
+}
+
+impl From<MockTransaction> for MockTx {
+    fn from(mock_tx: MockTransaction) -> MockTx {
+        let inputs = mock_tx
+            .mock_info
+            .inputs
+            .into_iter()
+            .map(|input| LiveCell {
+                out_point: input.input.previous_output(),
+                output: input.output,
+                data: input.data,
+            })
+            .collect();
+        let cell_deps = mock_tx
+            .mock_info
+            .cell_deps
+            .into_iter()
+            .map(|cell_dep| LiveCell {
+                out_point: cell_dep.cell_dep.out_point(),
+                output: cell_dep.output,
+                data: cell_dep.data,
+            })
+            .collect();
+        MockTx {
+            tx: mock_tx.tx.into_view(),
+            inputs,
+            cell_deps,
+            headers: mock_tx.mock_info.header_deps,
+        }
+    }
+}
+
+// Runs index-state-type and info-type of the binaries directory on the mock transaction, and
+// returns the lines of the report with the cycles or the error of every script
+pub fn verify_mock_tx<P: AsRef<Path>>(
+    mut mock_tx: MockTx,
+    binaries: P,
+) -> Result<Vec<Result<String, String>>, CliError> {
+    let index_state_type_script = mock_tx
+        .index_state_type_script()
+        .ok_or_else(|| CliError::InvalidData("no index state cell".to_string()))?;
+    let mut scripts = vec![index_state_type_script.clone()];
+    scripts.extend(mock_tx.info_type_script(&index_state_type_script));
+    for (name, script) in ["index-state-type", "info-type"].iter().zip(scripts.iter()) {
+        let binary = fs::read(binaries.as_ref().join(name))
+            .map_err(|err| CliError::InvalidFile(format!("{} {}", name, err)))?;
+        mock_tx.replace_binary(script, Bytes::from(binary))?;
+    }
+
+    // The scripts are found again, as the binary of info-type changes the args which pin it
+    let index_state_type_script = mock_tx
+        .index_state_type_script()
+        .ok_or_else(|| CliError::InvalidData("no index state cell".to_string()))?;
+    let mut replaced = vec![(
+        "index-state-type",
+        index_state_type_script.clone(),
+        INDEX_STATE_TYPE_ERRORS,
+    )];
+    if let Some(info_type_script) = mock_tx.info_type_script(&index_state_type_script) {
+        replaced.push(("info-type", info_type_script, INFO_TYPE_ERRORS));
+    }
+
+    let verifier = mock_tx.verifier();
+    Ok(replaced
+        .into_iter()
+        .map(|(name, script, errors)| {
+            match verifier.verify_script(&mock_tx.tx, name, &script, errors) {
+                Ok(cycles) => Ok(format!("{}: {} cycles", name, cycles.cycles)),
+                Err(err @ VerifyError::Script { .. }) => Err(err.to_string()),
+                Err(err) => Err(format!("{}: {}", name, err)),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,146 @@
+use crate::error::UpdaterError;
+use crate::plan::OracleKind;
+use ckb_crypto::secp::Privkey;
+use ckb_jsonrpc_types::{CellDep, JsonBytes};
+use ckb_types::packed;
+use migration::{network::Network, Migration};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The latest migration of the network in the directory gives the type ids of the oracle type
+// scripts and their dep group, so no code hash is configured
+#[derive(Debug, Clone, Deserialize)]
+pub struct MigrationConfig {
+    pub dir: PathBuf,
+    pub network: Network,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OracleScripts {
+    pub index_state_type_script: packed::Script,
+    pub info_type_script: packed::Script,
+    // The dep group of the oracle type scripts and the cell deps of the config
+    pub cell_deps: Vec<packed::CellDep>,
+}
+
+// How an update is retried when its inputs are spent by another transaction before it's committed
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    // The delay before the first retry, which doubles on every retry
+    pub backoff_millis: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> RetryConfig {
+        RetryConfig {
+            max_retries: 3,
+            backoff_millis: 1000,
+        }
+    }
+}
+
+// An oracle which is updated by the updater, and whose cells are only spent by its instance
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstanceConfig {
+    // The name of the instance in the logs
+    pub name: String,
+    // The args of the index state type script and the info type script of the oracle
+    pub args: JsonBytes,
+    pub kind: OracleKind,
+    // The file of the hex private key which unlocks the index state cell, the info cells and the
+    // funding cells, which have the same lock
+    pub private_key_path: PathBuf,
+    // The fee in shannons which is paid by a funding cell, see funding::FundingCells
+    pub fee: u64,
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub ckb_rpc_url: String,
+    pub indexer_rpc_url: String,
+    pub migration: MigrationConfig,
+    // The cell deps of the lock scripts
+    pub cell_deps: Vec<CellDep>,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    pub instances: Vec<InstanceConfig>,
+}
+
+impl Config {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config, UpdaterError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| UpdaterError::Config(err.to_string()))?;
+        let config: Config =
+            toml::from_str(&content).map_err(|err| UpdaterError::Config(err.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // The instances spend the cells of their own oracles, so two instances of the same oracle would
+    // spend the same cells at once. The funding cells of a lock are shared by the reservations.
+    pub fn validate(&self) -> Result<(), UpdaterError> {
+        if self.instances.is_empty() {
+            return Err(UpdaterError::Config("no instance".to_string()));
+        }
+        for (position, instance) in self.instances.iter().enumerate() {
+            let others = &self.instances[..position];
+            if others.iter().any(|other| other.name == instance.name) {
+                return Err(UpdaterError::Config(format!(
+                    "the instance name {} is duplicated",
+                    instance.name
+                )));
+            }
+            if let Some(other) = others.iter().find(|other| other.args == instance.args) {
+                return Err(UpdaterError::Config(format!(
+                    "the instances {} and {} update the same oracle",
+                    other.name, instance.name
+                )));
+            }
+            if instance.interval_secs == 0 {
+                return Err(UpdaterError::Config(format!(
+                    "the interval of {} is zero",
+                    instance.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn oracle_scripts(&self, instance: &InstanceConfig) -> Result<OracleScripts, UpdaterError> {
+        let migration = Migration::latest(&self.migration.dir, self.migration.network)
+            .map_err(UpdaterError::Migration)?;
+        let args = instance.args.as_bytes();
+        let mut cell_deps = vec![migration
+            .oracle_cell_dep()
+            .map_err(UpdaterError::Migration)?];
+        cell_deps.extend(self.cell_deps.iter().cloned().map(packed::CellDep::from));
+        Ok(OracleScripts {
+            index_state_type_script: migration
+                .index_state_type_script(args)
+                .map_err(UpdaterError::Migration)?,
+            info_type_script: migration
+                .info_type_script(args)
+                .map_err(UpdaterError::Migration)?,
+            cell_deps,
+        })
+    }
+}
+
+impl InstanceConfig {
+    pub fn load_privkey(&self) -> Result<Privkey, UpdaterError> {
+        let content = fs::read_to_string(&self.private_key_path)
+            .map_err(|err| UpdaterError::Config(err.to_string()))?;
+        let content = content.trim();
+        let key = hex::decode(content.strip_prefix("0x").unwrap_or(content))
+            .map_err(|err| UpdaterError::Config(err.to_string()))?;
+        if key.len() != 32 {
+            return Err(UpdaterError::Config(
+                "the private key must be 32 bytes".to_string(),
+            ));
+        }
+        Ok(Privkey::from_slice(&key))
+    }
+}
@@ -0,0 +1,63 @@
+use crate::verify::VerifyError;
+use migration::error::MigrationError;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdaterError {
+    Config(String),
+    Migration(MigrationError),
+    Rpc(String),
+    // The out point of an input which is spent by another transaction
+    InputDead(String),
+    IndexStateNotFound,
+    // The info cell of the index which should be updated next
+    InfoNotFound(u16),
+    InvalidOracleData(String),
+    DataTypeMismatch,
+    // The new value is not bigger than the latest one, so there is nothing to update yet
+    ValueNotNewer,
+    CapacityNotEnough,
+    // No funding cell of the lock of the oracle cells is free and pays the fee
+    FundingNotFound,
+    Sign(String),
+    // The transaction fails the oracle type scripts locally, so it is not sent
+    Verify(VerifyError),
+}
+
+impl fmt::Display for UpdaterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdaterError::Config(err) => write!(f, "invalid config: {}", err),
+            UpdaterError::Migration(err) => write!(f, "invalid migration: {}", err),
+            UpdaterError::Rpc(err) => write!(f, "rpc error: {}", err),
+            UpdaterError::InputDead(out_point) => {
+                write!(f, "the input {} is spent by another transaction", out_point)
+            }
+            UpdaterError::IndexStateNotFound => write!(f, "the index state cell is not found"),
+            UpdaterError::InfoNotFound(index) => {
+                write!(f, "the info cell of index {} is not found", index)
+            }
+            UpdaterError::InvalidOracleData(err) => write!(f, "invalid oracle data: {}", err),
+            UpdaterError::DataTypeMismatch => {
+                write!(
+                    f,
+                    "the data type of the info cells is not the configured one"
+                )
+            }
+            UpdaterError::ValueNotNewer => write!(f, "the value is not newer than the latest one"),
+            UpdaterError::CapacityNotEnough => {
+                write!(
+                    f,
+                    "the capacity of a cell is not enough for its data or the fee"
+                )
+            }
+            UpdaterError::FundingNotFound => {
+                write!(f, "no free funding cell has the capacity for the fee")
+            }
+            UpdaterError::Sign(err) => write!(f, "failed to sign: {}", err),
+            UpdaterError::Verify(err) => write!(f, "local verification failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for UpdaterError {}
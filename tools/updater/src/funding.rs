@@ -0,0 +1,61 @@
+use crate::error::UpdaterError;
+use crate::tx::LiveCell;
+use ckb_types::{
+    core::Capacity,
+    packed::{Byte32, OutPoint, Script},
+    prelude::*,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// The funding cells pay the fees of the updates, they are the cells of the lock of the oracle cells
+// without type script and data. The updaters of a scheduler share the reservations, so a funding
+// cell is never handed to another instance while it is in use, even if the instances have the same
+// lock.
+#[derive(Debug, Clone, Default)]
+pub struct FundingCells {
+    // The funding cells in use or spent by a sent transaction, and the hashes of their locks
+    reserved: Arc<Mutex<HashMap<OutPoint, Byte32>>>,
+}
+
+impl FundingCells {
+    pub fn is_funding_cell(cell: &LiveCell, lock: &Script) -> bool {
+        &cell.output.lock() == lock && cell.output.type_().is_none() && cell.data.is_empty()
+    }
+
+    // Reserves the first of the live funding cells of the lock which isn't reserved, and whose
+    // capacity is enough. A sent transaction keeps its funding cell reserved until the cell is no
+    // longer live, i.e. until the indexer drops it after the transaction is committed.
+    pub fn reserve(
+        &self,
+        lock: &Script,
+        cells: &[LiveCell],
+        capacity: Capacity,
+    ) -> Result<LiveCell, UpdaterError> {
+        let lock_hash = lock.calc_script_hash();
+        let mut reserved = self.reserved.lock().expect("lock funding reservations");
+        reserved.retain(|out_point, reserved_lock_hash| {
+            reserved_lock_hash != &lock_hash
+                || cells.iter().any(|cell| &cell.out_point == out_point)
+        });
+        let cell = cells
+            .iter()
+            .filter(|cell| Self::is_funding_cell(cell, lock))
+            .filter(|cell| !reserved.contains_key(&cell.out_point))
+            .find(|cell| {
+                let cell_capacity: Capacity = cell.output.capacity().unpack();
+                cell_capacity >= capacity
+            })
+            .ok_or(UpdaterError::FundingNotFound)?;
+        reserved.insert(cell.out_point.clone(), lock_hash);
+        Ok(cell.clone())
+    }
+
+    // The funding cell of a transaction which isn't sent can be spent by another update
+    pub fn release(&self, out_point: &OutPoint) {
+        self.reserved
+            .lock()
+            .expect("lock funding reservations")
+            .remove(out_point);
+    }
+}
@@ -0,0 +1,11 @@
+pub mod config;
+pub mod error;
+pub mod funding;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod plan;
+pub mod rpc;
+pub mod scheduler;
+pub mod tx;
+pub mod updater;
+pub mod verify;
@@ -0,0 +1,31 @@
+use std::env;
+use std::process;
+use updater::config::Config;
+use updater::scheduler::{print_result, Scheduler};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 || (args.len() == 3 && args[2] != "--once") {
+        eprintln!("Usage: ckb-time-updater <config.toml> [--once]");
+        process::exit(1);
+    }
+
+    let scheduler = match Config::from_file(&args[1]).and_then(|config| Scheduler::new(&config)) {
+        Ok(scheduler) => scheduler,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+    if args.len() == 2 {
+        scheduler.run();
+        return;
+    }
+    let results = scheduler.update_all_once();
+    for result in &results {
+        print_result(result);
+    }
+    if results.iter().any(|(_, result)| result.is_err()) {
+        process::exit(1);
+    }
+}
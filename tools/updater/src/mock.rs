@@ -0,0 +1,252 @@
+use crate::error::UpdaterError;
+use crate::rpc::{IndexerCell, IndexerCells, ScriptType, SearchKey};
+use ckb_jsonrpc_types::{
+    CellData, CellInfo, CellWithStatus, HeaderView, JsonBytes, OutPoint as JsonOutPoint,
+    Transaction, Uint64,
+};
+use ckb_types::{
+    bytes::Bytes,
+    core::HeaderBuilder,
+    packed::{self, CellOutput, OutPoint},
+    prelude::*,
+    H256,
+};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Response, Server};
+
+// The live cells and the tip of a fake chain, and the transactions which were sent to it. A sent
+// transaction is committed at once: its inputs must be live, and they are replaced by its outputs.
+#[derive(Debug, Clone, Default)]
+pub struct MockChain {
+    pub tip_number: u64,
+    // In milliseconds like the median_time of get_blockchain_info
+    pub median_time: u64,
+    pub cells: Vec<IndexerCell>,
+    // The cells which the indexer serves while it is behind the node, the live cells if None
+    pub indexed_cells: Option<Vec<IndexerCell>>,
+    // The transactions of another updater, which are committed right before the next sent one
+    pub competing_transactions: Vec<Transaction>,
+    pub sent_transactions: Vec<Transaction>,
+    pushed_cells: u64,
+}
+
+impl MockChain {
+    pub fn new(tip_number: u64, median_time: u64) -> MockChain {
+        MockChain {
+            tip_number,
+            median_time,
+            ..Default::default()
+        }
+    }
+
+    pub fn push_cell(&mut self, output: CellOutput, data: Bytes) -> OutPoint {
+        // The pushed cells are the outputs of fake transactions which are never sent
+        self.pushed_cells += 1;
+        let mut tx_hash = [0u8; 32];
+        tx_hash[..8].copy_from_slice(&self.pushed_cells.to_be_bytes());
+        let out_point = OutPoint::new(tx_hash.pack(), 0);
+        self.cells.push(IndexerCell {
+            output: output.into(),
+            output_data: JsonBytes::from_bytes(data),
+            out_point: out_point.clone().into(),
+            block_number: self.tip_number.into(),
+            tx_index: 0u32.into(),
+        });
+        out_point
+    }
+
+    pub fn live_cell(&self, out_point: &OutPoint) -> Option<&IndexerCell> {
+        let out_point: JsonOutPoint = out_point.clone().into();
+        self.cells.iter().find(|cell| cell.out_point == out_point)
+    }
+
+    fn search(&self, search_key: &SearchKey) -> Vec<IndexerCell> {
+        self.indexed_cells
+            .as_ref()
+            .unwrap_or(&self.cells)
+            .iter()
+            .filter(|cell| {
+                let script = match search_key.script_type {
+                    ScriptType::Lock => Some(&cell.output.lock),
+                    ScriptType::Type => cell.output.type_.as_ref(),
+                };
+                script.map_or(false, |script| {
+                    script.code_hash == search_key.script.code_hash
+                        && script.hash_type == search_key.script.hash_type
+                        && script
+                            .args
+                            .as_bytes()
+                            .starts_with(search_key.script.args.as_bytes())
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn commit(&mut self, tx: Transaction) -> Result<Value, String> {
+        let tx_view = packed::Transaction::from(tx.clone()).into_view();
+        let mut positions = Vec::new();
+        for input in tx_view.inputs() {
+            let out_point: JsonOutPoint = input.previous_output().into();
+            match self
+                .cells
+                .iter()
+                .position(|cell| cell.out_point == out_point)
+            {
+                Some(position) => positions.push(position),
+                None => return Err("dead cell".to_string()),
+            }
+        }
+        positions.sort_unstable();
+        for position in positions.into_iter().rev() {
+            self.cells.remove(position);
+        }
+
+        self.tip_number += 1;
+        for (index, (output, data)) in tx_view.outputs_with_data_iter().enumerate() {
+            self.cells.push(IndexerCell {
+                output: output.into(),
+                output_data: JsonBytes::from_bytes(data),
+                out_point: OutPoint::new(tx_view.hash(), index as u32).into(),
+                block_number: self.tip_number.into(),
+                tx_index: 1u32.into(),
+            });
+        }
+        self.sent_transactions.push(tx);
+        let tx_hash: H256 = tx_view.hash().unpack();
+        Ok(json!(tx_hash))
+    }
+
+    fn handle(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        match method {
+            "get_tip_header" => {
+                let header = HeaderBuilder::default()
+                    .number(self.tip_number.pack())
+                    .timestamp(self.median_time.pack())
+                    .build();
+                Ok(json!(HeaderView::from(header)))
+            }
+            "get_blockchain_info" => Ok(json!({
+                "chain": "ckb_dev",
+                "median_time": Uint64::from(self.median_time),
+            })),
+            "get_live_cell" => {
+                let out_point: JsonOutPoint =
+                    serde_json::from_value(params[0].clone()).map_err(|err| err.to_string())?;
+                let cell = self.live_cell(&out_point.into()).map(|cell| {
+                    let data = cell.output_data.clone().into_bytes();
+                    CellInfo {
+                        output: cell.output.clone(),
+                        data: Some(CellData {
+                            hash: CellOutput::calc_data_hash(&data).unpack(),
+                            content: JsonBytes::from_bytes(data),
+                        }),
+                    }
+                });
+                let status = if cell.is_some() { "live" } else { "unknown" };
+                Ok(json!(CellWithStatus {
+                    cell,
+                    status: status.to_string(),
+                }))
+            }
+            "get_cells" => {
+                let search_key: SearchKey =
+                    serde_json::from_value(params[0].clone()).map_err(|err| err.to_string())?;
+                Ok(json!(IndexerCells {
+                    objects: self.search(&search_key),
+                    last_cursor: JsonBytes::default(),
+                }))
+            }
+            "send_transaction" => {
+                let tx: Transaction =
+                    serde_json::from_value(params[0].clone()).map_err(|err| err.to_string())?;
+                for competing_tx in std::mem::take(&mut self.competing_transactions) {
+                    self.commit(competing_tx)?;
+                }
+                self.commit(tx)
+            }
+            _ => Err(format!("method {} is not supported", method)),
+        }
+    }
+}
+
+// A local JSON-RPC server of both the CKB node and the CKB indexer methods used by the updater
+pub struct MockRpcServer {
+    url: String,
+    chain: Arc<Mutex<MockChain>>,
+    server: Arc<Server>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockRpcServer {
+    pub fn start(chain: MockChain) -> Result<MockRpcServer, UpdaterError> {
+        let server = Arc::new(
+            Server::http("127.0.0.1:0").map_err(|err| UpdaterError::Rpc(err.to_string()))?,
+        );
+        let url = format!("http://{}", server.server_addr());
+        let chain = Arc::new(Mutex::new(chain));
+
+        let (thread_server, thread_chain) = (Arc::clone(&server), Arc::clone(&chain));
+        let handle = thread::spawn(move || {
+            for mut request in thread_server.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                let response = match serde_json::from_str::<Value>(&body) {
+                    Ok(mut request) => {
+                        let method = request["method"].as_str().unwrap_or_default().to_string();
+                        let result = thread_chain
+                            .lock()
+                            .expect("lock mock chain")
+                            .handle(&method, request["params"].take());
+                        match result {
+                            Ok(result) => {
+                                json!({"jsonrpc": "2.0", "id": request["id"], "result": result})
+                            }
+                            Err(message) => json!({
+                                "jsonrpc": "2.0",
+                                "id": request["id"],
+                                "error": {"code": -1, "message": message},
+                            }),
+                        }
+                    }
+                    Err(err) => json!({
+                        "jsonrpc": "2.0",
+                        "id": null,
+                        "error": {"code": -32700, "message": err.to_string()},
+                    }),
+                };
+                let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                    .expect("content type header");
+                let _ = request
+                    .respond(Response::from_string(response.to_string()).with_header(header));
+            }
+        });
+
+        Ok(MockRpcServer {
+            url,
+            chain,
+            server,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn chain(&self) -> MutexGuard<'_, MockChain> {
+        self.chain.lock().expect("lock mock chain")
+    }
+}
+
+impl Drop for MockRpcServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
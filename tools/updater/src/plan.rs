@@ -0,0 +1,125 @@
+use crate::error::UpdaterError;
+use common::{
+    constants::*,
+    history::extend_history_root,
+    index_state::IndexState,
+    merkle::{info_proof, info_root},
+};
+use serde::Deserialize;
+
+// The data types which can be updated with a since only, the others need header deps
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleKind {
+    Timestamp,
+    BlockNumber,
+}
+
+impl OracleKind {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            OracleKind::Timestamp => DataType::Timestamp,
+            OracleKind::BlockNumber => DataType::BlockNumber,
+        }
+    }
+
+    // The since of the info cell input, which the chain accepts as soon as the value is published:
+    // the timestamp is the median time of the chain and the block number is the tip number.
+    pub fn since(&self, value: u64) -> u64 {
+        match self {
+            OracleKind::Timestamp => SINCE_TIMESTAMP_BASE + value,
+            OracleKind::BlockNumber => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdatePlan {
+    // The position of the info cell to be consumed in the info cells of the ring
+    pub info_position: usize,
+    pub index_state_data: Vec<u8>,
+    pub info_data: Vec<u8>,
+    pub since: u64,
+    // The merkle proof of the updated info cell for the version 4 index state
+    pub info_proof: Option<Vec<u8>>,
+}
+
+// Plans the update which writes the value into the next slot of the ring, the info cell of the next
+// index is the oldest one, and it is replaced by the new value.
+pub fn plan_update(
+    index_state_data: &[u8],
+    infos_data: &[Vec<u8>],
+    kind: OracleKind,
+    value: u64,
+) -> Result<UpdatePlan, UpdaterError> {
+    let index_state = IndexState::from_slice(index_state_data)
+        .map_err(|err| UpdaterError::InvalidOracleData(format!("{:?}", err)))?;
+    let layout = index_state.info_layout();
+    if infos_data
+        .iter()
+        .any(|info_data| !layout.is_data_len_valid(info_data))
+    {
+        return Err(UpdaterError::InvalidOracleData(
+            "info cell data length".to_string(),
+        ));
+    }
+
+    let find_info = |index: u16| {
+        infos_data
+            .iter()
+            .position(|info_data| layout.index(info_data) == index)
+            .ok_or(UpdaterError::InfoNotFound(index))
+    };
+    let latest_info_data = &infos_data[find_info(index_state.index)?];
+    let next_index = index_state.next_index(index_state.length);
+    let info_position = find_info(next_index)?;
+
+    let data_type = kind.data_type() as u8;
+    if layout.data_type(latest_info_data) != data_type
+        || layout.data_type(&infos_data[info_position]) != data_type
+    {
+        return Err(UpdaterError::DataTypeMismatch);
+    }
+    let mut latest_buf = [0u8; INFO_CELL_CONTENT_LEN];
+    latest_buf.copy_from_slice(&layout.content(latest_info_data)[..INFO_CELL_CONTENT_LEN]);
+    if value <= u64::from_be_bytes(latest_buf) {
+        return Err(UpdaterError::ValueNotNewer);
+    }
+
+    let sequence = match index_state.sequence {
+        Some(sequence) => Some(
+            sequence
+                .checked_add(1)
+                .ok_or_else(|| UpdaterError::InvalidOracleData("sequence overflow".to_string()))?,
+        ),
+        None => None,
+    };
+    let info_data = layout.build(next_index, data_type, sequence, &value.to_be_bytes());
+
+    let mut next_index_state = index_state;
+    next_index_state.index = next_index;
+    next_index_state.sequence = sequence;
+    next_index_state.history_root = index_state
+        .history_root
+        .map(|history_root| extend_history_root(&history_root, &info_data));
+
+    let mut info_proof_data = None;
+    if index_state.info_root.is_some() {
+        // The whole ring is needed to build the merkle proof
+        let mut ring: Vec<&[u8]> = Vec::with_capacity(index_state.length as usize);
+        for index in 0..index_state.length {
+            ring.push(&infos_data[find_info(index)?]);
+        }
+        info_proof_data = Some(info_proof(&ring, next_index));
+        ring[next_index as usize] = &info_data;
+        next_index_state.info_root = Some(info_root(&ring));
+    }
+
+    Ok(UpdatePlan {
+        info_position,
+        index_state_data: next_index_state.to_vec(),
+        info_data,
+        since: kind.since(value),
+        info_proof: info_proof_data,
+    })
+}
@@ -0,0 +1,118 @@
+use crate::error::UpdaterError;
+use ckb_jsonrpc_types::{
+    BlockNumber, CellOutput, CellWithStatus, HeaderView, JsonBytes, OutPoint, Script, Transaction,
+    Uint32, Uint64,
+};
+use ckb_types::H256;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const CELLS_PAGE_LIMIT: u32 = 100;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockchainInfo {
+    // The median of the timestamps of the last 37 blocks in milliseconds
+    pub median_time: Uint64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptType {
+    Lock,
+    Type,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchKey {
+    pub script: Script,
+    pub script_type: ScriptType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerCell {
+    pub output: CellOutput,
+    pub output_data: JsonBytes,
+    pub out_point: OutPoint,
+    pub block_number: BlockNumber,
+    pub tx_index: Uint32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerCells {
+    pub objects: Vec<IndexerCell>,
+    pub last_cursor: JsonBytes,
+}
+
+// A blocking JSON-RPC client of a CKB node or a CKB indexer
+pub struct RpcClient {
+    client: reqwest::blocking::Client,
+    url: String,
+    id: AtomicU64,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> RpcClient {
+        RpcClient {
+            client: reqwest::blocking::Client::new(),
+            url: url.to_string(),
+            id: AtomicU64::new(0),
+        }
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, UpdaterError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let mut response: Value = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .and_then(|response| response.json())
+            .map_err(|err| UpdaterError::Rpc(err.to_string()))?;
+        if let Some(err) = response.get("error") {
+            return Err(UpdaterError::Rpc(format!("{} {}", method, err)));
+        }
+        serde_json::from_value(response["result"].take())
+            .map_err(|err| UpdaterError::Rpc(format!("{} {}", method, err)))
+    }
+
+    pub fn get_tip_header(&self) -> Result<HeaderView, UpdaterError> {
+        self.call("get_tip_header", json!([]))
+    }
+
+    pub fn get_blockchain_info(&self) -> Result<BlockchainInfo, UpdaterError> {
+        self.call("get_blockchain_info", json!([]))
+    }
+
+    pub fn get_live_cell(&self, out_point: OutPoint) -> Result<CellWithStatus, UpdaterError> {
+        self.call("get_live_cell", json!([out_point, true]))
+    }
+
+    pub fn send_transaction(&self, tx: Transaction) -> Result<H256, UpdaterError> {
+        self.call("send_transaction", json!([tx, "passthrough"]))
+    }
+
+    // All the live cells of the indexer whose script starts with the search key script
+    pub fn get_cells(&self, search_key: SearchKey) -> Result<Vec<IndexerCell>, UpdaterError> {
+        let mut cells = Vec::new();
+        let mut cursor: Option<JsonBytes> = None;
+        loop {
+            let page: IndexerCells = self.call(
+                "get_cells",
+                json!([search_key, "asc", Uint32::from(CELLS_PAGE_LIMIT), cursor]),
+            )?;
+            let page_len = page.objects.len();
+            cells.extend(page.objects);
+            if page_len < CELLS_PAGE_LIMIT as usize {
+                return Ok(cells);
+            }
+            cursor = Some(page.last_cursor);
+        }
+    }
+}
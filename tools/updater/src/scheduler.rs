@@ -0,0 +1,121 @@
+use crate::config::Config;
+use crate::error::UpdaterError;
+use crate::funding::FundingCells;
+use crate::updater::Updater;
+use ckb_types::H256;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The result of an update of an instance, see Updater::update_once
+pub type UpdateResult = (String, Result<Option<H256>, UpdaterError>);
+
+// Tells the threads of a running scheduler to stop, see Scheduler::spawn
+#[derive(Debug, Clone, Default)]
+pub struct StopSignal(Arc<(Mutex<bool>, Condvar)>);
+
+impl StopSignal {
+    pub fn stop(&self) {
+        let (stopped, condvar) = &*self.0;
+        *stopped.lock().expect("lock stop signal") = true;
+        condvar.notify_all();
+    }
+
+    // Waits for the timeout unless it's stopped meanwhile, and returns whether it's stopped
+    fn wait(&self, timeout: Duration) -> bool {
+        let (stopped, condvar) = &*self.0;
+        let stopped = stopped.lock().expect("lock stop signal");
+        let (stopped, _) = condvar
+            .wait_timeout_while(stopped, timeout, |stopped| !*stopped)
+            .expect("lock stop signal");
+        *stopped
+    }
+}
+
+// The updaters of the instances of a config, each one is updated at its own interval and on its own
+// thread, so a slow node or the retry backoff of one instance never delays the others. The fees are
+// paid by the funding cells of the lock of the oracle cells, and the updaters share the reservations
+// of them, so two instances of the same lock never spend the same funding cell.
+pub struct Scheduler {
+    updaters: Vec<Arc<Updater>>,
+}
+
+impl Scheduler {
+    pub fn new(config: &Config) -> Result<Scheduler, UpdaterError> {
+        config.validate()?;
+        let funding = FundingCells::default();
+        let updaters = config
+            .instances
+            .iter()
+            .cloned()
+            .map(|instance| {
+                Updater::new(config, instance)
+                    .map(|updater| Arc::new(updater.funding(funding.clone())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Scheduler { updaters })
+    }
+
+    pub fn updaters(&self) -> &[Arc<Updater>] {
+        &self.updaters
+    }
+
+    // Updates every instance once, whether it is due or not
+    pub fn update_all_once(&self) -> Vec<UpdateResult> {
+        update_concurrently(self.updaters.iter())
+    }
+
+    // Every instance loops on its own thread until the signal stops it, and the interval is the
+    // least time between the starts of two of its updates. The results are received as soon as the
+    // updates are done, and the receiver is closed when all the threads have stopped.
+    pub fn spawn(self, stop: &StopSignal) -> Receiver<UpdateResult> {
+        let (sender, receiver) = mpsc::channel();
+        for updater in self.updaters {
+            let (sender, stop) = (sender.clone(), stop.clone());
+            thread::spawn(move || loop {
+                let started = Instant::now();
+                let result = (updater.instance().name.clone(), updater.update_once());
+                if sender.send(result).is_err() {
+                    return;
+                }
+                let interval = Duration::from_secs(updater.instance().interval_secs);
+                let delay = interval
+                    .checked_sub(started.elapsed())
+                    .unwrap_or_else(|| Duration::from_secs(0));
+                if stop.wait(delay) {
+                    return;
+                }
+            });
+        }
+        receiver
+    }
+
+    pub fn run(self) {
+        for result in self.spawn(&StopSignal::default()) {
+            print_result(&result);
+        }
+    }
+}
+
+// Runs the updates on their own threads, and returns the results in the order of the updaters
+fn update_concurrently<'a, I: Iterator<Item = &'a Arc<Updater>>>(updaters: I) -> Vec<UpdateResult> {
+    let handles: Vec<_> = updaters
+        .map(|updater| {
+            let updater = Arc::clone(updater);
+            thread::spawn(move || (updater.instance().name.clone(), updater.update_once()))
+        })
+        .collect();
+    handles
+        .into_iter()
+        .map(|handle| handle.join().expect("updater thread"))
+        .collect()
+}
+
+pub fn print_result((name, result): &UpdateResult) {
+    match result {
+        Ok(Some(tx_hash)) => println!("{}: sent update transaction {:#x}", name, tx_hash),
+        Ok(None) => println!("{}: no newer value to update", name),
+        Err(err) => eprintln!("{}: failed to update: {}", name, err),
+    }
+}
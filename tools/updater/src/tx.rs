@@ -0,0 +1,156 @@
+use crate::error::UpdaterError;
+use crate::plan::UpdatePlan;
+use crate::rpc::IndexerCell;
+use ckb_crypto::secp::Privkey;
+use ckb_hash::new_blake2b;
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionBuilder, TransactionView},
+    packed::{CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+    H256,
+};
+
+const SIGNATURE_LEN: usize = 65;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveCell {
+    pub out_point: OutPoint,
+    pub output: CellOutput,
+    pub data: Bytes,
+}
+
+impl From<IndexerCell> for LiveCell {
+    fn from(cell: IndexerCell) -> LiveCell {
+        LiveCell {
+            out_point: cell.out_point.into(),
+            output: cell.output.into(),
+            data: cell.output_data.into_bytes(),
+        }
+    }
+}
+
+// The least capacity of a funding cell of the lock, which pays the fee and keeps the change cell
+pub fn funding_capacity(lock: &Script, fee: u64) -> Result<Capacity, UpdaterError> {
+    CellOutput::new_builder()
+        .lock(lock.clone())
+        .build()
+        .occupied_capacity(Capacity::zero())
+        .and_then(|occupied_capacity| occupied_capacity.safe_add(Capacity::shannons(fee)))
+        .map_err(|_| UpdaterError::CapacityNotEnough)
+}
+
+// Inputs: [index state cell, info cell with the since of the plan, funding cell]
+// Outputs: [index state cell, info cell, change cell which pays the fee]
+// The info cell keeps its capacity, so the ring never runs out of capacity however often it is
+// updated. The info type script requires the index state cell to be the first output, and it reads
+// the merkle proof of version 4 from the input_type of the witness of the info cell input.
+pub fn build_update_tx(
+    index_state_cell: &LiveCell,
+    info_cell: &LiveCell,
+    funding_cell: &LiveCell,
+    plan: &UpdatePlan,
+    cell_deps: Vec<CellDep>,
+    fee: u64,
+) -> Result<TransactionView, UpdaterError> {
+    let info_output = info_cell.output.clone();
+    let info_capacity: Capacity = info_output.capacity().unpack();
+    let occupied_capacity = Capacity::bytes(plan.info_data.len())
+        .and_then(|data_capacity| info_output.occupied_capacity(data_capacity))
+        .map_err(|_| UpdaterError::CapacityNotEnough)?;
+    if info_capacity < occupied_capacity {
+        return Err(UpdaterError::CapacityNotEnough);
+    }
+
+    let funding_capacity: Capacity = funding_cell.output.capacity().unpack();
+    let change_capacity = funding_capacity
+        .safe_sub(Capacity::shannons(fee))
+        .map_err(|_| UpdaterError::CapacityNotEnough)?;
+    let change_output = funding_cell
+        .output
+        .clone()
+        .as_builder()
+        .capacity(change_capacity.pack())
+        .build();
+    let occupied_capacity = change_output
+        .occupied_capacity(Capacity::zero())
+        .map_err(|_| UpdaterError::CapacityNotEnough)?;
+    if change_capacity < occupied_capacity {
+        return Err(UpdaterError::CapacityNotEnough);
+    }
+
+    let info_witness = match &plan.info_proof {
+        Some(proof) => WitnessArgs::new_builder()
+            .input_type(Some(Bytes::from(proof.clone())).pack())
+            .build()
+            .as_bytes(),
+        None => Bytes::new(),
+    };
+
+    Ok(TransactionBuilder::default()
+        .input(CellInput::new(index_state_cell.out_point.clone(), 0))
+        .input(CellInput::new(info_cell.out_point.clone(), plan.since))
+        .input(CellInput::new(funding_cell.out_point.clone(), 0))
+        .output(index_state_cell.output.clone())
+        .output_data(Bytes::from(plan.index_state_data.clone()).pack())
+        .output(info_output)
+        .output_data(Bytes::from(plan.info_data.clone()).pack())
+        .output(change_output)
+        .output_data(Bytes::new().pack())
+        .cell_deps(cell_deps)
+        .witness(Bytes::new().pack())
+        .witness(info_witness.pack())
+        .witness(Bytes::new().pack())
+        .build())
+}
+
+// Signs the inputs as one secp256k1 sighash all group, so all the input cells must have the same
+// lock, which the funding cells are found by
+pub fn sign_tx(
+    tx: TransactionView,
+    input_cells: &[&LiveCell],
+    privkey: &Privkey,
+) -> Result<TransactionView, UpdaterError> {
+    let lock = input_cells.first().map(|cell| cell.output.lock());
+    if input_cells
+        .iter()
+        .any(|cell| Some(cell.output.lock()) != lock)
+    {
+        return Err(UpdaterError::Sign(
+            "the input cells have different locks".to_string(),
+        ));
+    }
+
+    let witnesses: Vec<Bytes> = tx.witnesses().into_iter().map(|w| w.unpack()).collect();
+    let placeholder = WitnessArgs::new_builder()
+        .lock(Some(Bytes::from(vec![0u8; SIGNATURE_LEN])).pack())
+        .build();
+
+    let mut blake2b = new_blake2b();
+    blake2b.update(&tx.hash().raw_data());
+    let placeholder_bytes = placeholder.as_bytes();
+    blake2b.update(&(placeholder_bytes.len() as u64).to_le_bytes());
+    blake2b.update(&placeholder_bytes);
+    for witness in witnesses.iter().skip(1) {
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(witness);
+    }
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+
+    let signature = privkey
+        .sign_recoverable(&H256::from(message))
+        .map_err(|err| UpdaterError::Sign(err.to_string()))?;
+    let lock_witness = placeholder
+        .as_builder()
+        .lock(Some(Bytes::from(signature.serialize())).pack())
+        .build()
+        .as_bytes();
+
+    let mut signed_witnesses = vec![lock_witness.pack()];
+    signed_witnesses.extend(witnesses.into_iter().skip(1).map(|w| w.pack()));
+    Ok(tx
+        .as_advanced_builder()
+        .set_witnesses(signed_witnesses)
+        .build())
+}
@@ -0,0 +1,252 @@
+use crate::config::{Config, InstanceConfig, OracleScripts, RetryConfig};
+use crate::error::UpdaterError;
+use crate::funding::FundingCells;
+use crate::plan::{plan_update, OracleKind, UpdatePlan};
+use crate::rpc::{RpcClient, ScriptType, SearchKey};
+use crate::tx::{build_update_tx, funding_capacity, sign_tx, LiveCell};
+use crate::verify::LocalVerifier;
+use ckb_crypto::secp::Privkey;
+use ckb_jsonrpc_types::CellInfo;
+use ckb_types::{core::DepType, packed, prelude::*, H256};
+use std::thread;
+use std::time::Duration;
+
+// The updater of one oracle instance, see scheduler::Scheduler for the instances of a config
+pub struct Updater {
+    instance: InstanceConfig,
+    scripts: OracleScripts,
+    retry: RetryConfig,
+    ckb: RpcClient,
+    indexer: RpcClient,
+    privkey: Privkey,
+    funding: FundingCells,
+}
+
+impl Updater {
+    pub fn new(config: &Config, instance: InstanceConfig) -> Result<Updater, UpdaterError> {
+        let privkey = instance.load_privkey()?;
+        let scripts = config.oracle_scripts(&instance)?;
+        Ok(Updater {
+            instance,
+            scripts,
+            retry: config.retry,
+            ckb: RpcClient::new(&config.ckb_rpc_url),
+            indexer: RpcClient::new(&config.indexer_rpc_url),
+            privkey,
+            funding: FundingCells::default(),
+        })
+    }
+
+    // The reservations of the funding cells, which the updaters of the same lock must share
+    pub fn funding(mut self, funding: FundingCells) -> Updater {
+        self.funding = funding;
+        self
+    }
+
+    pub fn instance(&self) -> &InstanceConfig {
+        &self.instance
+    }
+
+    // The indexer searches by the prefix of the args, so the cells are filtered by the whole script
+    fn load_cells(&self, type_script: &packed::Script) -> Result<Vec<LiveCell>, UpdaterError> {
+        let cells = self.indexer.get_cells(SearchKey {
+            script: type_script.clone().into(),
+            script_type: ScriptType::Type,
+        })?;
+        Ok(cells
+            .into_iter()
+            .map(LiveCell::from)
+            .filter(|cell| cell.output.type_().to_opt().as_ref() == Some(type_script))
+            .collect())
+    }
+
+    fn load_funding_cells(&self, lock: &packed::Script) -> Result<Vec<LiveCell>, UpdaterError> {
+        let cells = self.indexer.get_cells(SearchKey {
+            script: lock.clone().into(),
+            script_type: ScriptType::Lock,
+        })?;
+        Ok(cells
+            .into_iter()
+            .map(LiveCell::from)
+            .filter(|cell| FundingCells::is_funding_cell(cell, lock))
+            .collect())
+    }
+
+    fn load_live_cell(&self, out_point: packed::OutPoint) -> Result<LiveCell, UpdaterError> {
+        match self.ckb.get_live_cell(out_point.clone().into())?.cell {
+            Some(CellInfo {
+                output,
+                data: Some(data),
+            }) => Ok(LiveCell {
+                out_point,
+                output: output.into(),
+                data: data.content.into_bytes(),
+            }),
+            _ => Err(UpdaterError::Config(format!(
+                "the cell dep {} is not live",
+                out_point
+            ))),
+        }
+    }
+
+    // The indexer may be behind the node, so a cell which it returns may be spent already
+    fn check_live(&self, cell: &LiveCell) -> Result<(), UpdaterError> {
+        match self
+            .ckb
+            .get_live_cell(cell.out_point.clone().into())?
+            .status
+            .as_str()
+        {
+            "live" => Ok(()),
+            _ => Err(UpdaterError::InputDead(cell.out_point.to_string())),
+        }
+    }
+
+    // The cells of the cell deps and of their dep groups, which the local verification resolves
+    fn load_cell_deps(&self, cell_deps: &[packed::CellDep]) -> Result<Vec<LiveCell>, UpdaterError> {
+        let mut cells = Vec::new();
+        for cell_dep in cell_deps {
+            let cell = self.load_live_cell(cell_dep.out_point())?;
+            if cell_dep.dep_type() == DepType::DepGroup.into() {
+                let out_points = packed::OutPointVec::from_slice(&cell.data)
+                    .map_err(|err| UpdaterError::Config(err.to_string()))?;
+                for out_point in out_points.into_iter() {
+                    cells.push(self.load_live_cell(out_point)?);
+                }
+            }
+            cells.push(cell);
+        }
+        Ok(cells)
+    }
+
+    // The value which the chain accepts as the since of the info cell input right now
+    fn current_value(&self) -> Result<u64, UpdaterError> {
+        match self.instance.kind {
+            OracleKind::Timestamp => {
+                let median_time = self.ckb.get_blockchain_info()?.median_time.value();
+                Ok(median_time / common::constants::MILLIS_PER_SECOND)
+            }
+            OracleKind::BlockNumber => Ok(self.ckb.get_tip_header()?.inner.number.value()),
+        }
+    }
+
+    // Sends one update transaction, and returns None if the chain has no newer value yet. If the
+    // oracle cells are spent by another transaction first, the update is planned again with the
+    // live cells after a backoff, so a slot which is written meanwhile is never written twice. The
+    // backoff only blocks the thread of this instance, see scheduler::Scheduler.
+    pub fn update_once(&self) -> Result<Option<H256>, UpdaterError> {
+        let mut sent_tx_hashes = Vec::new();
+        let mut backoff = Duration::from_millis(self.retry.backoff_millis);
+        let mut retries = 0;
+        loop {
+            match self.try_update(&mut sent_tx_hashes) {
+                Err(UpdaterError::InputDead(_)) if retries < self.retry.max_retries => {
+                    retries += 1;
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // The transactions sent by the previous tries may be committed though they were reported as
+    // failed, the live index state cell tells whether one of them is
+    fn try_update(&self, sent_tx_hashes: &mut Vec<H256>) -> Result<Option<H256>, UpdaterError> {
+        let mut index_state_cells = self.load_cells(&self.scripts.index_state_type_script)?;
+        if index_state_cells.len() != 1 {
+            return Err(UpdaterError::IndexStateNotFound);
+        }
+        let index_state_cell = index_state_cells.remove(0);
+        self.check_live(&index_state_cell)?;
+        let index_state_tx_hash: H256 = index_state_cell.out_point.tx_hash().unpack();
+        if sent_tx_hashes.contains(&index_state_tx_hash) {
+            return Ok(Some(index_state_tx_hash));
+        }
+        let info_cells = self.load_cells(&self.scripts.info_type_script)?;
+        let infos_data: Vec<Vec<u8>> = info_cells.iter().map(|cell| cell.data.to_vec()).collect();
+
+        let value = self.current_value()?;
+        let plan = match plan_update(
+            &index_state_cell.data,
+            &infos_data,
+            self.instance.kind,
+            value,
+        ) {
+            Ok(plan) => plan,
+            Err(UpdaterError::ValueNotNewer) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let info_cell = &info_cells[plan.info_position];
+        self.check_live(info_cell)?;
+
+        // The funding cell is found by the lock of the oracle cells, so one key signs all inputs
+        let lock = index_state_cell.output.lock();
+        let funding_cell = self.funding.reserve(
+            &lock,
+            &self.load_funding_cells(&lock)?,
+            funding_capacity(&lock, self.instance.fee)?,
+        )?;
+        let result = self.send_update(
+            &index_state_cell,
+            info_cell,
+            &funding_cell,
+            &plan,
+            sent_tx_hashes,
+        );
+        if result.is_err() {
+            self.funding.release(&funding_cell.out_point);
+        }
+        result
+    }
+
+    fn send_update(
+        &self,
+        index_state_cell: &LiveCell,
+        info_cell: &LiveCell,
+        funding_cell: &LiveCell,
+        plan: &UpdatePlan,
+        sent_tx_hashes: &mut Vec<H256>,
+    ) -> Result<Option<H256>, UpdaterError> {
+        self.check_live(funding_cell)?;
+        let tx = build_update_tx(
+            index_state_cell,
+            info_cell,
+            funding_cell,
+            plan,
+            self.scripts.cell_deps.clone(),
+            self.instance.fee,
+        )?;
+        let tx = sign_tx(
+            tx,
+            &[index_state_cell, info_cell, funding_cell],
+            &self.privkey,
+        )?;
+
+        // A transaction which fails the oracle type scripts would only waste the fee
+        let mut cells = self.load_cell_deps(&self.scripts.cell_deps)?;
+        cells.push(index_state_cell.clone());
+        cells.push(info_cell.clone());
+        cells.push(funding_cell.clone());
+        LocalVerifier::new(cells)
+            .verify(
+                &tx,
+                &self.scripts.index_state_type_script,
+                &self.scripts.info_type_script,
+            )
+            .map_err(UpdaterError::Verify)?;
+
+        sent_tx_hashes.push(tx.hash().unpack());
+        match self.ckb.send_transaction(tx.data().into()) {
+            Ok(tx_hash) => Ok(Some(tx_hash)),
+            // The node rejects a transaction whose inputs are spent, which is told by the inputs
+            Err(err) => {
+                self.check_live(index_state_cell)?;
+                self.check_live(info_cell)?;
+                self.check_live(funding_cell)?;
+                Err(err)
+            }
+        }
+    }
+}
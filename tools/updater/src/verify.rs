@@ -0,0 +1,207 @@
+use crate::tx::LiveCell;
+use ckb_tool::ckb_script::{ScriptError, ScriptGroupType, TransactionScriptsVerifier};
+use ckb_traits::{CellDataProvider, HeaderProvider};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{CellMeta, CellMetaBuilder, ResolvedTransaction},
+        Cycle, DepType, HeaderView, TransactionView,
+    },
+    packed::{Byte32, OutPoint, OutPointVec, Script},
+    prelude::*,
+};
+use common::script_error::{error_name, INDEX_STATE_TYPE_ERRORS, INFO_TYPE_ERRORS};
+use std::collections::HashMap;
+use std::fmt;
+
+pub const DEFAULT_MAX_CYCLES: Cycle = 70_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptCycles {
+    pub name: &'static str,
+    pub cycles: Cycle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    // The input or the cell dep which is not given to the verifier
+    CellNotResolved(OutPoint),
+    InvalidDepGroup(OutPoint),
+    Script {
+        name: &'static str,
+        // The exit code of the script and the name of its Error variant
+        code: Option<i8>,
+        error: Option<&'static str>,
+        message: String,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::CellNotResolved(out_point) => {
+                write!(f, "the cell {} is not resolved", out_point)
+            }
+            VerifyError::InvalidDepGroup(out_point) => {
+                write!(f, "the dep group {} is invalid", out_point)
+            }
+            VerifyError::Script {
+                name,
+                code: Some(code),
+                error: Some(error),
+                ..
+            } => write!(f, "{} failed with {}({})", name, error, code),
+            VerifyError::Script { name, message, .. } => write!(f, "{} failed: {}", name, message),
+        }
+    }
+}
+
+// Runs the oracle type scripts of a transaction like the tests do with ckb-testtool, against the
+// resolved inputs and cell deps, so a transaction which would fail on chain is never sent.
+pub struct LocalVerifier {
+    cells: HashMap<OutPoint, LiveCell>,
+    headers: Vec<HeaderView>,
+    max_cycles: Cycle,
+}
+
+impl LocalVerifier {
+    pub fn new(cells: Vec<LiveCell>) -> LocalVerifier {
+        LocalVerifier {
+            cells: cells
+                .into_iter()
+                .map(|cell| (cell.out_point.clone(), cell))
+                .collect(),
+            headers: Vec::new(),
+            max_cycles: DEFAULT_MAX_CYCLES,
+        }
+    }
+
+    // The headers of the header deps, which info-type reads for the millisecond data types
+    pub fn headers(mut self, headers: Vec<HeaderView>) -> LocalVerifier {
+        self.headers = headers;
+        self
+    }
+
+    pub fn max_cycles(mut self, max_cycles: Cycle) -> LocalVerifier {
+        self.max_cycles = max_cycles;
+        self
+    }
+
+    fn resolve(&self, out_point: &OutPoint) -> Result<CellMeta, VerifyError> {
+        match self.cells.get(out_point) {
+            Some(cell) => Ok(CellMetaBuilder::from_cell_output(
+                cell.output.clone(),
+                cell.data.clone(),
+            )
+            .out_point(out_point.clone())
+            .build()),
+            None => Err(VerifyError::CellNotResolved(out_point.clone())),
+        }
+    }
+
+    fn resolve_tx(&self, tx: &TransactionView) -> Result<ResolvedTransaction, VerifyError> {
+        let mut resolved_cell_deps = Vec::new();
+        let mut resolved_dep_groups = Vec::new();
+        for cell_dep in tx.cell_deps() {
+            let out_point = cell_dep.out_point();
+            let cell_meta = self.resolve(&out_point)?;
+            if cell_dep.dep_type() != DepType::DepGroup.into() {
+                resolved_cell_deps.push(cell_meta);
+                continue;
+            }
+            // The dep group is expanded to its cells like the chain does
+            let out_points = OutPointVec::from_slice(&self.cells[&out_point].data)
+                .map_err(|_| VerifyError::InvalidDepGroup(out_point.clone()))?;
+            for member in out_points.into_iter() {
+                resolved_cell_deps.push(self.resolve(&member)?);
+            }
+            resolved_dep_groups.push(cell_meta);
+        }
+        let resolved_inputs = tx
+            .inputs()
+            .into_iter()
+            .map(|input| self.resolve(&input.previous_output()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ResolvedTransaction {
+            transaction: tx.clone(),
+            resolved_cell_deps,
+            resolved_inputs,
+            resolved_dep_groups,
+        })
+    }
+
+    // Only the type script groups of the oracle are run, the locks are left to the chain
+    pub fn verify(
+        &self,
+        tx: &TransactionView,
+        index_state_type_script: &Script,
+        info_type_script: &Script,
+    ) -> Result<Vec<ScriptCycles>, VerifyError> {
+        Ok(vec![
+            self.verify_script(
+                tx,
+                "index-state-type",
+                index_state_type_script,
+                INDEX_STATE_TYPE_ERRORS,
+            )?,
+            self.verify_script(tx, "info-type", info_type_script, INFO_TYPE_ERRORS)?,
+        ])
+    }
+
+    // Runs the type script group of the script, whose exit codes are named by the errors
+    pub fn verify_script(
+        &self,
+        tx: &TransactionView,
+        name: &'static str,
+        script: &Script,
+        errors: &[(i8, &'static str)],
+    ) -> Result<ScriptCycles, VerifyError> {
+        let resolved_tx = self.resolve_tx(tx)?;
+        let data_loader = HeaderLoader(&self.headers);
+        TransactionScriptsVerifier::new(&resolved_tx, &data_loader)
+            .verify_single(
+                ScriptGroupType::Type,
+                &script.calc_script_hash(),
+                self.max_cycles,
+            )
+            .map(|cycles| ScriptCycles { name, cycles })
+            .map_err(|err| script_error(name, errors, err))
+    }
+}
+
+// The resolved cells carry their data, so only the headers of the header deps are loaded
+struct HeaderLoader<'a>(&'a [HeaderView]);
+
+impl<'a> CellDataProvider for HeaderLoader<'a> {
+    fn get_cell_data(&self, _out_point: &OutPoint) -> Option<(Bytes, Byte32)> {
+        None
+    }
+}
+
+impl<'a> HeaderProvider for HeaderLoader<'a> {
+    fn get_header(&self, hash: &Byte32) -> Option<HeaderView> {
+        self.0.iter().find(|header| &header.hash() == hash).cloned()
+    }
+}
+
+fn script_error(
+    name: &'static str,
+    errors: &[(i8, &'static str)],
+    err: ScriptError,
+) -> VerifyError {
+    let code = validation_failure_code(&err);
+    VerifyError::Script {
+        name,
+        code,
+        error: code.and_then(|code| error_name(errors, code)),
+        message: err.to_string(),
+    }
+}
+
+// The exit code of a script group which failed by returning an error
+pub fn validation_failure_code(err: &ScriptError) -> Option<i8> {
+    match err {
+        ScriptError::ValidationFailure(code) => Some(*code),
+        _ => None,
+    }
+}